@@ -0,0 +1,138 @@
+use crate::{Result, UsbBootHutError};
+use fatfs::{FileSystem, FormatVolumeOptions, FsOptions};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Builds a FAT32 EFI System Partition by writing straight to the
+/// partition device/file with the `fatfs` crate: no `mount`/`umount` and
+/// no root mount privileges, the same approach offline disk-image builders
+/// use to lay down an ESP. Chain `mkfs()` (or skip it to add files to an
+/// already-formatted ESP), `add_file()`/`add_dir_recursive()` for each
+/// bootloader binary or generated config, then `finish()`.
+///
+/// `std::fs::File` implements `Read`/`Write`/`Seek` through `&File` as well
+/// as by value, so `fatfs::FileSystem` can borrow `self.file` for the
+/// duration of each call without the builder having to hold one open
+/// across the whole chain.
+pub struct EspBuilder {
+    part_path: PathBuf,
+    file: File,
+}
+
+impl EspBuilder {
+    pub fn new(part_path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(part_path)
+            .map_err(|e| UsbBootHutError::Bootloader(
+                format!("Failed to open ESP partition {}: {}", part_path.display(), e)
+            ))?;
+
+        Ok(Self { part_path: part_path.to_path_buf(), file })
+    }
+
+    /// Formats the partition FAT32 (`fatfs::format_volume`) and lays down
+    /// the `/EFI/BOOT` directory tree every UEFI firmware expects to find
+    /// the removable-media bootloader in.
+    pub fn mkfs(self) -> Result<Self> {
+        fatfs::format_volume(&self.file, FormatVolumeOptions::new().volume_label(*b"USB_ESP    "))
+            .map_err(|e| UsbBootHutError::Bootloader(
+                format!("Failed to format ESP {} as FAT32: {}", self.part_path.display(), e)
+            ))?;
+
+        let fs = self.open_fs()?;
+        let efi_dir = self.ensure_dir(&fs.root_dir(), "EFI")?;
+        self.ensure_dir(&efi_dir, "BOOT")?;
+
+        Ok(self)
+    }
+
+    /// Writes `bytes` to `path_in_fs` (e.g. `"EFI/BOOT/BOOTX64.EFI"`),
+    /// creating any missing parent directories first.
+    pub fn add_file(self, path_in_fs: &str, bytes: &[u8]) -> Result<Self> {
+        let fs = self.open_fs()?;
+
+        let mut components: Vec<&str> = path_in_fs.split('/').filter(|c| !c.is_empty()).collect();
+        let file_name = components.pop().ok_or_else(|| UsbBootHutError::Bootloader(
+            format!("Cannot write to empty ESP path")
+        ))?;
+
+        let mut dir = fs.root_dir();
+        for component in components {
+            dir = self.ensure_dir(&dir, component)?;
+        }
+
+        let mut file = dir.create_file(file_name)
+            .map_err(|e| UsbBootHutError::Bootloader(
+                format!("Failed to create {} on ESP: {}", path_in_fs, e)
+            ))?;
+        file.truncate()
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to truncate {}: {}", path_in_fs, e)))?;
+        file.write_all(bytes)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write {}: {}", path_in_fs, e)))?;
+
+        Ok(self)
+    }
+
+    /// Streams every regular file under `src_dir` into the ESP at the same
+    /// relative path. Lets callers (e.g. `GrubInstaller`) assemble the ESP
+    /// contents in an ordinary scratch directory with whatever tools they
+    /// need (`grub-install` included), then adopt the result onto the real
+    /// partition in one pure-Rust pass with no mount of `src_dir` or the
+    /// partition involved.
+    pub fn add_dir_recursive(mut self, src_dir: &Path) -> Result<Self> {
+        for entry in WalkDir::new(src_dir) {
+            let entry = entry.map_err(|e| UsbBootHutError::Bootloader(
+                format!("Failed to walk {}: {}", src_dir.display(), e)
+            ))?;
+
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(src_dir).map_err(|e| UsbBootHutError::Bootloader(
+                format!("Failed to compute relative path under {}: {}", src_dir.display(), e)
+            ))?;
+            let path_in_fs = relative.to_string_lossy().replace('\\', "/");
+
+            let bytes = std::fs::read(entry.path())
+                .map_err(|e| UsbBootHutError::Bootloader(
+                    format!("Failed to read {}: {}", entry.path().display(), e)
+                ))?;
+
+            self = self.add_file(&path_in_fs, &bytes)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Flushes the FAT filesystem's pending writes to the partition.
+    pub fn finish(self) -> Result<()> {
+        self.file.sync_all()
+            .map_err(|e| UsbBootHutError::Bootloader(
+                format!("Failed to sync ESP {}: {}", self.part_path.display(), e)
+            ))
+    }
+
+    fn open_fs(&self) -> Result<FileSystem<&File>> {
+        FileSystem::new(&self.file, FsOptions::new())
+            .map_err(|e| UsbBootHutError::Bootloader(
+                format!("Failed to open ESP {}: {}", self.part_path.display(), e)
+            ))
+    }
+
+    /// `fatfs::Dir::create_dir` errors if the directory already exists, but
+    /// `mkfs()` and `add_file()` both need "create if missing" semantics
+    /// (e.g. re-running `add_dir_recursive` for a second bootloader entry).
+    fn ensure_dir<'fs>(&self, parent: &fatfs::Dir<'fs, &'fs File>, name: &str) -> Result<fatfs::Dir<'fs, &'fs File>> {
+        match parent.open_dir(name) {
+            Ok(dir) => Ok(dir),
+            Err(_) => parent.create_dir(name).map_err(|e| UsbBootHutError::Bootloader(
+                format!("Failed to create /{} on ESP: {}", name, e)
+            )),
+        }
+    }
+}
@@ -1,14 +1,76 @@
+pub mod syslinux;
+pub mod esp;
+
+pub use syslinux::SyslinuxInstaller;
+pub use esp::EspBuilder;
+
 use crate::{Result, UsbBootHutError};
+use crate::utils::atomic_write;
 use std::path::{Path, PathBuf};
 use std::fs::{self, File};
 use std::io::Write;
 use std::process::Command;
+use regex::Regex;
 use tempfile::TempDir;
 
+/// A bootloader backend that can be installed onto a device's ESP/boot
+/// partitions and then have per-ISO menu entries added or removed.
+/// Implemented by `GrubInstaller` (EFI, loopback-mounted ISOs) and
+/// `SyslinuxInstaller` (legacy BIOS, via MBR boot code).
+pub trait Bootloader {
+    /// `boot_partition` is whatever the caller can currently write a
+    /// filesystem onto -- the raw partition, or (when `--encrypt-boot` is
+    /// set) the already-opened `/dev/mapper/...` device for it. `boot_crypto`
+    /// carries the LUKS UUID to unlock at power-on; `Some` only makes sense
+    /// for backends (currently just `GrubInstaller`) that can emit a
+    /// `cryptomount` preamble.
+    fn install(&self, esp_partition: &Path, boot_partition: &Path, boot_crypto: Option<&BootCrypto>) -> Result<()>;
+    fn add_entry(&self, boot_mount: &Path, iso_name: &str, iso_path: &str, boot_params: &BootParams) -> Result<()>;
+    fn remove_entry(&self, boot_mount: &Path, iso_name: &str) -> Result<()>;
+}
+
+/// The LUKS UUID of an encrypted `/boot`, so `GrubInstaller` can embed a
+/// `cryptomount -u <uuid>` preamble in `grub.cfg` that unlocks it at
+/// power-on before GRUB tries to read anything else off it.
+pub struct BootCrypto {
+    pub luks_uuid: String,
+}
+
+/// Which `Bootloader` backend to install; selectable via `--bootloader` at format time.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum BootloaderKind {
+    /// GRUB2 with EFI chainloading and ISO loopback mounting.
+    Grub,
+    /// Syslinux/EXTLINUX with MBR boot code, for legacy BIOS hardware.
+    Syslinux,
+}
+
+/// Builds the requested `Bootloader` backend for `device_path`.
+pub fn create_bootloader(kind: BootloaderKind, device_path: &Path) -> Box<dyn Bootloader> {
+    match kind {
+        BootloaderKind::Grub => Box::new(GrubInstaller::new(device_path)),
+        BootloaderKind::Syslinux => Box::new(SyslinuxInstaller::new(device_path)),
+    }
+}
+
 pub struct GrubInstaller {
     device_path: PathBuf,
 }
 
+impl Bootloader for GrubInstaller {
+    fn install(&self, esp_partition: &Path, boot_partition: &Path, boot_crypto: Option<&BootCrypto>) -> Result<()> {
+        GrubInstaller::install(self, esp_partition, boot_partition, boot_crypto)
+    }
+
+    fn add_entry(&self, boot_mount: &Path, iso_name: &str, iso_path: &str, boot_params: &BootParams) -> Result<()> {
+        GrubConfigManager::new(boot_mount).add_iso_entry(iso_name, iso_path, boot_params)
+    }
+
+    fn remove_entry(&self, boot_mount: &Path, iso_name: &str) -> Result<()> {
+        GrubConfigManager::new(boot_mount).remove_iso_entry(iso_name)
+    }
+}
+
 impl GrubInstaller {
     pub fn new(device_path: &Path) -> Self {
         Self {
@@ -16,45 +78,55 @@ impl GrubInstaller {
         }
     }
     
-    pub fn install(&self, esp_partition: &Path, boot_partition: &Path) -> Result<()> {
-        // Create temporary mount points
+    /// Installs GRUB onto `boot_partition` (still mounted conventionally --
+    /// `grub-install` needs a real ext4 filesystem to lay down `core.img`
+    /// and `grub.cfg` into; if `/boot` is encrypted, `boot_partition` is the
+    /// already-opened `/dev/mapper/...` device, not the raw LUKS partition)
+    /// and `esp_partition` (populated with no mount at all: `grub-install`
+    /// is pointed at a plain scratch directory for its `--efi-directory`,
+    /// and the resulting `EFI/BOOT` tree is then adopted onto the real ESP
+    /// in one pure-Rust pass via `EspBuilder`).
+    pub fn install(&self, esp_partition: &Path, boot_partition: &Path, boot_crypto: Option<&BootCrypto>) -> Result<()> {
         let temp_dir = TempDir::new()
             .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to create temp dir: {}", e)))?;
-            
-        let esp_mount = temp_dir.path().join("esp");
+
+        let esp_scratch = temp_dir.path().join("esp");
         let boot_mount = temp_dir.path().join("boot");
-        
-        fs::create_dir_all(&esp_mount)
-            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to create ESP mount: {}", e)))?;
+
+        fs::create_dir_all(&esp_scratch)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to create ESP scratch dir: {}", e)))?;
         fs::create_dir_all(&boot_mount)
             .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to create boot mount: {}", e)))?;
-        
-        // Mount partitions
-        self.mount_partition(esp_partition, &esp_mount)?;
-        let esp_mounted = true;
-        
+
         self.mount_partition(boot_partition, &boot_mount)?;
         let boot_mounted = true;
-        
-        // Install GRUB
-        let result = self.install_grub_files(&esp_mount, &boot_mount);
-        
-        // Always unmount, even if installation failed
+
+        let result = self.install_grub_files(&esp_scratch, &boot_mount, boot_crypto)
+            .and_then(|_| self.write_esp(esp_partition, &esp_scratch));
+
         if boot_mounted {
             let _ = self.unmount_partition(&boot_mount);
         }
-        if esp_mounted {
-            let _ = self.unmount_partition(&esp_mount);
-        }
-        
+
         result?;
-        
+
         Ok(())
     }
+
+    /// Formats `esp_partition` FAT32 and streams the `EFI/BOOT` tree
+    /// `install_grub_files` assembled under `esp_scratch` onto it, via
+    /// `EspBuilder`. Replaces the old mount-the-real-partition-and-copy
+    /// step with one that needs no mount privileges.
+    fn write_esp(&self, esp_partition: &Path, esp_scratch: &Path) -> Result<()> {
+        EspBuilder::new(esp_partition)?
+            .mkfs()?
+            .add_dir_recursive(esp_scratch)?
+            .finish()
+    }
     
-    fn install_grub_files(&self, esp_mount: &Path, boot_mount: &Path) -> Result<()> {
+    fn install_grub_files(&self, esp_scratch: &Path, boot_mount: &Path, boot_crypto: Option<&BootCrypto>) -> Result<()> {
         // Create necessary directories
-        let efi_dir = esp_mount.join("EFI");
+        let efi_dir = esp_scratch.join("EFI");
         let boot_dir = efi_dir.join("BOOT");
         fs::create_dir_all(&boot_dir)
             .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to create EFI dirs: {}", e)))?;
@@ -69,16 +141,27 @@ impl GrubInstaller {
         #[cfg(target_arch = "aarch64")]
         let grub_target = "arm64-efi";
         
-        let output = Command::new("grub-install")
-            .args([
-                "--target", grub_target,
-                "--efi-directory", esp_mount.to_str().unwrap(),
-                "--boot-directory", boot_mount.to_str().unwrap(),
-                "--removable",
-                "--recheck",
-                self.device_path.to_str().unwrap(),
-            ])
-            .output()
+        let mut grub_install = Command::new("grub-install");
+        grub_install.args([
+            "--target", grub_target,
+            "--efi-directory", esp_scratch.to_str().unwrap(),
+            "--boot-directory", boot_mount.to_str().unwrap(),
+            "--removable",
+            "--recheck",
+            self.device_path.to_str().unwrap(),
+        ]);
+
+        // `grub-install` only reads `GRUB_ENABLE_CRYPTODISK` from its own
+        // environment, not from `grub.cfg` -- it needs it at install time to
+        // detect the LUKS device underneath and bake cryptodisk/luks/luks2
+        // support into the core image, which is what lets the boot-time
+        // stub even reach the `grub.cfg` that carries the `cryptomount`
+        // preamble `create_grub_config` writes below.
+        if boot_crypto.is_some() {
+            grub_install.env("GRUB_ENABLE_CRYPTODISK", "y");
+        }
+
+        let output = grub_install.output()
             .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to run grub-install: {}", e)))?;
             
         if !output.status.success() {
@@ -88,20 +171,55 @@ impl GrubInstaller {
         }
         
         // Create initial grub.cfg
-        self.create_grub_config(&grub_dir)?;
-        
+        self.create_grub_config(&grub_dir, boot_crypto)?;
+
         // Install theme
         self.install_theme(&grub_dir)?;
-        
+
+        // Install the wimboot EFI helper for direct Windows ISO booting, if available.
+        self.install_wimboot(esp_scratch)?;
+
+        Ok(())
+    }
+
+    /// Copies the `wimboot` EFI helper (ipxe.org/wimboot) onto the ESP root
+    /// as `wimboot.efi`. The Windows menu entries `add_iso_entry` writes use
+    /// `insmod wimboot` and do not reference this file. Best-effort:
+    /// a missing wimboot package is skipped and does not fail the rest of
+    /// the install.
+    fn install_wimboot(&self, esp_scratch: &Path) -> Result<()> {
+        for candidate in ["/usr/lib/wimboot/wimboot.efi", "/usr/share/wimboot/wimboot.efi"] {
+            let src = Path::new(candidate);
+            if src.exists() {
+                fs::copy(src, esp_scratch.join("wimboot.efi"))
+                    .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to copy wimboot.efi: {}", e)))?;
+                break;
+            }
+        }
+
         Ok(())
     }
     
-    fn create_grub_config(&self, grub_dir: &Path) -> Result<()> {
+    fn create_grub_config(&self, grub_dir: &Path, boot_crypto: Option<&BootCrypto>) -> Result<()> {
         let config_path = grub_dir.join("grub.cfg");
         let mut config_file = File::create(&config_path)
             .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to create grub.cfg: {}", e)))?;
-            
-        let config = r#"# USB Boot Hut GRUB Configuration
+
+        // Must run before anything else touches the boot partition: loads
+        // the modules needed to decrypt it and unlocks it by LUKS UUID.
+        // `GRUB_ENABLE_CRYPTODISK` itself isn't `grub.cfg` script -- it's an
+        // environment variable `install_grub_files` sets for `grub-install`
+        // so the boot-time stub that locates this file is built with
+        // cryptodisk support in the first place.
+        let crypto_preamble = match boot_crypto {
+            Some(crypto) => format!(
+                "insmod cryptodisk\ninsmod luks\ninsmod luks2\ninsmod gcm\ncryptomount -u {}\n\n",
+                crypto.luks_uuid.replace('-', "")
+            ),
+            None => String::new(),
+        };
+
+        let template = r#"# USB Boot Hut GRUB Configuration
 set timeout=10
 set default=0
 
@@ -119,14 +237,16 @@ set theme=/grub/themes/usb-boot-hut/theme.txt
 set menu_color_normal=white/black
 set menu_color_highlight=black/white
 
-# Boot entries will be dynamically added here
-# Example entry:
+# --- usb-boot-hut: managed ISO entries start ---
+# Entries in this block are maintained by `usb-boot-hut add`/`remove` and
+# `update-grub --regenerate`; edits here may be overwritten. Example entry:
 # menuentry "Ubuntu 22.04 Live" {
 #     set isofile="/isos/ubuntu-22.04-desktop-amd64.iso"
 #     loopback loop $isofile
 #     linux (loop)/casper/vmlinuz boot=casper iso-scan/filename=$isofile quiet splash
 #     initrd (loop)/casper/initrd
 # }
+# --- usb-boot-hut: managed ISO entries end ---
 
 menuentry "System Settings" {
     insmod part_gpt
@@ -142,7 +262,8 @@ menuentry "Shutdown" {
     halt
 }
 "#;
-        
+        let config = format!("{}{}", crypto_preamble, template);
+
         config_file.write_all(config.as_bytes())
             .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write grub.cfg: {}", e)))?;
             
@@ -245,6 +366,11 @@ terminal-box: "terminal_box_*.png"
     }
 }
 
+/// Delimits the block of `grub.cfg` that `add_iso_entry`/`remove_iso_entry`
+/// and `regenerate` own; hand-written entries outside it are left alone.
+const MANAGED_ENTRIES_START: &str = "# --- usb-boot-hut: managed ISO entries start ---";
+const MANAGED_ENTRIES_END: &str = "# --- usb-boot-hut: managed ISO entries end ---";
+
 pub struct GrubConfigManager {
     config_path: PathBuf,
 }
@@ -255,11 +381,11 @@ impl GrubConfigManager {
             config_path: boot_mount.join("grub/grub.cfg"),
         }
     }
-    
+
     pub fn add_iso_entry(&self, iso_name: &str, iso_path: &str, boot_params: &BootParams) -> Result<()> {
         let mut config = fs::read_to_string(&self.config_path)
             .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to read grub.cfg: {}", e)))?;
-            
+
         // Check if entry already exists
         if config.contains(&format!("menuentry \"{}\"", iso_name)) {
             return Ok(()); // Already exists
@@ -298,16 +424,19 @@ menuentry "{}" {{
 "#, iso_name, iso_path)
             },
             BootParams::Windows { version: _ } => {
-                // Windows requires chainloading
+                // Windows install media isn't loopback-bootable like a Linux live
+                // ISO; wimboot extracts bootmgr/bcd/boot.sdi/boot.wim from the
+                // loopback-mounted ISO and hands them to the Windows boot manager.
                 format!(r#"
 menuentry "{}" {{
-    # Windows ISOs require special handling
-    # This is a placeholder - actual implementation would use wimboot
-    echo "Windows direct boot not yet implemented"
-    echo "Please use a Windows-to-Go installation instead"
-    sleep 5
+    set isofile="{}"
+    insmod part_gpt
+    insmod fat
+    loopback loop $isofile
+    insmod wimboot
+    wimboot @:bootmgr:(loop)/bootmgr @:bcd:(loop)/boot/bcd @:boot.sdi:(loop)/boot/boot.sdi @:boot.wim:(loop)/sources/boot.wim
 }}
-"#, iso_name)
+"#, iso_name, iso_path)
             },
             BootParams::Custom { kernel, initrd, params } => {
                 format!(r#"
@@ -321,16 +450,16 @@ menuentry "{}" {{
             },
         };
         
-        // Insert before the System Settings menu entry
-        let insert_pos = config.find("menuentry \"System Settings\"")
+        // Insert at the end of the managed block, just before the control entries.
+        let insert_pos = config.find(MANAGED_ENTRIES_END)
             .ok_or_else(|| UsbBootHutError::Bootloader("Invalid grub.cfg format".to_string()))?;
-            
+
         config.insert_str(insert_pos, &entry);
-        
+
         // Write back
-        fs::write(&self.config_path, config)
+        atomic_write(&self.config_path, config.as_bytes())
             .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write grub.cfg: {}", e)))?;
-            
+
         Ok(())
     }
     
@@ -359,9 +488,208 @@ menuentry "{}" {{
             }
         }
         
-        fs::write(&self.config_path, new_config)
+        atomic_write(&self.config_path, new_config.as_bytes())
             .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write grub.cfg: {}", e)))?;
-            
+
+        Ok(())
+    }
+
+    /// Rebuilds the managed ISO entries from scratch by scanning `iso_dir`,
+    /// rather than trusting whatever is already in `grub.cfg`. Each `*.iso`
+    /// (skipping in-progress `.part` downloads) is validated and probed via
+    /// `IsoProber::detect` to pick its kernel/initrd, and every entry gets a
+    /// `search --fs-uuid` guard keyed on `boot_uuid` so it finds the USB
+    /// drive regardless of BIOS drive ordering. Unrecognized images still
+    /// get an entry (a chainload attempt that prints why it can't boot)
+    /// rather than being silently dropped. Returns the number of entries written.
+    pub fn regenerate(&self, boot_uuid: &str, iso_dir: &Path) -> Result<usize> {
+        use crate::iso::{IsoMetadata, IsoProber, IsoValidator};
+
+        let mut iso_files: Vec<PathBuf> = fs::read_dir(iso_dir)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to read ISO directory: {}", e)))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension().map(|ext| ext.eq_ignore_ascii_case("iso")).unwrap_or(false)
+            })
+            .collect();
+        iso_files.sort();
+
+        let mut rendered = String::new();
+        let mut written = 0;
+        for iso_path in &iso_files {
+            let iso_info = match IsoValidator::validate_iso(iso_path) {
+                Ok(info) => info,
+                Err(e) => {
+                    println!("⚠️  Skipping {} ({})", iso_path.display(), e);
+                    continue;
+                }
+            };
+
+            let detected = IsoProber::detect(iso_path, &iso_info.volume_id)
+                .unwrap_or_else(|_| crate::iso::DetectedBoot {
+                    boot_params: BootParams::Custom {
+                        kernel: String::new(),
+                        initrd: String::new(),
+                        params: "echo 'usb-boot-hut: could not detect a bootable kernel on this ISO'".to_string(),
+                    },
+                    kernel: String::new(),
+                    initrd: String::new(),
+                });
+
+            let filename = iso_path.file_name().and_then(|f| f.to_str()).unwrap_or_default().to_string();
+            let display_name = IsoMetadata::new(filename.clone(), iso_info.iso_type.clone(), iso_info.size, String::new())
+                .display_name;
+            let iso_rel_path = format!("/isos/{}", filename);
+
+            rendered.push_str(&Self::render_entry(boot_uuid, &display_name, &iso_rel_path, &detected));
+            written += 1;
+        }
+
+        let config = fs::read_to_string(&self.config_path)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to read grub.cfg: {}", e)))?;
+
+        let start = config.find(MANAGED_ENTRIES_START)
+            .ok_or_else(|| UsbBootHutError::Bootloader("Invalid grub.cfg format".to_string()))?
+            + MANAGED_ENTRIES_START.len();
+        let end = config.find(MANAGED_ENTRIES_END)
+            .ok_or_else(|| UsbBootHutError::Bootloader("Invalid grub.cfg format".to_string()))?;
+
+        let mut new_config = String::with_capacity(config.len() + rendered.len());
+        new_config.push_str(&config[..start]);
+        new_config.push('\n');
+        new_config.push_str(&rendered);
+        new_config.push_str(&config[end..]);
+
+        atomic_write(&self.config_path, new_config.as_bytes())
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write grub.cfg: {}", e)))?;
+
+        Ok(written)
+    }
+
+    /// Renders a single `regenerate`d menu entry. Unlike `add_iso_entry`'s
+    /// templates (which assume GRUB already booted from this partition),
+    /// entries here always `search --fs-uuid` for `boot_uuid` first.
+    fn render_entry(boot_uuid: &str, iso_name: &str, iso_path: &str, detected: &crate::iso::DetectedBoot) -> String {
+        let search_line = format!("    search --no-floppy --fs-uuid --set=root {}\n", boot_uuid);
+
+        if let BootParams::Windows { .. } = &detected.boot_params {
+            return format!(
+                "\nmenuentry \"{name}\" {{\n{search}    set isofile=\"{path}\"\n    insmod part_gpt\n    insmod fat\n    loopback loop $isofile\n    insmod wimboot\n    wimboot @:bootmgr:(loop)/bootmgr @:bcd:(loop)/boot/bcd @:boot.sdi:(loop)/boot/boot.sdi @:boot.wim:(loop)/sources/boot.wim\n}}\n",
+                name = iso_name, search = search_line, path = iso_path,
+            );
+        }
+
+        if detected.kernel.is_empty() {
+            // Nothing recognisable inside the ISO: a generic chainload
+            // attempt that at least explains itself instead of vanishing.
+            let command = match &detected.boot_params {
+                BootParams::Custom { params, .. } => params.clone(),
+                _ => "echo 'usb-boot-hut: could not detect a bootable kernel on this ISO'".to_string(),
+            };
+            return format!(
+                "\nmenuentry \"{name}\" {{\n{search}    set isofile=\"{path}\"\n    {command}\n    sleep 3\n    loopback loop $isofile\n    chainloader (loop)/EFI/BOOT/BOOTX64.EFI\n}}\n",
+                name = iso_name, search = search_line, path = iso_path, command = command,
+            );
+        }
+
+        let params = match &detected.boot_params {
+            BootParams::Ubuntu { .. } => "boot=casper iso-scan/filename=$isofile quiet splash".to_string(),
+            BootParams::Debian { .. } => "boot=live findiso=$isofile quiet splash".to_string(),
+            BootParams::Arch => "img_dev=/dev/disk/by-label/USB_DATA img_loop=$isofile".to_string(),
+            BootParams::Custom { params, .. } => params.clone(),
+            BootParams::Windows { .. } => unreachable!("Windows is handled above"),
+        };
+
+        format!(
+            "\nmenuentry \"{name}\" {{\n{search}    set isofile=\"{path}\"\n    loopback loop $isofile\n    linux (loop){kernel} {params}\n    initrd (loop){initrd}\n}}\n",
+            name = iso_name, search = search_line, path = iso_path,
+            kernel = detected.kernel, params = params, initrd = detected.initrd,
+        )
+    }
+
+    /// Appends `option` to the `linux (loop)...` command line of every ISO
+    /// menu entry, skipping the System Settings/Reboot/Shutdown control
+    /// entries. A no-op for entries that already carry the option.
+    pub fn add_boot_option(&self, option: &str) -> Result<()> {
+        self.rewrite_linux_lines(|tokens| {
+            if !tokens.iter().any(|t| *t == option) {
+                tokens.push(option.to_string());
+            }
+        })
+    }
+
+    /// Strips any kernel command-line token matching `pattern` from every
+    /// ISO menu entry's `linux (loop)...` line, leaving the kernel path and
+    /// `isofile`/`loopback` lines untouched.
+    pub fn remove_boot_option(&self, pattern: &str) -> Result<()> {
+        let re = Regex::new(pattern)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Invalid boot option regex: {}", e)))?;
+
+        self.rewrite_linux_lines(|tokens| {
+            tokens.retain(|t| !re.is_match(t));
+        })
+    }
+
+    /// Rewrites `set default=...` to boot straight into the menu entry named
+    /// `name` by default. Returns an error if no such entry exists.
+    pub fn set_default_entry(&self, name: &str) -> Result<()> {
+        let config = fs::read_to_string(&self.config_path)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to read grub.cfg: {}", e)))?;
+
+        if !config.contains(&format!("menuentry \"{}\"", name)) {
+            return Err(UsbBootHutError::Bootloader(
+                format!("No menu entry named \"{}\"", name)
+            ));
+        }
+
+        let mut new_config = String::new();
+        for line in config.lines() {
+            if line.trim_start().starts_with("set default=") {
+                new_config.push_str(&format!("set default=\"{}\"", name));
+            } else {
+                new_config.push_str(line);
+            }
+            new_config.push('\n');
+        }
+
+        atomic_write(&self.config_path, new_config.as_bytes())
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write grub.cfg: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Walks each `menuentry` block in `grub.cfg` and hands the space-split
+    /// kernel command-line tokens of its `linux (loop)...` line to `edit`,
+    /// rejoining the result. The System Settings/Reboot/Shutdown control
+    /// entries have no such line and are passed through unchanged; the
+    /// `(loop)/path/to/kernel` token itself is never touched.
+    fn rewrite_linux_lines(&self, mut edit: impl FnMut(&mut Vec<String>)) -> Result<()> {
+        let config = fs::read_to_string(&self.config_path)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to read grub.cfg: {}", e)))?;
+
+        let mut new_config = String::new();
+        for line in config.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("linux (loop)") {
+                let indent = &line[..line.len() - trimmed.len()];
+                let mut tokens: Vec<String> = trimmed.split_whitespace().map(String::from).collect();
+                let head: Vec<String> = tokens.drain(..2.min(tokens.len())).collect();
+                edit(&mut tokens);
+
+                let mut rewritten = head;
+                rewritten.extend(tokens);
+                new_config.push_str(indent);
+                new_config.push_str(&rewritten.join(" "));
+            } else {
+                new_config.push_str(line);
+            }
+            new_config.push('\n');
+        }
+
+        atomic_write(&self.config_path, new_config.as_bytes())
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write grub.cfg: {}", e)))?;
+
         Ok(())
     }
 }
@@ -373,4 +701,38 @@ pub enum BootParams {
     Arch,
     Windows { version: String },
     Custom { kernel: String, initrd: String, params: String },
+}
+
+/// Looks up the filesystem UUID of whatever device is mounted at
+/// `mount_point`, for GRUB's `search --fs-uuid` in regenerated entries.
+/// Reads `/proc/mounts` rather than `findmnt`/`df` since that's already the
+/// repo's convention (see `IsoManager::data_partition_is_fat32`).
+pub fn fs_uuid(mount_point: &Path) -> Result<String> {
+    let mounts = fs::read_to_string("/proc/mounts")
+        .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to read /proc/mounts: {}", e)))?;
+    let mount_str = mount_point.to_string_lossy();
+
+    let device = mounts.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?;
+            let mount = fields.next()?;
+            Some((device, mount))
+        })
+        .find(|(_, mount)| *mount == mount_str)
+        .map(|(device, _)| device.to_string())
+        .ok_or_else(|| UsbBootHutError::Bootloader(format!("{} is not a mount point", mount_point.display())))?;
+
+    let output = Command::new("blkid")
+        .args(["-s", "UUID", "-o", "value", &device])
+        .output()
+        .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to run blkid: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(UsbBootHutError::Bootloader(
+            format!("blkid found no UUID for {}", device)
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
\ No newline at end of file
@@ -0,0 +1,280 @@
+use crate::{Result, UsbBootHutError};
+use crate::bootloader::{Bootloader, BootCrypto, BootParams};
+use crate::utils::atomic_write;
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Installs Syslinux/EXTLINUX the way grml2usb does by default: copy the
+/// library modules onto the boot partition's filesystem, run `extlinux
+/// --install` to lay down the boot sector, mark the partition active, and
+/// write syslinux's MBR boot code to the whole device. Used for legacy
+/// BIOS hardware where GRUB's loopback chainloading is unreliable.
+pub struct SyslinuxInstaller {
+    device_path: PathBuf,
+}
+
+impl SyslinuxInstaller {
+    pub fn new(device_path: &Path) -> Self {
+        Self {
+            device_path: device_path.to_path_buf(),
+        }
+    }
+
+    pub fn install(&self, _esp_partition: &Path, boot_partition: &Path) -> Result<()> {
+        let temp_dir = TempDir::new()
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to create temp dir: {}", e)))?;
+        let boot_mount = temp_dir.path().join("boot");
+        fs::create_dir_all(&boot_mount)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to create boot mount: {}", e)))?;
+
+        self.mount_partition(boot_partition, &boot_mount)?;
+        let result = self.install_syslinux_files(&boot_mount);
+        let _ = self.unmount_partition(&boot_mount);
+        result?;
+
+        self.mark_partition_active()?;
+        self.install_mbr_code()?;
+
+        Ok(())
+    }
+
+    fn install_syslinux_files(&self, boot_mount: &Path) -> Result<()> {
+        // Copy the library modules (menu driver, com32 runtime, etc.) onto the
+        // partition, matching the set grml2usb ships alongside its syslinux bundle.
+        let modules_dir = Path::new("/usr/lib/syslinux/modules/bios");
+        for module in ["ldlinux.c32", "libcom32.c32", "libutil.c32", "menu.c32", "chain.c32", "reboot.c32", "poweroff.c32"] {
+            let src = modules_dir.join(module);
+            if src.exists() {
+                fs::copy(&src, boot_mount.join(module))
+                    .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to copy {}: {}", module, e)))?;
+            }
+        }
+
+        self.create_syslinux_config(boot_mount)?;
+
+        // extlinux writes its boot sector and ldlinux.sys directly onto the
+        // already-mounted filesystem (the ext4-equivalent of grub-install's
+        // --boot-directory step).
+        let output = Command::new("extlinux")
+            .args(["--install", boot_mount.to_str().unwrap()])
+            .output()
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to run extlinux: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::Bootloader(
+                format!("extlinux --install failed: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn create_syslinux_config(&self, boot_mount: &Path) -> Result<()> {
+        let config_path = boot_mount.join("syslinux.cfg");
+        let config = r#"# USB Boot Hut Syslinux Configuration
+DEFAULT menu.c32
+PROMPT 0
+TIMEOUT 100
+MENU TITLE USB Boot Hut
+
+# Boot entries will be dynamically added here
+
+LABEL reboot
+    MENU LABEL Reboot
+    COM32 reboot.c32
+
+LABEL poweroff
+    MENU LABEL Shutdown
+    COM32 poweroff.c32
+"#;
+
+        atomic_write(&config_path, config.as_bytes())
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write syslinux.cfg: {}", e)))?;
+
+        Ok(())
+    }
+
+    fn mark_partition_active(&self) -> Result<()> {
+        // Boot partition is always partition 2 in our ESP/boot/data layout.
+        let output = Command::new("sfdisk")
+            .args(["--activate", self.device_path.to_str().unwrap(), "2"])
+            .output()
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to run sfdisk: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::Bootloader(
+                format!("Failed to mark partition active: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn install_mbr_code(&self) -> Result<()> {
+        let output = Command::new("dd")
+            .args([
+                "if=/usr/lib/syslinux/mbr/mbr.bin",
+                &format!("of={}", self.device_path.display()),
+                "bs=440",
+                "count=1",
+                "conv=notrunc",
+            ])
+            .output()
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write MBR boot code: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::Bootloader(
+                format!("Failed to write MBR boot code: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn mount_partition(&self, partition: &Path, mount_point: &Path) -> Result<()> {
+        let output = Command::new("mount")
+            .args([partition.to_str().unwrap(), mount_point.to_str().unwrap()])
+            .output()
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to mount: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::Bootloader(
+                format!("Mount failed: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn unmount_partition(&self, mount_point: &Path) -> Result<()> {
+        let output = Command::new("umount")
+            .arg(mount_point.to_str().unwrap())
+            .output()
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to unmount: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::Bootloader(
+                format!("Unmount failed: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Bootloader for SyslinuxInstaller {
+    fn install(&self, esp_partition: &Path, boot_partition: &Path, boot_crypto: Option<&BootCrypto>) -> Result<()> {
+        if boot_crypto.is_some() {
+            return Err(UsbBootHutError::Bootloader(
+                "Encrypted /boot requires --bootloader grub; syslinux's MBR boot code can't unlock LUKS".to_string()
+            ));
+        }
+
+        SyslinuxInstaller::install(self, esp_partition, boot_partition)
+    }
+
+    fn add_entry(&self, boot_mount: &Path, iso_name: &str, iso_path: &str, boot_params: &BootParams) -> Result<()> {
+        SyslinuxConfigManager::new(boot_mount).add_iso_entry(iso_name, iso_path, boot_params)
+    }
+
+    fn remove_entry(&self, boot_mount: &Path, iso_name: &str) -> Result<()> {
+        SyslinuxConfigManager::new(boot_mount).remove_iso_entry(iso_name)
+    }
+}
+
+pub struct SyslinuxConfigManager {
+    config_path: PathBuf,
+}
+
+impl SyslinuxConfigManager {
+    pub fn new(boot_mount: &Path) -> Self {
+        Self {
+            config_path: boot_mount.join("syslinux.cfg"),
+        }
+    }
+
+    pub fn add_iso_entry(&self, iso_name: &str, iso_path: &str, boot_params: &BootParams) -> Result<()> {
+        let mut config = fs::read_to_string(&self.config_path)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to read syslinux.cfg: {}", e)))?;
+
+        let label = Self::slugify(iso_name);
+        if config.contains(&format!("LABEL {}", label)) {
+            return Ok(()); // Already exists
+        }
+
+        // Most live ISOs boot fine via chain.c32's iso= trick, which avoids
+        // needing per-distro kernel/initrd paths the way GRUB's loopback does.
+        // A Custom entry still boots a bare kernel/initrd directly.
+        let entry = match boot_params {
+            BootParams::Custom { kernel, initrd, params } => format!(
+                r#"
+LABEL {}
+    MENU LABEL {}
+    LINUX {}
+    INITRD {}
+    APPEND {}
+"#,
+                label, iso_name, kernel, initrd, params
+            ),
+            _ => format!(
+                r#"
+LABEL {}
+    MENU LABEL {}
+    COM32 chain.c32
+    APPEND iso={}
+"#,
+                label, iso_name, iso_path
+            ),
+        };
+
+        let insert_pos = config.find("LABEL reboot")
+            .ok_or_else(|| UsbBootHutError::Bootloader("Invalid syslinux.cfg format".to_string()))?;
+
+        config.insert_str(insert_pos, &entry);
+
+        atomic_write(&self.config_path, config.as_bytes())
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write syslinux.cfg: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub fn remove_iso_entry(&self, iso_name: &str) -> Result<()> {
+        let config = fs::read_to_string(&self.config_path)
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to read syslinux.cfg: {}", e)))?;
+
+        let label = Self::slugify(iso_name);
+        let marker = format!("LABEL {}", label);
+        let mut new_config = String::new();
+        let mut skip = false;
+
+        for line in config.lines() {
+            if line.starts_with(&marker) {
+                skip = true;
+                continue;
+            }
+
+            if skip {
+                if line.trim().is_empty() || line.starts_with(' ') || line.starts_with('\t') {
+                    continue;
+                }
+                skip = false;
+            }
+
+            new_config.push_str(line);
+            new_config.push('\n');
+        }
+
+        atomic_write(&self.config_path, new_config.as_bytes())
+            .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to write syslinux.cfg: {}", e)))?;
+
+        Ok(())
+    }
+
+    fn slugify(name: &str) -> String {
+        name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .collect()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Result, UsbBootHutError};
+use crate::{Result, UsbBootHutError, IoContext};
 use std::path::{Path, PathBuf};
 use std::fs;
 use walkdir::WalkDir;
@@ -117,22 +117,18 @@ impl CleanupEngine {
     }
     
     pub fn load_config(config_path: &Path) -> Result<CleanupConfig> {
-        let content = fs::read_to_string(config_path)
-            .map_err(|e| UsbBootHutError::Config(format!("Failed to read config: {}", e)))?;
-            
+        let content = fs::read_to_string(config_path).io_context("read cleanup config", config_path)?;
+
         toml::from_str(&content)
             .map_err(|e| UsbBootHutError::Config(format!("Failed to parse config: {}", e)))
     }
-    
+
     pub fn save_default_config(config_path: &Path) -> Result<()> {
         let config = CleanupConfig::default();
         let content = toml::to_string_pretty(&config)
             .map_err(|e| UsbBootHutError::Config(format!("Failed to serialize config: {}", e)))?;
-            
-        fs::write(config_path, content)
-            .map_err(|e| UsbBootHutError::Config(format!("Failed to write config: {}", e)))?;
-            
-        Ok(())
+
+        crate::utils::atomic_write(config_path, content.as_bytes())
     }
     
     pub fn clean(&self, target_path: &Path) -> Result<CleanupStats> {
@@ -352,7 +348,7 @@ impl CleanupStats {
     }
 }
 
-fn format_size(bytes: u64) -> String {
+pub(crate) fn format_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     let mut size = bytes as f64;
     let mut unit_idx = 0;
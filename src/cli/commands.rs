@@ -1,6 +1,6 @@
 use crate::{Result, UsbBootHutError};
-use crate::cli::{Cli, Commands, ConfigAction, ListFormat, WipePattern};
-use crate::disk::{enumerate_usb_devices, DriveManager};
+use crate::cli::{Cli, Commands, BackupAction, BootSlotName, ConfigAction, ListFormat, SlotAction, WipePattern};
+use crate::disk::{enumerate_usb_devices, resolve_device_path, DriveManager};
 use crate::cleanup::{CleanupEngine, CleanupConfig};
 use crate::config::ConfigManager;
 use crate::utils::print_banner;
@@ -23,37 +23,55 @@ pub fn run(cli: Cli) -> Result<()> {
     
     // Handle commands
     match cli.command {
-        Commands::Format { device, encrypt, secure_wipe, yes } => {
-            handle_format(&device, encrypt, secure_wipe, yes)
+        Commands::Format { device, encrypt, secure_wipe, yes, require_healthy, bootloader, data_fs, data_size_mb, persistence, persistence_size_mb, key_file, encrypt_boot } => {
+            handle_format(&device, encrypt, secure_wipe, yes, require_healthy, bootloader, data_fs, data_size_mb, persistence.as_deref(), persistence_size_mb, key_file.as_deref(), encrypt_boot)
         },
-        Commands::Unlock { device, mount } => {
-            handle_unlock(&device, mount.as_deref())
+        Commands::Unlock { device, mount, key_file } => {
+            let device = resolve_device_path(&device)?;
+            handle_unlock(&device, mount.as_deref(), key_file.as_deref())
         },
-        Commands::Lock { device } => {
+        Commands::Lock { device, key_file: _ } => {
+            let device = resolve_device_path(&device)?;
             handle_lock(&device)
         },
-        Commands::Add { iso_file, verify, category, tags } => {
-            handle_add(&iso_file, verify.as_deref(), category.as_deref(), tags.as_deref())
+        Commands::Add { device, iso_source, verify, category, tags, channel, signing_key, trusted_keyring } => {
+            handle_add(&device, &iso_source, verify.as_deref(), category.as_deref(), tags.as_deref(), channel, signing_key, trusted_keyring)
         },
-        Commands::Remove { iso_name, yes } => {
-            handle_remove(&iso_name, yes)
+        Commands::Remove { device, iso_name, yes } => {
+            handle_remove(&device, &iso_name, yes)
+        },
+        Commands::Archive { device, iso_name, codec } => {
+            handle_archive(&device, &iso_name, codec)
+        },
+        Commands::Restore { device, iso_name } => {
+            handle_restore(&device, &iso_name)
         },
         Commands::List { device, category, format } => {
+            let device = device.map(|d| resolve_device_path(&d)).transpose()?;
             handle_list(device.as_deref(), category.as_deref(), format)
         },
-        Commands::Verify { device, iso_name } => {
-            handle_verify(&device, iso_name.as_deref())
+        Commands::Verify { device, iso_name, threads, known_good, trusted_keyring, channel } => {
+            let device = resolve_device_path(&device)?;
+            handle_verify(&device, iso_name.as_deref(), threads, known_good.as_deref(), trusted_keyring.as_deref(), &channel)
         },
         Commands::Clean { device, config, dry_run } => {
+            let device = resolve_device_path(&device)?;
             handle_clean(&device, config.as_deref(), dry_run)
         },
+        Commands::Dedupe { device, dry_run, yes } => {
+            handle_dedupe(&device, dry_run, yes)
+        },
+        Commands::Backup { action } => {
+            handle_backup(action)
+        },
         Commands::Config { action } => {
             handle_config(action)
         },
-        Commands::Devices { all, format } => {
-            handle_devices(all, format)
+        Commands::Devices { all, format, wait, wait_timeout } => {
+            handle_devices(all, format, wait, wait_timeout)
         },
         Commands::Status { device } => {
+            let device = resolve_device_path(&device)?;
             handle_status(&device)
         },
         Commands::UpdateGrub { device, regenerate } => {
@@ -65,25 +83,93 @@ pub fn run(cli: Cli) -> Result<()> {
         Commands::Burn { image, device, no_verify, enable_ssh, wifi, yes, eject } => {
             handle_burn(&image, &device, no_verify, enable_ssh, wifi.as_deref(), yes, eject)
         },
+        Commands::CreateImage { output, size_mb, encrypt, encrypt_boot, bootloader, data_fs, data_size_mb, key_file } => {
+            handle_create_image(&output, size_mb, encrypt, encrypt_boot, bootloader, data_fs, data_size_mb, key_file.as_deref())
+        },
+        Commands::Slot { action } => {
+            handle_slot(action)
+        },
     }
 }
 
-fn handle_format(device_path: &Path, encrypt: bool, secure_wipe: bool, skip_confirm: bool) -> Result<()> {
+fn handle_format(
+    device_path: &Path,
+    encrypt: bool,
+    secure_wipe: bool,
+    skip_confirm: bool,
+    require_healthy: bool,
+    bootloader: crate::bootloader::BootloaderKind,
+    data_fs: Option<crate::partition::DataFilesystem>,
+    data_size_mb: Option<u64>,
+    persistence: Option<&str>,
+    persistence_size_mb: Option<u64>,
+    key_file: Option<&Path>,
+    encrypt_boot: bool,
+) -> Result<()> {
+    use crate::disk::check_health_or_abort;
+    use crate::partition::{DataFilesystem, PartitionLayout, PersistenceLayout};
+
+    let app_config = ConfigManager::new()?;
+    let data_filesystem = match data_fs {
+        Some(fs) => fs,
+        None => DataFilesystem::parse(&app_config.get().default_data_filesystem)?,
+    };
+    let layout = PartitionLayout {
+        data_filesystem,
+        data_size_mb,
+        persistence: persistence.map(|label| PersistenceLayout {
+            label: label.to_string(),
+            size_mb: persistence_size_mb.unwrap_or(app_config.get().default_persistence_size_mb),
+        }),
+    };
+    if layout.persistence.is_some() && layout.data_size_mb.is_none() {
+        return Err(UsbBootHutError::Partition(
+            "--data-size-mb must be set when requesting --persistence, so the persistence partition knows how much space is left to claim".to_string()
+        ));
+    }
+
+    if key_file.is_some() && !encrypt {
+        return Err(UsbBootHutError::Encryption(
+            "--key-file requires --encrypt; there's no LUKS volume to add it as a key slot on".to_string()
+        ));
+    }
+
+    if encrypt_boot && !encrypt {
+        return Err(UsbBootHutError::Encryption(
+            "--encrypt-boot requires --encrypt; the boot partition shares the same passphrase as the data partition".to_string()
+        ));
+    }
+
     // Find the device
     let devices = enumerate_usb_devices()?;
     let device = devices.into_iter()
         .find(|d| d.path == device_path)
         .ok_or_else(|| UsbBootHutError::Device(format!("Device not found: {}", device_path.display())))?;
-    
+
     // Show device info
     println!("\n{}", "Device Information:".bold());
     println!("  Path:     {}", device.path.display());
     println!("  Model:    {} {}", device.vendor, device.model);
     println!("  Size:     {} GB", device.size / 1_000_000_000);
     println!("  Type:     {}", if device.removable { "Removable" } else { "Fixed" }.red());
-    
+    if let Some(serial) = &device.serial {
+        println!("  Serial:   {}", serial);
+    }
+    if let Some(warning) = device.internal_bus_warning() {
+        println!("\n{} {}", "⚠️ ".yellow(), warning.yellow());
+    }
+
     // Validate device
     device.is_valid_for_boot()?;
+
+    // SMART pre-flight check
+    let health = check_health_or_abort(&device, require_healthy)?;
+    if !health.warnings.is_empty() {
+        println!("\n{}", "⚠️  SMART Health Warnings:".yellow().bold());
+        for warning in &health.warnings {
+            println!("  - {}", warning);
+        }
+    }
     
     // Safety check
     if device.has_system_files() {
@@ -110,12 +196,23 @@ fn handle_format(device_path: &Path, encrypt: bool, secure_wipe: bool, skip_conf
     println!("  2. 📊 Create GPT partition table");
     println!("  3. 💾 Create partitions:");
     println!("     - ESP:  512MB FAT32 (UEFI boot)");
-    println!("     - Boot: 512MB ext4 (GRUB config)");
-    println!("     - Data: {:.1}GB {} (ISO storage)", 
-        (device.size - 1024*1024*1024) as f64 / 1_000_000_000.0,
-        if encrypt { "LUKS-encrypted ext4" } else { "ext4" }
+    println!("     - Boot: 512MB {} (GRUB config)", if encrypt_boot { "LUKS-encrypted ext4" } else { "ext4" });
+    let data_fs_label = format!("{:?}", layout.data_filesystem).to_lowercase();
+    let data_size_label = match layout.data_size_mb {
+        Some(mb) => format!("{:.1}GB", mb as f64 / 1024.0),
+        None => format!("{:.1}GB", (device.size - 1024*1024*1024) as f64 / 1_000_000_000.0),
+    };
+    println!("     - Data: {} {} (ISO storage)",
+        data_size_label,
+        if encrypt { format!("LUKS-encrypted {}", data_fs_label) } else { data_fs_label }
     );
-    println!("  4. 🚀 Install GRUB2 bootloader");
+    if let Some(persistence) = &layout.persistence {
+        println!("     - {}: {:.1}GB ext4 (live-persistence overlay)", persistence.label, persistence.size_mb as f64 / 1024.0);
+    }
+    println!("  4. 🚀 Install {} bootloader", match bootloader {
+        crate::bootloader::BootloaderKind::Grub => "GRUB2",
+        crate::bootloader::BootloaderKind::Syslinux => "Syslinux",
+    });
     println!("  5. 📁 Create directory structure");
     
     // Confirm format
@@ -166,18 +263,21 @@ fn handle_format(device_path: &Path, encrypt: bool, secure_wipe: bool, skip_conf
     #[cfg(target_os = "linux")]
     {
         // Create drive manager
-        let mut manager = DriveManager::new(device);
+        let mut manager = DriveManager::new(device).with_bootloader(bootloader);
         if encrypt {
             manager = manager.with_encryption();
         }
+        if encrypt_boot {
+            manager = manager.with_boot_encryption();
+        }
         
         // Format the drive
         println!("\n{}", "🚀 Starting format process...".cyan().bold());
         
         if secure_wipe {
-            manager.secure_format(passphrase.as_deref())?;
+            manager.secure_format(passphrase.as_deref(), key_file, &layout)?;
         } else {
-            manager.format_and_setup(passphrase.as_deref())?;
+            manager.format_and_setup(passphrase.as_deref(), key_file, &layout)?;
         }
         
         println!("\n{}", "✅ USB drive successfully formatted!".green().bold());
@@ -190,29 +290,105 @@ fn handle_format(device_path: &Path, encrypt: bool, secure_wipe: bool, skip_conf
     Ok(())
 }
 
-fn handle_unlock(device_path: &Path, mount_point: Option<&Path>) -> Result<()> {
-    // TODO: Implement unlock functionality
-    println!("Unlocking encrypted drive: {}", device_path.display());
-    println!("Mount point: {:?}", mount_point);
+fn handle_unlock(device_path: &Path, mount_point: Option<&Path>, key_file: Option<&Path>) -> Result<()> {
+    use crate::crypto::{ensure_mapper_available, resolve_mapper_identity, LuksKeySource, LuksManager, PasswordHolder};
+    use std::fs;
+
+    let (mapper_name, default_mount_target) = resolve_mapper_identity(device_path)?;
+    ensure_mapper_available(&mapper_name)?;
+    let mount_target = mount_point.map(|p| p.to_path_buf()).unwrap_or(default_mount_target);
+
+    let luks_mgr = LuksManager::new();
+
+    // Prefer the key file for an unattended unlock, falling back to an
+    // interactive passphrase prompt if it's missing or cryptsetup rejects it.
+    let holder = match key_file {
+        Some(path) => {
+            let sudo_holder = PasswordHolder::prompt_sudo_only()?;
+            println!("🔓 Unlocking {} with key file...", device_path.display());
+            match luks_mgr.open_encrypted_partition(device_path, &LuksKeySource::KeyFile(path.to_path_buf()), &mapper_name) {
+                Ok(()) => sudo_holder,
+                Err(_) => {
+                    println!("Key file did not unlock the drive; falling back to a passphrase.");
+                    let holder = PasswordHolder::prompt("LUKS passphrase")?;
+                    luks_mgr.open_encrypted_partition(device_path, &LuksKeySource::Passphrase(holder.luks_passphrase().to_string()), &mapper_name)?;
+                    holder
+                }
+            }
+        }
+        None => {
+            let holder = PasswordHolder::prompt("LUKS passphrase")?;
+            println!("🔓 Unlocking {}...", device_path.display());
+            luks_mgr.open_encrypted_partition(device_path, &LuksKeySource::Passphrase(holder.luks_passphrase().to_string()), &mapper_name)?;
+            holder
+        }
+    };
+
+    fs::create_dir_all(&mount_target)
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to create mount point: {}", e)))?;
+
+    let mapper_path = format!("/dev/mapper/{}", mapper_name);
+    holder.run_sudo("mount", &[&mapper_path, mount_target.to_str().unwrap()], None)?;
+
+    println!("✅ Unlocked and mounted at {}", mount_target.display());
     Ok(())
 }
 
 fn handle_lock(device_path: &Path) -> Result<()> {
-    // TODO: Implement lock functionality
-    println!("Locking encrypted drive: {}", device_path.display());
+    use crate::crypto::{resolve_mapper_identity, LuksManager, PasswordHolder};
+
+    let (mapper_name, mount_target) = resolve_mapper_identity(device_path)?;
+    // luksClose only needs the mapper name, not the passphrase, so we only
+    // need sudo rights to unmount.
+    let holder = PasswordHolder::prompt_sudo_only()?;
+
+    println!("🔒 Unmounting {}...", mount_target.display());
+    holder.run_sudo("umount", &[mount_target.to_str().unwrap()], None)?;
+
+    println!("🔒 Closing LUKS mapping {}...", mapper_name);
+    LuksManager::new().close_encrypted_partition(&mapper_name)?;
+
+    println!("✅ Locked: {}", device_path.display());
     Ok(())
 }
 
-fn handle_add(iso_path: &Path, verify_checksum: Option<&str>, _category: Option<&str>, _tags: Option<&str>) -> Result<()> {
-    // TODO: Need to determine mount points
-    println!("Adding ISO: {}", iso_path.display());
-    if let Some(checksum) = verify_checksum {
-        println!("Verifying checksum: {}", checksum);
+fn handle_add(
+    device_path: &Path,
+    iso_source: &str,
+    verify_checksum: Option<&str>,
+    category: Option<&str>,
+    tags: Option<&str>,
+    channel: Option<String>,
+    signing_key: Option<String>,
+    trusted_keyring: Option<std::path::PathBuf>,
+) -> Result<()> {
+    use crate::iso::IsoManager;
+    use crate::iso::signing::MetadataTrust;
+
+    let trust_channel = channel.clone().unwrap_or_else(|| "stable".to_string());
+    let trust = signing_key.map(|key| MetadataTrust::signing(trust_channel.clone(), trusted_keyring.clone(), key))
+        .or_else(|| trusted_keyring.clone().map(|keyring| MetadataTrust::verify_only(trust_channel.clone(), Some(keyring))));
+
+    let mut manager = IsoManager::with_trust(device_path, device_path, trust)?;
+    let category = category.map(parse_category);
+    let tags = tags.map(|t| t.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect());
+
+    if iso_source.starts_with("http://") || iso_source.starts_with("https://") {
+        manager.add_iso_from_url(iso_source, verify_checksum, category, tags, channel)
+    } else {
+        manager.add_iso(Path::new(iso_source), verify_checksum, category, tags, channel)
     }
-    Ok(())
 }
 
-fn handle_remove(iso_name: &str, skip_confirm: bool) -> Result<()> {
+fn handle_remove(device_path: &Path, iso_name: &str, skip_confirm: bool) -> Result<()> {
+    use crate::iso::IsoManager;
+
+    let mut manager = IsoManager::new(device_path, device_path)?;
+    let iso_id = manager.list_isos(None).into_iter()
+        .find(|m| m.display_name == iso_name || m.filename == iso_name || m.id == iso_name)
+        .ok_or_else(|| UsbBootHutError::Iso(format!("ISO not found: {}", iso_name)))?
+        .id.clone();
+
     if !skip_confirm {
         if !Confirm::new()
             .with_prompt(format!("Remove ISO '{}'?", iso_name))
@@ -224,42 +400,121 @@ fn handle_remove(iso_name: &str, skip_confirm: bool) -> Result<()> {
             return Ok(());
         }
     }
-    
-    // TODO: Implement remove functionality
-    println!("Removing ISO: {}", iso_name);
-    Ok(())
+
+    manager.remove_iso(&iso_id)
 }
 
-fn handle_list(_device: Option<&Path>, _category: Option<&str>, format: ListFormat) -> Result<()> {
-    // TODO: Implement list functionality
-    println!("Listing ISOs...");
+fn handle_archive(device_path: &Path, iso_name: &str, codec: crate::iso::ContainerCodec) -> Result<()> {
+    use crate::iso::IsoManager;
+
+    let mut manager = IsoManager::new(device_path, device_path)?;
+    let iso_id = manager.list_isos(None).into_iter()
+        .find(|m| m.display_name == iso_name || m.filename == iso_name || m.id == iso_name)
+        .ok_or_else(|| UsbBootHutError::Iso(format!("ISO not found: {}", iso_name)))?
+        .id.clone();
+
+    manager.archive_iso(&iso_id, codec)
+}
+
+fn handle_restore(device_path: &Path, iso_name: &str) -> Result<()> {
+    use crate::iso::IsoManager;
+
+    let mut manager = IsoManager::new(device_path, device_path)?;
+    let iso_id = manager.list_isos(None).into_iter()
+        .find(|m| m.display_name == iso_name || m.filename == iso_name || m.id == iso_name)
+        .ok_or_else(|| UsbBootHutError::Iso(format!("ISO not found: {}", iso_name)))?
+        .id.clone();
+
+    manager.restore_iso(&iso_id)
+}
+
+/// Maps a free-form `--category` string onto the closed `IsoCategory` set,
+/// falling back to `Custom` for anything that doesn't match a known one
+/// rather than rejecting the value outright.
+fn parse_category(input: &str) -> crate::iso::IsoCategory {
+    use crate::iso::IsoCategory;
+
+    match input.to_lowercase().as_str() {
+        "linux" => IsoCategory::Linux,
+        "windows" => IsoCategory::Windows,
+        "rescue" => IsoCategory::Rescue,
+        "utility" => IsoCategory::Utility,
+        "security" => IsoCategory::Security,
+        _ => IsoCategory::Custom,
+    }
+}
+
+fn handle_list(device: Option<&Path>, category: Option<&str>, format: ListFormat) -> Result<()> {
+    use crate::iso::IsoManager;
+    use crate::cleanup::format_size;
+
+    let device = device.ok_or_else(|| UsbBootHutError::Device(
+        "No device specified; pass a mount point, e.g. `usb-boot-hut list /mnt/usb`".to_string()
+    ))?;
+
+    let manager = IsoManager::new(device, device)?;
+    let isos = manager.list_isos(category.map(parse_category));
+
     match format {
         ListFormat::Table => {
             let mut table = Table::new();
-            table.add_row(row!["Name", "Size", "Type", "Added"]);
-            table.add_row(row!["Ubuntu 22.04", "4.7 GB", "Linux", "2024-01-15"]);
+            table.add_row(row!["Name", "Size", "Type", "Added", "Verification", "Archived"]);
+            for iso in &isos {
+                table.add_row(row![
+                    iso.display_name,
+                    format_size(iso.size),
+                    format!("{:?}", iso.iso_type),
+                    iso.added_date.format("%Y-%m-%d"),
+                    format!("{:?}", iso.verification_status),
+                    match &iso.compression {
+                        Some(c) => format!("{:?} ({:.0}%)", c.codec, c.ratio(iso.size) * 100.0),
+                        None => "-".to_string(),
+                    },
+                ]);
+            }
             table.printstd();
         },
         ListFormat::Json => {
-            println!(r#"{{"isos": []}}"#);
+            let json = serde_json::to_string_pretty(&isos)
+                .map_err(|e| UsbBootHutError::Iso(format!("Failed to serialize ISO list: {}", e)))?;
+            println!("{}", json);
         },
         ListFormat::Csv => {
-            println!("name,size,type,added");
+            println!("name,size,type,added,verification");
+            for iso in &isos {
+                println!("{},{},{:?},{},{:?}", iso.display_name, iso.size, iso.iso_type, iso.added_date.format("%Y-%m-%d"), iso.verification_status);
+            }
         },
         ListFormat::Simple => {
-            println!("Ubuntu 22.04 (4.7 GB)");
+            for iso in &isos {
+                println!("{} ({}) [{:?}]", iso.display_name, format_size(iso.size), iso.verification_status);
+            }
         }
     }
     Ok(())
 }
 
-fn handle_verify(device_path: &Path, iso_name: Option<&str>) -> Result<()> {
-    println!("Verifying ISOs on: {}", device_path.display());
-    if let Some(name) = iso_name {
-        println!("Checking: {}", name);
-    } else {
-        println!("Checking all ISOs...");
+fn handle_verify(device_path: &Path, iso_name: Option<&str>, threads: Option<usize>, known_good_path: Option<&Path>, trusted_keyring: Option<&Path>, channel: &str) -> Result<()> {
+    use crate::iso::IsoManager;
+    use crate::iso::signing::MetadataTrust;
+
+    let trust = trusted_keyring.map(|keyring| MetadataTrust::verify_only(channel.to_string(), Some(keyring.to_path_buf())));
+    let mut manager = IsoManager::with_trust(device_path, device_path, trust)?;
+    let known_good = known_good_path.map(IsoManager::load_known_good).transpose()?;
+
+    match iso_name {
+        Some(name) if name != "all" => {
+            let iso_id = manager.list_isos(None).into_iter()
+                .find(|m| m.display_name == name || m.filename == name || m.id == name)
+                .ok_or_else(|| UsbBootHutError::Iso(format!("ISO not found: {}", name)))?
+                .id.clone();
+            manager.verify_iso(&iso_id, known_good.as_ref())?;
+        },
+        _ => {
+            manager.verify_all(threads, known_good.as_ref())?;
+        }
     }
+
     Ok(())
 }
 
@@ -277,7 +532,108 @@ fn handle_clean(device_path: &Path, config_path: Option<&Path>, dry_run: bool) -
     
     let stats = engine.clean(device_path)?;
     stats.print_summary();
-    
+
+    Ok(())
+}
+
+fn handle_dedupe(device_path: &Path, dry_run: bool, skip_confirm: bool) -> Result<()> {
+    use crate::iso::IsoManager;
+    use crate::cleanup::format_size;
+
+    let mut manager = IsoManager::new(device_path, device_path)?;
+
+    println!("{}", "🔍 Scanning for duplicate ISOs...".cyan());
+    let groups = manager.find_duplicates()?;
+
+    if groups.is_empty() {
+        println!("{}", "✨ No duplicate ISOs found!".green());
+        return Ok(());
+    }
+
+    println!("\n{}", format!("Found {} duplicate group(s):", groups.len()).yellow());
+    for group in &groups {
+        println!("  {} {} ({} copies, {} reclaimable)",
+            "•".cyan(),
+            group.checksum,
+            group.paths.len(),
+            format_size(group.reclaimable_bytes())
+        );
+        for path in &group.paths {
+            println!("      {}", path.display());
+        }
+    }
+
+    let total_reclaimable: u64 = groups.iter().map(|g| g.reclaimable_bytes()).sum();
+    println!("\n{}", format!("Total space reclaimable: {}", format_size(total_reclaimable)).bold());
+
+    if !dry_run && !skip_confirm {
+        if !Confirm::new()
+            .with_prompt("Delete all but one copy from each group?")
+            .default(false)
+            .interact()
+            .map_err(|e| UsbBootHutError::Dialog(e.to_string()))?
+        {
+            println!("{}", "Dedupe cancelled".yellow());
+            return Ok(());
+        }
+    }
+
+    let stats = manager.remove_duplicates(&groups, dry_run)?;
+    if dry_run {
+        println!("\n{}", "DRY RUN - No files were deleted".yellow());
+    }
+    stats.print_summary();
+
+    Ok(())
+}
+
+fn handle_backup(action: BackupAction) -> Result<()> {
+    use crate::iso::IsoManager;
+
+    match action {
+        BackupAction::Create { device, config, name } => {
+            let manager = IsoManager::new(&device, &device)?;
+
+            println!("{}", "📸 Exporting snapshot...".cyan());
+            let snapshot = manager.create_snapshot(config.as_deref())?;
+
+            let name = name.unwrap_or_else(|| snapshot.created_at.format("%Y%m%dT%H%M%SZ").to_string());
+            let path = manager.save_snapshot(&snapshot, &name)?;
+
+            println!("✅ Snapshot saved: {}", path.display());
+            println!("   {} ISO(s), cleanup config: {}, GRUB menu: {}",
+                snapshot.metadata.len(),
+                if snapshot.cleanup_config.is_some() { "yes" } else { "no" },
+                if snapshot.grub_config.is_some() { "yes" } else { "no" }
+            );
+        },
+        BackupAction::Restore { device, snapshot, config, reconcile } => {
+            let mut manager = IsoManager::new(&device, &device)?;
+
+            println!("{}", "📦 Loading snapshot...".cyan());
+            let snapshot = IsoManager::load_snapshot(&snapshot)?;
+
+            println!("🔧 Restoring metadata, cleanup config, and GRUB entries...");
+            manager.restore_snapshot(&snapshot, config.as_deref())?;
+            println!("✅ Restore complete");
+
+            if reconcile {
+                println!("\n{}", "🔍 Reconciling /isos against restored metadata...".cyan());
+                let stats = manager.reconcile()?;
+                println!("✅ Re-registered {} ISO(s), pruned {} stale entr{}",
+                    stats.reregistered, stats.pruned, if stats.pruned == 1 { "y" } else { "ies" });
+            }
+        },
+        BackupAction::Reconcile { device } => {
+            let mut manager = IsoManager::new(&device, &device)?;
+
+            println!("{}", "🔍 Reconciling /isos against metadata...".cyan());
+            let stats = manager.reconcile()?;
+            println!("✅ Re-registered {} ISO(s), pruned {} stale entr{}",
+                stats.reregistered, stats.pruned, if stats.pruned == 1 { "y" } else { "ies" });
+        },
+    }
+
     Ok(())
 }
 
@@ -317,7 +673,23 @@ fn handle_config(action: ConfigAction) -> Result<()> {
     Ok(())
 }
 
-fn handle_devices(show_all: bool, format: ListFormat) -> Result<()> {
+fn handle_devices(show_all: bool, format: ListFormat, wait: bool, wait_timeout: u64) -> Result<()> {
+    if wait {
+        use crate::disk::wait_for_device;
+        use std::time::Duration;
+
+        println!("{}", "Waiting for a USB device to be inserted...".cyan());
+        let device = wait_for_device(Duration::from_secs(wait_timeout))?;
+        println!("{} {} ({} {}, {:.1} GB)",
+            "✅ Detected:".green().bold(),
+            device.path.display(),
+            device.vendor,
+            device.model,
+            device.size as f64 / 1_000_000_000.0
+        );
+        return Ok(());
+    }
+
     let devices = enumerate_usb_devices()?;
     let filtered: Vec<_> = if show_all {
         devices
@@ -337,34 +709,41 @@ fn handle_devices(show_all: bool, format: ListFormat) -> Result<()> {
                 "Device",
                 "Size",
                 "Model",
+                "Bus",
                 "Removable",
-                "Partitions"
+                "Partitions",
+                "Serial"
             ]);
-            
+
             for device in filtered {
                 table.add_row(row![
                     device.path.display(),
                     format!("{:.1} GB", device.size as f64 / 1_000_000_000.0),
                     format!("{} {}", device.vendor, device.model),
+                    format!("{:?}", device.bus),
                     if device.removable { "Yes".green() } else { "No".red() },
-                    device.partitions.len()
+                    device.partitions.len(),
+                    device.serial.as_deref().unwrap_or("-")
                 ]);
             }
-            
+
             table.printstd();
         },
         ListFormat::Json => {
             println!("{}", serde_json::to_string_pretty(&filtered).unwrap());
         },
         ListFormat::Csv => {
-            println!("device,size_gb,model,removable,partitions");
+            println!("device,size_gb,model,bus,removable,partitions,serial");
             for device in filtered {
-                println!("{},{:.1},{} {},{}",
+                println!("{},{:.1},{} {},{:?},{},{},{}",
                     device.path.display(),
                     device.size as f64 / 1_000_000_000.0,
                     device.vendor,
                     device.model,
-                    device.removable
+                    device.bus,
+                    device.removable,
+                    device.partitions.len(),
+                    device.serial.as_deref().unwrap_or("-")
                 );
             }
         },
@@ -390,17 +769,31 @@ fn handle_status(device_path: &Path) -> Result<()> {
 }
 
 fn handle_update_grub(device_path: &Path, regenerate: bool) -> Result<()> {
+    use crate::bootloader::{fs_uuid, GrubConfigManager};
+    use crate::iso::IsoManager;
+
     println!("Updating GRUB configuration on: {}", device_path.display());
-    if regenerate {
-        println!("Regenerating all entries...");
+
+    if !regenerate {
+        let mut manager = IsoManager::new(device_path, device_path)?;
+        let stats = manager.reconcile()?;
+        println!("✅ Appended {} new entr{}, pruned {} stale catalog entr{}",
+            stats.reregistered, if stats.reregistered == 1 { "y" } else { "ies" },
+            stats.pruned, if stats.pruned == 1 { "y" } else { "ies" });
+        return Ok(());
     }
-    // TODO: Implement GRUB update
+
+    println!("Regenerating all entries...");
+    let boot_uuid = fs_uuid(device_path)?;
+    let grub_mgr = GrubConfigManager::new(device_path);
+    let count = grub_mgr.regenerate(&boot_uuid, &device_path.join("isos"))?;
+    println!("✅ Regenerated {} menu entr{}", count, if count == 1 { "y" } else { "ies" });
+
     Ok(())
 }
 
 fn handle_nuke(device_path: &Path, passes: u8, pattern: WipePattern, force: bool, verify: bool) -> Result<()> {
     use crate::disk::SecureWipe;
-    use crate::utils::AnimationPlayer;
     use indicatif::{ProgressBar, ProgressStyle};
     
     // Find the device
@@ -448,6 +841,11 @@ fn handle_nuke(device_path: &Path, passes: u8, pattern: WipePattern, force: bool
             println!("  Method:  Peter Gutmann's 35-pass secure deletion");
             println!("  Note:    This is overkill for modern drives!");
         },
+        WipePattern::HardwareSecure => {
+            println!("  Pattern: Hardware secure erase");
+            println!("  Method:  BLKSECDISCARD/BLKDISCARD (falls back to random overwrite if unsupported)");
+            println!("  Note:    Multi-pass overwrite is meaningless on wear-levelled flash; this is faster and more thorough when supported.");
+        },
     }
     
     let total_passes = match pattern {
@@ -539,48 +937,330 @@ fn handle_nuke(device_path: &Path, passes: u8, pattern: WipePattern, force: bool
     println!("{}", "Press Ctrl+C to abort (data may already be partially destroyed)".yellow());
     
     let wiper = SecureWipe::new(device_path);
-    
-    // Create progress tracking
-    let pb = ProgressBar::new(100);
+
+    // Byte-accurate progress: indicatif derives MiB/s and ETA from a
+    // sliding window over `{bar}`'s position history, so feeding it real
+    // bytes-written/bytes-total (rather than a hand-rolled percentage) gets
+    // trustworthy throughput and ETA for free.
+    let pb = ProgressBar::new(0);
     pb.set_style(
         ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.red/yellow} {pos}% | Pass {msg}")
+            .template("🧹 {msg}\n[{elapsed_precise}] [{bar:40.red/yellow}] {bytes}/{total_bytes} ({bytes_per_sec}, ETA {eta})")
             .unwrap()
             .progress_chars("█▓░")
     );
-    
+
     // Perform the wipe
-    wiper.nuke_drive(pattern, passes, |current_pass, total_passes, message| {
-        pb.set_message(format!("{}/{}", current_pass, total_passes));
-        
-        // Extract percentage from message if available
-        if let Some(percent_pos) = message.rfind('%') {
-            if let Some(num_start) = message[..percent_pos].rfind(' ') {
-                if let Ok(percent) = message[num_start+1..percent_pos].parse::<u64>() {
-                    pb.set_position(percent);
-                }
-            }
+    wiper.nuke_drive(pattern, passes, |current_pass, total_passes, message, bytes_done, bytes_total| {
+        if bytes_total > 0 {
+            pb.set_length(bytes_total);
         }
-        
-        pb.set_prefix(message);
+        pb.set_position(bytes_done);
+        pb.set_message(format!("Pass {}/{}: {}", current_pass, total_passes, message));
     })?;
-    
+
     pb.finish_with_message("COMPLETE");
     
     // Verify if requested
     if verify {
         println!("\n{}", "🔍 Verifying wipe...".cyan());
-        let wiped = wiper.verify_wiped()?;
-        if wiped {
+        let report = wiper.verify_wiped()?;
+        if report.is_clean() {
             println!("{}", "✅ Verification passed: No filesystem signatures found".green());
         } else {
-            println!("{}", "⚠️  Verification failed: Filesystem signatures still present!".red());
+            println!("{}", "⚠️  Verification failed: Residual data still present!".red());
+            for found in report.signatures.iter().chain(report.gpt_headers.iter()) {
+                println!("  - {}", found);
+            }
             println!("The wipe may have been incomplete. Consider running again.");
         }
     }
     
     println!("\n{}", "☠️  DEVICE NUKED ☠️".red().bold());
     println!("The device has been securely wiped and is ready for disposal or reuse.");
-    
+
+    Ok(())
+}
+
+fn handle_burn(image: &Path, device_path: &Path, no_verify: bool, enable_ssh: bool, wifi: Option<&str>, skip_confirm: bool, eject: bool) -> Result<()> {
+    use crate::disk::{configure_raspberry_pi, inject_provisioning, inject_serial_console, ImageBurner};
+
+    let devices = enumerate_usb_devices()?;
+    let device = devices.into_iter()
+        .find(|d| d.path == device_path)
+        .ok_or_else(|| UsbBootHutError::Device(format!("Device not found: {}", device_path.display())))?;
+
+    println!("\n{}", "Device Information:".bold());
+    println!("  Path:     {}", device.path.display());
+    println!("  Model:    {} {}", device.vendor, device.model);
+    println!("  Size:     {} GB", device.size / 1_000_000_000);
+
+    let wifi_credentials = wifi.map(parse_wifi).transpose()?;
+
+    if !skip_confirm {
+        println!("\n{}", "⚠️  WARNING: All data on this device will be destroyed!".red().bold());
+        if !Confirm::new()
+            .with_prompt(format!("Burn {} to {}?", image.display(), device_path.display()))
+            .default(false)
+            .interact()
+            .map_err(|e| UsbBootHutError::Dialog(e.to_string()))?
+        {
+            println!("Operation cancelled.");
+            return Ok(());
+        }
+    }
+
+    println!("\n{}", "🔥 Burning image...".cyan().bold());
+    let burner = ImageBurner::new(image, device_path);
+    burner.burn()?;
+
+    if !no_verify {
+        burner.verify()?;
+    }
+
+    // Raspberry Pi OS boot firmware takes its first-boot config as raw
+    // `ssh`/`wpa_supplicant.conf` files rather than cloud-init/Ignition, so
+    // it's handled separately from `inject_provisioning` below.
+    let wifi_pair = wifi_credentials.as_ref().map(|(ssid, psk)| (ssid.as_str(), psk.as_str()));
+    if enable_ssh || wifi_pair.is_some() {
+        match configure_raspberry_pi(device_path, enable_ssh, wifi_pair) {
+            Ok(()) => println!("✅ Raspberry Pi first-boot files written"),
+            Err(e) => println!("ℹ️  Skipping Raspberry Pi first-boot files ({})", e),
+        }
+    }
+
+    inject_provisioning(image, device_path, enable_ssh, wifi_pair)?;
+    inject_serial_console(device_path)?;
+
+    if eject {
+        eject_device(device_path)?;
+    }
+
+    println!("\n{}", "✅ Image burned successfully!".green().bold());
+    Ok(())
+}
+
+fn handle_create_image(
+    output: &Path,
+    size_mb: u64,
+    encrypt: bool,
+    encrypt_boot: bool,
+    bootloader: crate::bootloader::BootloaderKind,
+    data_fs: Option<crate::partition::DataFilesystem>,
+    data_size_mb: Option<u64>,
+    key_file: Option<&Path>,
+) -> Result<()> {
+    use crate::disk::DriveManager;
+    use crate::partition::{DataFilesystem, PartitionLayout};
+
+    let app_config = ConfigManager::new()?;
+    let data_filesystem = match data_fs {
+        Some(fs) => fs,
+        None => DataFilesystem::parse(&app_config.get().default_data_filesystem)?,
+    };
+    let layout = PartitionLayout {
+        data_filesystem,
+        data_size_mb,
+        persistence: None,
+    };
+
+    if key_file.is_some() && !encrypt {
+        return Err(UsbBootHutError::Encryption(
+            "--key-file requires --encrypt; there's no LUKS volume to add it as a key slot on".to_string()
+        ));
+    }
+
+    if encrypt_boot && !encrypt {
+        return Err(UsbBootHutError::Encryption(
+            "--encrypt-boot requires --encrypt; the boot partition shares the same passphrase as the data partition".to_string()
+        ));
+    }
+
+    if output.exists() {
+        println!("\n{}", format!("⚠️  {} already exists and will be overwritten.", output.display()).yellow());
+        if !Confirm::new()
+            .with_prompt("Overwrite it?")
+            .default(false)
+            .interact()
+            .map_err(|e| UsbBootHutError::Dialog(e.to_string()))?
+        {
+            println!("Operation cancelled.");
+            return Ok(());
+        }
+    }
+
+    println!("\n{}", "📋 Image Plan:".cyan().bold());
+    println!("  Output: {} ({} MB)", output.display(), size_mb);
+    println!("  1. 📊 Create GPT partition table");
+    println!("  2. 💾 Create partitions:");
+    println!("     - ESP:  512MB FAT32 (UEFI boot)");
+    println!("     - Boot: 512MB {} (GRUB config)", if encrypt_boot { "LUKS-encrypted ext4" } else { "ext4" });
+    let data_fs_label = format!("{:?}", layout.data_filesystem).to_lowercase();
+    println!("     - Data: {} (ISO storage)", if encrypt { format!("LUKS-encrypted {}", data_fs_label) } else { data_fs_label });
+    println!("  3. 🚀 Install {} bootloader", match bootloader {
+        crate::bootloader::BootloaderKind::Grub => "GRUB2",
+        crate::bootloader::BootloaderKind::Syslinux => "Syslinux",
+    });
+
+    let passphrase = if encrypt {
+        println!("\n{}", "🔐 Encryption Setup".green().bold());
+        println!("Enter a strong passphrase for LUKS encryption.");
+        println!("Requirements: 12+ chars, mixed case, numbers or symbols");
+
+        let pass = Password::new()
+            .with_prompt("Passphrase")
+            .with_confirmation("Confirm passphrase", "Passphrases do not match")
+            .interact()
+            .map_err(|e| UsbBootHutError::Dialog(e.to_string()))?;
+
+        Some(pass)
+    } else {
+        None
+    };
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        println!("\n{}", "❌ Platform Limitation".red().bold());
+        println!("Building a disk image requires Linux for losetup, cryptsetup, and ext4/FAT32 support.");
+        return Ok(());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut manager = DriveManager::for_image().with_bootloader(bootloader);
+        if encrypt {
+            manager = manager.with_encryption();
+        }
+        if encrypt_boot {
+            manager = manager.with_boot_encryption();
+        }
+
+        println!("\n{}", "🚀 Building image...".cyan().bold());
+        manager.build_image(output, size_mb, passphrase.as_deref(), key_file, &layout)?;
+
+        println!("\nNext steps:");
+        println!("  1. Flash it to a drive: {}", format!("usb-boot-hut burn {} <device>", output.display()).cyan());
+        println!("  2. Add ISOs: {}", "usb-boot-hut add <iso-file>".cyan());
+    }
+
+    Ok(())
+}
+
+fn handle_slot(action: SlotAction) -> Result<()> {
+    use crate::partition::{BootSlotAttributes, DataFilesystem, PartitionManager, PartitionTableLayout, BOOT_SLOT_A_PARTITION, BOOT_SLOT_B_PARTITION};
+
+    fn partition_number(slot: BootSlotName) -> u32 {
+        match slot {
+            BootSlotName::A => BOOT_SLOT_A_PARTITION,
+            BootSlotName::B => BOOT_SLOT_B_PARTITION,
+        }
+    }
+
+    fn other_slot(slot: BootSlotName) -> BootSlotName {
+        match slot {
+            BootSlotName::A => BootSlotName::B,
+            BootSlotName::B => BootSlotName::A,
+        }
+    }
+
+    // `get`/`set_slot_attributes` read/write the on-disk GPT directly, so
+    // the layout `PartitionManager` carries only matters for
+    // `create_partitions`; a placeholder `with_ab_boot` layout is enough
+    // for a manager that's only ever used for its slot accessors here.
+    fn manager_for(device_path: &Path) -> PartitionManager {
+        PartitionManager::new(device_path, PartitionTableLayout::with_ab_boot(DataFilesystem::Ext4, None))
+    }
+
+    match action {
+        SlotAction::Activate { device, slot, tries } => {
+            let partition_mgr = manager_for(&device);
+
+            let demoted = partition_mgr.get_slot_attributes(partition_number(other_slot(slot)))?;
+            partition_mgr.set_slot_attributes(partition_number(other_slot(slot)), BootSlotAttributes {
+                priority: demoted.priority.min(1),
+                ..demoted
+            })?;
+
+            partition_mgr.set_slot_attributes(partition_number(slot), BootSlotAttributes {
+                priority: 15,
+                tries_remaining: tries,
+                successful: false,
+            })?;
+
+            println!("✅ Slot {} activated (priority 15, {} boot attempt{})", slot, tries, if tries == 1 { "" } else { "s" });
+        },
+        SlotAction::MarkGood { device, slot } => {
+            let partition_mgr = manager_for(&device);
+
+            let slot = match slot {
+                Some(slot) => slot,
+                None => {
+                    let a = partition_mgr.get_slot_attributes(BOOT_SLOT_A_PARTITION)?;
+                    let b = partition_mgr.get_slot_attributes(BOOT_SLOT_B_PARTITION)?;
+                    if a.priority >= b.priority { BootSlotName::A } else { BootSlotName::B }
+                }
+            };
+
+            let attrs = partition_mgr.get_slot_attributes(partition_number(slot))?;
+            partition_mgr.set_slot_attributes(partition_number(slot), BootSlotAttributes {
+                successful: true,
+                tries_remaining: 0,
+                ..attrs
+            })?;
+
+            println!("✅ Slot {} marked successful", slot);
+        },
+        SlotAction::Rollback { device } => {
+            let partition_mgr = manager_for(&device);
+
+            let a = partition_mgr.get_slot_attributes(BOOT_SLOT_A_PARTITION)?;
+            let b = partition_mgr.get_slot_attributes(BOOT_SLOT_B_PARTITION)?;
+            let (active_num, active, fallback_num, fallback_name) = if a.priority >= b.priority {
+                (BOOT_SLOT_A_PARTITION, a, BOOT_SLOT_B_PARTITION, BootSlotName::B)
+            } else {
+                (BOOT_SLOT_B_PARTITION, b, BOOT_SLOT_A_PARTITION, BootSlotName::A)
+            };
+
+            if active.successful {
+                return Err(UsbBootHutError::Partition(
+                    "The active slot already marked itself successful; nothing to roll back".to_string()
+                ));
+            }
+
+            partition_mgr.set_slot_attributes(active_num, BootSlotAttributes { priority: 0, tries_remaining: 0, successful: false })?;
+            partition_mgr.set_slot_attributes(fallback_num, BootSlotAttributes { priority: 15, tries_remaining: 0, successful: true })?;
+
+            println!("⏮️  Rolled back to slot {}", fallback_name);
+        },
+    }
+
+    Ok(())
+}
+
+/// Parses `--wifi`'s "SSID:password" format.
+fn parse_wifi(spec: &str) -> Result<(String, String)> {
+    spec.split_once(':')
+        .map(|(ssid, password)| (ssid.to_string(), password.to_string()))
+        .ok_or_else(|| UsbBootHutError::Device(
+            "Invalid --wifi value; expected \"SSID:password\"".to_string()
+        ))
+}
+
+fn eject_device(device_path: &Path) -> Result<()> {
+    use std::process::Command;
+
+    println!("⏏️  Ejecting {}...", device_path.display());
+    let output = Command::new("eject")
+        .arg(device_path)
+        .output()
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to run eject: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(UsbBootHutError::Device(
+            format!("Eject failed: {}", String::from_utf8_lossy(&output.stderr))
+        ));
+    }
+
+    println!("✅ Ejected {}", device_path.display());
     Ok(())
 }
\ No newline at end of file
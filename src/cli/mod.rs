@@ -38,52 +38,139 @@ pub enum Commands {
         /// Skip confirmation prompts
         #[arg(short = 'y', long)]
         yes: bool,
+
+        /// Abort if the SMART health check fails or wear is excessive
+        #[arg(long)]
+        require_healthy: bool,
+
+        /// Bootloader backend to install
+        #[arg(long, default_value = "grub")]
+        bootloader: crate::bootloader::BootloaderKind,
+
+        /// Data partition filesystem (default: from config, usually ext4)
+        #[arg(long)]
+        data_fs: Option<crate::partition::DataFilesystem>,
+
+        /// Explicit data partition size in MB (default: use all remaining space)
+        #[arg(long)]
+        data_size_mb: Option<u64>,
+
+        /// Carve out a labeled persistence partition for live distros (e.g. "persistence" or "casper-rw")
+        #[arg(long)]
+        persistence: Option<String>,
+
+        /// Persistence partition size in MB (default: from config)
+        #[arg(long)]
+        persistence_size_mb: Option<u64>,
+
+        /// Add this key file as LUKS slot 1 alongside the passphrase, for unattended unlocks
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+
+        /// Also encrypt the boot partition (requires --encrypt and --bootloader grub)
+        #[arg(long)]
+        encrypt_boot: bool,
     },
-    
+
     /// Unlock an encrypted USB drive
     Unlock {
-        /// Device path
+        /// Device path, or a `LABEL=`/`PARTLABEL=`/`UUID=` spec resolved
+        /// through /dev/disk/by-label, /dev/disk/by-partlabel, or
+        /// /dev/disk/by-uuid, so a drive can be addressed by its stable
+        /// partition label/UUID instead of a /dev/sdX name that shuffles
+        /// between boots
         device: PathBuf,
-        
+
         /// Mount point (optional, will auto-mount if not specified)
         #[arg(short, long)]
         mount: Option<PathBuf>,
+
+        /// Unlock with this key file instead of prompting for a passphrase
+        /// (falls back to a passphrase prompt if it doesn't unlock the drive)
+        #[arg(long)]
+        key_file: Option<PathBuf>,
     },
-    
+
     /// Lock an encrypted USB drive
     Lock {
-        /// Device path or mount point
+        /// Device path, or a `LABEL=`/`PARTLABEL=`/`UUID=` spec (see `unlock`)
         device: PathBuf,
+
+        /// Accepted for symmetry with `unlock`/`format`; unused since
+        /// `luksClose` only needs the mapper name, not key material
+        #[arg(long)]
+        key_file: Option<PathBuf>,
     },
     
     /// Add an ISO to the USB drive
     Add {
-        /// Path to the ISO file
-        iso_file: PathBuf,
-        
-        /// Verify checksum (provide expected SHA256)
+        /// Device path or mount point
+        device: PathBuf,
+
+        /// Path to a local ISO file, or an http(s):// URL to download it from
+        iso_source: String,
+
+        /// Verify checksum (provide expected digest, optionally "sha256:"/"sha512:"-prefixed)
         #[arg(long)]
         verify: Option<String>,
-        
+
         /// Category for the ISO
         #[arg(short, long)]
         category: Option<String>,
-        
+
         /// Tags for the ISO (comma-separated)
         #[arg(short, long)]
         tags: Option<String>,
+
+        /// Trust channel this entry is vouched for under (e.g. "stable"); see `verify --trusted-keyring`
+        #[arg(long)]
+        channel: Option<String>,
+
+        /// Re-sign the catalog after adding, with `gpg --local-user <key>`
+        #[arg(long)]
+        signing_key: Option<String>,
+
+        /// Keyring to check the catalog's existing signature against before modifying it
+        #[arg(long)]
+        trusted_keyring: Option<PathBuf>,
     },
     
     /// Remove an ISO from the USB drive
     Remove {
+        /// Device path or mount point
+        device: PathBuf,
+
         /// ISO name or ID
         iso_name: String,
-        
+
         /// Skip confirmation
         #[arg(short = 'y', long)]
         yes: bool,
     },
     
+    /// Compress a stored ISO into a container to save space; it won't be
+    /// directly bootable until `restore`d
+    Archive {
+        /// Device path or mount point
+        device: PathBuf,
+
+        /// ISO name or ID
+        iso_name: String,
+
+        /// Compression codec
+        #[arg(short, long, default_value = "zstd")]
+        codec: crate::iso::ContainerCodec,
+    },
+
+    /// Decompress a previously archived ISO back into a plain, bootable file
+    Restore {
+        /// Device path or mount point
+        device: PathBuf,
+
+        /// ISO name or ID
+        iso_name: String,
+    },
+
     /// List ISOs on the USB drive
     List {
         /// Device path or mount point
@@ -102,25 +189,63 @@ pub enum Commands {
     Verify {
         /// Device path or mount point
         device: PathBuf,
-        
+
         /// Specific ISO to verify (or "all")
         iso_name: Option<String>,
+
+        /// Number of ISOs to hash concurrently (default: available cores)
+        #[arg(short = 'j', long)]
+        threads: Option<usize>,
+
+        /// Known-good (redump-style) release database, a JSON array of
+        /// {name, sha1, size} records, to check matching ISOs against
+        #[arg(long)]
+        known_good: Option<PathBuf>,
+
+        /// Verify the ISO catalog itself is signed and untampered-with,
+        /// checking its detached signature against this keyring
+        #[arg(long)]
+        trusted_keyring: Option<PathBuf>,
+
+        /// Trust channel the catalog's signature must have been made for (default: "stable")
+        #[arg(long, default_value = "stable")]
+        channel: String,
     },
-    
+
     /// Clean junk files from the USB drive
     Clean {
         /// Device path or mount point
         device: PathBuf,
-        
+
         /// Custom cleanup config file
         #[arg(long)]
         config: Option<PathBuf>,
-        
+
+        /// Perform dry run (show what would be deleted)
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Find and remove ISOs stored more than once under different names
+    Dedupe {
+        /// Device path or mount point
+        device: PathBuf,
+
         /// Perform dry run (show what would be deleted)
         #[arg(long)]
         dry_run: bool,
+
+        /// Skip confirmation prompts
+        #[arg(short = 'y', long)]
+        yes: bool,
     },
     
+    /// Back up or restore the metadata catalog, cleanup config, and GRUB menu
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+
     /// Manage configuration
     Config {
         #[command(subcommand)]
@@ -132,10 +257,18 @@ pub enum Commands {
         /// Show all devices (not just removable)
         #[arg(short, long)]
         all: bool,
-        
+
         /// Output format
         #[arg(short, long, default_value = "table")]
         format: ListFormat,
+
+        /// Wait for a new device to be inserted instead of listing now
+        #[arg(short, long)]
+        wait: bool,
+
+        /// How long to wait for an insertion, in seconds
+        #[arg(long, default_value = "60")]
+        wait_timeout: u64,
     },
     
     /// Show USB drive status
@@ -204,6 +337,138 @@ pub enum Commands {
         #[arg(long)]
         eject: bool,
     },
+
+    /// Build a bootable disk image file with no USB drive attached; flash it later with `burn`
+    CreateImage {
+        /// Output image file path
+        output: PathBuf,
+
+        /// Image size in MB
+        #[arg(long)]
+        size_mb: u64,
+
+        /// Enable LUKS encryption for the data partition
+        #[arg(long)]
+        encrypt: bool,
+
+        /// Also encrypt the boot partition (requires --encrypt and --bootloader grub)
+        #[arg(long)]
+        encrypt_boot: bool,
+
+        /// Bootloader backend to install
+        #[arg(long, default_value = "grub")]
+        bootloader: crate::bootloader::BootloaderKind,
+
+        /// Data partition filesystem (default: from config, usually ext4)
+        #[arg(long)]
+        data_fs: Option<crate::partition::DataFilesystem>,
+
+        /// Explicit data partition size in MB (default: use all remaining space)
+        #[arg(long)]
+        data_size_mb: Option<u64>,
+
+        /// Add this key file as LUKS slot 1 alongside the passphrase, for unattended unlocks
+        #[arg(long)]
+        key_file: Option<PathBuf>,
+    },
+
+    /// Flip GPT priority/tries/successful attributes on a drive partitioned
+    /// with `partition::PartitionTableLayout::with_ab_boot`'s `USB_BOOT_A`/
+    /// `USB_BOOT_B` slots, to stage, confirm, or roll back a boot update
+    Slot {
+        #[command(subcommand)]
+        action: SlotAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SlotAction {
+    /// Stage `slot` as the one to try next: raises its priority above the
+    /// other slot's and gives it a fresh set of boot attempts
+    Activate {
+        /// Device path
+        device: PathBuf,
+
+        /// Which boot slot to activate
+        slot: BootSlotName,
+
+        /// Boot attempts to grant the activated slot before it's
+        /// considered failed and rolled back
+        #[arg(long, default_value_t = 3)]
+        tries: u8,
+    },
+
+    /// Confirm a slot booted successfully: latches `successful` and clears
+    /// its remaining tries, so a crash afterwards can't burn through them
+    MarkGood {
+        /// Device path
+        device: PathBuf,
+
+        /// Which slot to confirm; defaults to whichever has the higher priority
+        slot: Option<BootSlotName>,
+    },
+
+    /// Demote a slot that exhausted its tries without marking itself
+    /// successful, and restore priority to the other (known-good) slot
+    Rollback {
+        /// Device path
+        device: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BootSlotName {
+    A,
+    B,
+}
+
+impl std::fmt::Display for BootSlotName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::A => write!(f, "A"),
+            Self::B => write!(f, "B"),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum BackupAction {
+    /// Export a versioned snapshot of metadata, cleanup config, and GRUB entries
+    Create {
+        /// Device path or mount point
+        device: PathBuf,
+
+        /// Cleanup config file to include in the snapshot, if any
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Snapshot name (default: current UTC timestamp)
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+
+    /// Rebuild metadata, cleanup config, and GRUB entries from a snapshot
+    Restore {
+        /// Device path or mount point
+        device: PathBuf,
+
+        /// Path to the snapshot file to restore from
+        snapshot: PathBuf,
+
+        /// Cleanup config file path to restore into, if the snapshot has one
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Scan /isos afterwards and self-heal any drift from the snapshot
+        #[arg(long)]
+        reconcile: bool,
+    },
+
+    /// Scan /isos against the metadata catalog and self-heal drift, without a snapshot
+    Reconcile {
+        /// Device path or mount point
+        device: PathBuf,
+    },
 }
 
 #[derive(Subcommand)]
@@ -252,4 +517,8 @@ pub enum WipePattern {
     Dod,
     /// Gutmann method (35 passes, paranoid level)
     Gutmann,
+    /// Block-discard/firmware sanitize (BLKDISCARD/BLKSECDISCARD) instead of
+    /// overwriting; fastest and best for SSDs/flash, falls back to a random
+    /// overwrite if the device doesn't support it
+    HardwareSecure,
 }
\ No newline at end of file
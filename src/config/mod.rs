@@ -13,6 +13,12 @@ pub struct AppConfig {
     pub verify_checksums: bool,
     pub theme: String,
     pub log_level: String,
+    /// Data-partition filesystem used by `format` when `--data-fs` is not
+    /// given; one of "ext4", "exfat", or "btrfs".
+    pub default_data_filesystem: String,
+    /// Size in MB of the `persistence`/`casper-rw` partition `format` carves
+    /// out when `--persistence` is passed without `--persistence-size-mb`.
+    pub default_persistence_size_mb: u64,
 }
 
 impl Default for AppConfig {
@@ -25,6 +31,8 @@ impl Default for AppConfig {
             verify_checksums: true,
             theme: "default".to_string(),
             log_level: "info".to_string(),
+            default_data_filesystem: "ext4".to_string(),
+            default_persistence_size_mb: 1024,
         }
     }
 }
@@ -1,10 +1,31 @@
 use crate::{Result, UsbBootHutError};
 use secrecy::{ExposeSecret, SecretString};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::io::Write;
 use zeroize::Zeroize;
 
+/// Which kind of key material a LUKS format/open/add-key operation
+/// authenticates with: an interactive passphrase (fed to `cryptsetup` on
+/// stdin) or a key file (passed as `cryptsetup --key-file <path>`, or as
+/// the new-key positional argument to `luksAddKey`). Threading this through
+/// `LuksManager` instead of a bare `&str` keeps the two cryptsetup calling
+/// conventions in one place rather than having every caller re-derive them.
+#[derive(Debug, Clone)]
+pub enum LuksKeySource {
+    Passphrase(String),
+    KeyFile(PathBuf),
+}
+
+impl LuksKeySource {
+    fn as_path(&self) -> Option<&Path> {
+        match self {
+            Self::Passphrase(_) => None,
+            Self::KeyFile(path) => Some(path),
+        }
+    }
+}
+
 pub struct LuksManager {
     iter_time: u32, // milliseconds for key derivation
 }
@@ -15,129 +36,190 @@ impl LuksManager {
             iter_time: 5000, // 5 seconds
         }
     }
-    
-    pub fn create_encrypted_partition(&self, device: &Path, passphrase: &str) -> Result<()> {
-        // Validate passphrase strength
-        self.validate_passphrase(passphrase)?;
-        
+
+    pub fn create_encrypted_partition(&self, device: &Path, key: &LuksKeySource) -> Result<()> {
+        if let LuksKeySource::Passphrase(passphrase) = key {
+            self.validate_passphrase(passphrase)?;
+        }
+
+        let mut args = vec![
+            "luksFormat".to_string(),
+            "--type".to_string(), "luks2".to_string(),
+            "--cipher".to_string(), "aes-xts-plain64".to_string(),
+            "--key-size".to_string(), "512".to_string(),
+            "--hash".to_string(), "sha256".to_string(),
+            "--pbkdf".to_string(), "argon2id".to_string(),
+            "--iter-time".to_string(), self.iter_time.to_string(),
+            "--use-random".to_string(),
+        ];
+        match key {
+            LuksKeySource::Passphrase(_) => args.push("--verify-passphrase".to_string()),
+            LuksKeySource::KeyFile(path) => {
+                args.push("--key-file".to_string());
+                args.push(path.to_str().unwrap().to_string());
+            }
+        }
+        args.push(device.to_str().unwrap().to_string());
+
         // Create LUKS2 container
         let mut child = Command::new("cryptsetup")
-            .args([
-                "luksFormat",
-                "--type", "luks2",
-                "--cipher", "aes-xts-plain64",
-                "--key-size", "512",
-                "--hash", "sha256",
-                "--pbkdf", "argon2id",
-                "--iter-time", &self.iter_time.to_string(),
-                "--use-random",
-                "--verify-passphrase",
-                device.to_str().unwrap(),
-            ])
+            .args(&args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| UsbBootHutError::Encryption(format!("Failed to run cryptsetup: {}", e)))?;
-            
-        // Write passphrase twice (for verification)
-        if let Some(mut stdin) = child.stdin.take() {
-            // Create a mutable copy for zeroization
-            let mut pass_bytes = format!("{}\n{}\n", passphrase, passphrase).into_bytes();
-            stdin.write_all(&pass_bytes)
-                .map_err(|e| UsbBootHutError::Encryption(format!("Failed to write passphrase: {}", e)))?;
-            pass_bytes.zeroize();
+
+        // A passphrase is written twice (for verification); a key file is
+        // read straight off disk via `--key-file`, so stdin stays untouched.
+        if let LuksKeySource::Passphrase(passphrase) = key {
+            if let Some(mut stdin) = child.stdin.take() {
+                let mut pass_bytes = format!("{}\n{}\n", passphrase, passphrase).into_bytes();
+                stdin.write_all(&pass_bytes)
+                    .map_err(|e| UsbBootHutError::Encryption(format!("Failed to write passphrase: {}", e)))?;
+                pass_bytes.zeroize();
+            }
         }
-        
+
         let output = child.wait_with_output()
             .map_err(|e| UsbBootHutError::Encryption(format!("cryptsetup failed: {}", e)))?;
-            
+
         if !output.status.success() {
             return Err(UsbBootHutError::Encryption(
                 format!("Failed to create LUKS container: {}", String::from_utf8_lossy(&output.stderr))
             ));
         }
-        
+
         Ok(())
     }
-    
-    pub fn open_encrypted_partition(&self, device: &Path, passphrase: &str, name: &str) -> Result<()> {
+
+    pub fn open_encrypted_partition(&self, device: &Path, key: &LuksKeySource, name: &str) -> Result<()> {
+        let mut args = vec!["luksOpen".to_string(), device.to_str().unwrap().to_string(), name.to_string()];
+        if let Some(path) = key.as_path() {
+            args.push("--key-file".to_string());
+            args.push(path.to_str().unwrap().to_string());
+        }
+
         let mut child = Command::new("cryptsetup")
-            .args([
-                "luksOpen",
-                device.to_str().unwrap(),
-                name,
-            ])
+            .args(&args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| UsbBootHutError::Encryption(format!("Failed to run cryptsetup: {}", e)))?;
-            
-        if let Some(mut stdin) = child.stdin.take() {
-            let mut pass_bytes = format!("{}\n", passphrase).into_bytes();
-            stdin.write_all(&pass_bytes)
-                .map_err(|e| UsbBootHutError::Encryption(format!("Failed to write passphrase: {}", e)))?;
-            pass_bytes.zeroize();
+
+        if let LuksKeySource::Passphrase(passphrase) = key {
+            if let Some(mut stdin) = child.stdin.take() {
+                let mut pass_bytes = format!("{}\n", passphrase).into_bytes();
+                stdin.write_all(&pass_bytes)
+                    .map_err(|e| UsbBootHutError::Encryption(format!("Failed to write passphrase: {}", e)))?;
+                pass_bytes.zeroize();
+            }
         }
-        
+
         let output = child.wait_with_output()
             .map_err(|e| UsbBootHutError::Encryption(format!("cryptsetup failed: {}", e)))?;
-            
+
         if !output.status.success() {
             return Err(UsbBootHutError::Encryption(
                 format!("Failed to open LUKS container: {}", String::from_utf8_lossy(&output.stderr))
             ));
         }
-        
+
         Ok(())
     }
-    
+
     pub fn close_encrypted_partition(&self, name: &str) -> Result<()> {
         let output = Command::new("cryptsetup")
             .args(["luksClose", name])
             .output()
             .map_err(|e| UsbBootHutError::Encryption(format!("Failed to run cryptsetup: {}", e)))?;
-            
+
         if !output.status.success() {
             return Err(UsbBootHutError::Encryption(
                 format!("Failed to close LUKS container: {}", String::from_utf8_lossy(&output.stderr))
             ));
         }
-        
+
         Ok(())
     }
-    
-    pub fn add_key_slot(&self, device: &Path, current_pass: &str, new_pass: &str) -> Result<()> {
-        self.validate_passphrase(new_pass)?;
-        
+
+    /// Returns `device`'s LUKS UUID (`cryptsetup luksUUID`), e.g. to embed
+    /// in a GRUB `cryptomount -u <uuid>` preamble for an encrypted `/boot`.
+    pub fn luks_uuid(&self, device: &Path) -> Result<String> {
+        let output = Command::new("cryptsetup")
+            .args(["luksUUID", device.to_str().unwrap()])
+            .output()
+            .map_err(|e| UsbBootHutError::Encryption(format!("Failed to run cryptsetup: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::Encryption(
+                format!("Failed to get LUKS UUID: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Adds `new` as another key slot, authenticating with `current` --
+    /// the pattern a dual-unlock (passphrase + key file) setup uses to add
+    /// a key file to slot 1 after `create_encrypted_partition` already put
+    /// a passphrase in slot 0.
+    pub fn add_key_slot(&self, device: &Path, current: &LuksKeySource, new: &LuksKeySource) -> Result<()> {
+        if let LuksKeySource::Passphrase(new_pass) = new {
+            self.validate_passphrase(new_pass)?;
+        }
+
+        let mut args = vec!["luksAddKey".to_string(), device.to_str().unwrap().to_string()];
+        // A new key file is cryptsetup's optional positional argument; a
+        // new passphrase is instead typed (and confirmed) on stdin below.
+        if let LuksKeySource::KeyFile(path) = new {
+            args.push(path.to_str().unwrap().to_string());
+        }
+        // The *existing* key authenticates via `--key-file` if it's a key
+        // file, or stdin (ahead of the new key material) if a passphrase.
+        if let Some(path) = current.as_path() {
+            args.push("--key-file".to_string());
+            args.push(path.to_str().unwrap().to_string());
+        }
+
         let mut child = Command::new("cryptsetup")
-            .args([
-                "luksAddKey",
-                device.to_str().unwrap(),
-            ])
+            .args(&args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| UsbBootHutError::Encryption(format!("Failed to run cryptsetup: {}", e)))?;
-            
-        if let Some(mut stdin) = child.stdin.take() {
-            let mut pass_bytes = format!("{}\n{}\n{}\n", current_pass, new_pass, new_pass).into_bytes();
-            stdin.write_all(&pass_bytes)
-                .map_err(|e| UsbBootHutError::Encryption(format!("Failed to write passphrases: {}", e)))?;
-            pass_bytes.zeroize();
+
+        let mut stdin_payload = String::new();
+        if let LuksKeySource::Passphrase(current_pass) = current {
+            stdin_payload.push_str(current_pass);
+            stdin_payload.push('\n');
         }
-        
+        if let LuksKeySource::Passphrase(new_pass) = new {
+            stdin_payload.push_str(new_pass);
+            stdin_payload.push('\n');
+            stdin_payload.push_str(new_pass);
+            stdin_payload.push('\n');
+        }
+        if !stdin_payload.is_empty() {
+            if let Some(mut stdin) = child.stdin.take() {
+                let mut bytes = stdin_payload.into_bytes();
+                stdin.write_all(&bytes)
+                    .map_err(|e| UsbBootHutError::Encryption(format!("Failed to write passphrases: {}", e)))?;
+                bytes.zeroize();
+            }
+        }
+
         let output = child.wait_with_output()
             .map_err(|e| UsbBootHutError::Encryption(format!("cryptsetup failed: {}", e)))?;
-            
+
         if !output.status.success() {
             return Err(UsbBootHutError::Encryption(
                 format!("Failed to add key: {}", String::from_utf8_lossy(&output.stderr))
             ));
         }
-        
+
         Ok(())
     }
     
@@ -230,4 +312,147 @@ impl Drop for SecurePassphrase {
     fn drop(&mut self) {
         // SecretString already handles zeroization
     }
+}
+
+/// Prompts for and holds the two secrets `unlock`/`lock` need up front — a
+/// sudo password (for the `mount`/`cryptsetup` calls that require root) and
+/// the LUKS passphrase — so the user supplies each once instead of being
+/// re-prompted per privileged subcommand. Both are zeroized on drop via
+/// `SecretString`.
+pub struct PasswordHolder {
+    sudo_password: SecretString,
+    luks_passphrase: Option<SecretString>,
+}
+
+impl PasswordHolder {
+    /// Prompts for a sudo password only, for flows like `lock` that never
+    /// need the LUKS passphrase (`cryptsetup luksClose` only takes a mapper
+    /// name).
+    pub fn prompt_sudo_only() -> Result<Self> {
+        use dialoguer::Password;
+
+        let sudo_password = Password::new()
+            .with_prompt("Sudo password")
+            .interact()
+            .map_err(|e| UsbBootHutError::Dialog(e.to_string()))?;
+
+        Ok(Self {
+            sudo_password: SecretString::new(sudo_password),
+            luks_passphrase: None,
+        })
+    }
+
+    /// Prompts for both the sudo password and the LUKS passphrase up front.
+    pub fn prompt(luks_prompt: &str) -> Result<Self> {
+        let mut holder = Self::prompt_sudo_only()?;
+
+        let luks_passphrase = dialoguer::Password::new()
+            .with_prompt(luks_prompt)
+            .interact()
+            .map_err(|e| UsbBootHutError::Dialog(e.to_string()))?;
+        holder.luks_passphrase = Some(SecretString::new(luks_passphrase));
+
+        Ok(holder)
+    }
+
+    pub fn luks_passphrase(&self) -> &str {
+        self.luks_passphrase.as_ref()
+            .expect("luks passphrase was not prompted for")
+            .expose_secret()
+    }
+
+    /// Runs `program` with `args` under `sudo -S`, feeding the held sudo
+    /// password on stdin (followed by `extra_stdin`, if any — e.g. a LUKS
+    /// passphrase `cryptsetup` also reads from stdin) so neither `sudo` nor
+    /// the wrapped program prompts a second time.
+    pub fn run_sudo(&self, program: &str, args: &[&str], extra_stdin: Option<&str>) -> Result<()> {
+        let mut child = Command::new("sudo")
+            .arg("-S")
+            .arg(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| UsbBootHutError::Permission(format!("Failed to run sudo {}: {}", program, e)))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let mut payload = format!("{}\n", self.sudo_password.expose_secret());
+            if let Some(extra) = extra_stdin {
+                payload.push_str(extra);
+                payload.push('\n');
+            }
+            let mut bytes = payload.into_bytes();
+            stdin.write_all(&bytes)
+                .map_err(|e| UsbBootHutError::Permission(format!("Failed to write to sudo {}: {}", program, e)))?;
+            bytes.zeroize();
+        }
+
+        let output = child.wait_with_output()
+            .map_err(|e| UsbBootHutError::Permission(format!("sudo {} failed: {}", program, e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::Permission(
+                format!("sudo {} failed: {}", program, String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves the deterministic mapper name and mount target for `device_path`
+/// from its GPT partition label or UUID (falling back to the UUID when no
+/// label was set), so repeated `unlock` calls against the same partition
+/// always land on the same `/dev/mapper/...` name and mount point.
+/// Derives the deterministic `/dev/mapper` name and default mount point for
+/// `device_path` from its GPT partition label (falling back to its UUID).
+/// Shared by `unlock` and `lock` so both agree on the same mapper/mount
+/// target without either having to be told it explicitly.
+pub fn resolve_mapper_identity(device_path: &Path) -> Result<(String, PathBuf)> {
+    use crate::disk::enumerate_usb_devices;
+
+    let devices = enumerate_usb_devices()?;
+    let partition = devices.iter()
+        .flat_map(|d| d.partitions.iter())
+        .find(|p| p.path == device_path)
+        .ok_or_else(|| UsbBootHutError::Device(format!("Partition not found: {}", device_path.display())))?;
+
+    let identity = partition.label.clone()
+        .or_else(|| partition.uuid.clone())
+        .ok_or_else(|| UsbBootHutError::Device(
+            format!("{} has no label or UUID to derive a mapper name from", device_path.display())
+        ))?;
+    let identity = sanitize_identity(&identity);
+
+    let mapper_name = format!("usb_boot_hut_{}", identity);
+    let mount_target = PathBuf::from("/run/media").join(invoking_user()).join(identity);
+
+    Ok((mapper_name, mount_target))
+}
+
+/// Preflight check for `unlock`: fails fast if another mapping is already
+/// sitting on the name we're about to claim, instead of letting
+/// `cryptsetup luksOpen` fail with a less obvious error later.
+pub fn ensure_mapper_available(mapper_name: &str) -> Result<()> {
+    if Path::new(&format!("/dev/mapper/{}", mapper_name)).exists() {
+        return Err(UsbBootHutError::Encryption(format!("Mapper name '{}' is already in use", mapper_name)));
+    }
+    Ok(())
+}
+
+/// Strips anything that isn't safe in a `/dev/mapper/<name>` or mount-point
+/// path component out of a partition label/UUID.
+fn sanitize_identity(identity: &str) -> String {
+    identity.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// The user `unlock`/`lock` should mount under in `/run/media/<user>`, even
+/// though the process itself runs as root via `sudo`.
+fn invoking_user() -> String {
+    std::env::var("SUDO_USER")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_else(|_| "root".to_string())
 }
\ No newline at end of file
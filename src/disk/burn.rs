@@ -2,13 +2,70 @@ use crate::{Result, UsbBootHutError};
 use std::path::{Path, PathBuf};
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write, Seek, SeekFrom, BufReader, BufWriter};
+use std::sync::Mutex;
+use std::process::Command;
 use indicatif::{ProgressBar, ProgressStyle};
-use flate2::read::GzDecoder;
+use sha2::{Sha256, Sha512, Digest};
+
+/// Which digest to hash the source image with. SHA256 is the default
+/// coreos-installer-style checksum; SHA512 is offered for images that only
+/// publish a SHA512SUMS file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgo {
+    Sha256,
+    Sha512,
+}
+
+/// A single-pass streaming hasher covering both algorithms `ImageBurner`
+/// supports, so `burn()`/`verify()` don't need to duplicate their read loop
+/// per algorithm.
+enum StreamHasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl StreamHasher {
+    fn new(algo: ChecksumAlgo) -> Self {
+        match algo {
+            ChecksumAlgo::Sha256 => StreamHasher::Sha256(Sha256::new()),
+            ChecksumAlgo::Sha512 => StreamHasher::Sha512(Sha512::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            StreamHasher::Sha256(h) => h.update(data),
+            StreamHasher::Sha512(h) => h.update(data),
+        }
+    }
+
+    fn finalize_hex(self) -> String {
+        match self {
+            StreamHasher::Sha256(h) => hex::encode(h.finalize()),
+            StreamHasher::Sha512(h) => hex::encode(h.finalize()),
+        }
+    }
+}
 
 pub struct ImageBurner {
     source_path: PathBuf,
     target_device: PathBuf,
     buffer_size: usize,
+    checksum_algo: ChecksumAlgo,
+    expected_checksum: Option<String>,
+    signature_path: Option<PathBuf>,
+    trusted_keyring: Option<PathBuf>,
+    /// Overrides the preflight guard in `WritePreflight`: lets a mounted,
+    /// read-only, or apparent-system device be written to anyway.
+    force: bool,
+    /// Digest of the decompressed source stream, computed while burning so
+    /// `verify()` can compare a device read-back against it without having
+    /// to decompress the source a second time.
+    source_checksum: Mutex<Option<String>>,
+    /// True decompressed byte count observed while burning, counted as the
+    /// stream was read rather than trusted from a header. `verify()` needs
+    /// this to know exactly how many bytes to read back from the device.
+    source_byte_count: Mutex<Option<u64>>,
 }
 
 impl ImageBurner {
@@ -17,44 +74,67 @@ impl ImageBurner {
             source_path: source.to_path_buf(),
             target_device: target.to_path_buf(),
             buffer_size: 4 * 1024 * 1024, // 4MB buffer
+            checksum_algo: ChecksumAlgo::Sha256,
+            expected_checksum: None,
+            signature_path: None,
+            trusted_keyring: None,
+            force: false,
+            source_checksum: Mutex::new(None),
+            source_byte_count: Mutex::new(None),
         }
     }
-    
+
+    /// Requires the decompressed source to hash to `expected_hex` under `algo`
+    /// before the burn is considered successful, coreos-installer-style.
+    pub fn with_checksum(mut self, algo: ChecksumAlgo, expected_hex: String) -> Self {
+        self.checksum_algo = algo;
+        self.expected_checksum = Some(expected_hex.to_lowercase());
+        self
+    }
+
+    /// Verifies `signature_path` (a detached `.asc`/`.sig`) against the source
+    /// image before burning. `trusted_keyring` pins `gpg` to a specific
+    /// keyring instead of the user's default one.
+    pub fn with_signature(mut self, signature_path: PathBuf, trusted_keyring: Option<PathBuf>) -> Self {
+        self.signature_path = Some(signature_path);
+        self.trusted_keyring = trusted_keyring;
+        self
+    }
+
+    /// Overrides the preflight device guard, allowing a burn to proceed
+    /// against a mounted, kernel-read-only, or apparent-system device.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
     pub fn burn(&self) -> Result<()> {
+        crate::disk::WritePreflight::new(self.force).check(&self.target_device)?;
+
+        if let Some(signature_path) = &self.signature_path {
+            self.verify_signature(signature_path)?;
+        }
+
         let source_size = self.get_source_size()?;
         let target_size = self.get_device_size()?;
-        
-        if source_size > target_size {
-            return Err(UsbBootHutError::Device(
-                format!("Image too large: {} bytes, device only {} bytes", 
-                    source_size, target_size)
-            ));
+
+        // Formats with no upfront size (gzip/bzip2/xz/zstd streams) can't be
+        // preflighted against the device capacity; `write_all` below will
+        // fail loudly if they turn out to be too large.
+        if let Some(source_size) = source_size {
+            if source_size > target_size {
+                return Err(UsbBootHutError::Device(
+                    format!("Image too large: {} bytes, device only {} bytes",
+                        source_size, target_size)
+                ));
+            }
         }
-        
-        // Create progress bar
-        let pb = ProgressBar::new(source_size);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("[{elapsed_precise}] {bar:40.cyan/blue} {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
-                .unwrap()
-                .progress_chars("█▓▒░ ")
-        );
-        pb.set_message("Burning image");
-        
-        // Open source
-        let source = File::open(&self.source_path)
-            .map_err(|e| UsbBootHutError::Io(e))?;
-            
-        let mut reader: Box<dyn Read> = if self.source_path.to_string_lossy().ends_with(".gz") {
-            Box::new(GzDecoder::new(source))
-        } else if self.source_path.to_string_lossy().ends_with(".xz") {
-            return Err(UsbBootHutError::Device(
-                "XZ decompression not yet implemented. Please decompress the image first.".to_string()
-            ));
-        } else {
-            Box::new(source)
-        };
-        
+
+        let pb = self.create_progress_bar(source_size, "Burning image");
+
+        // Open source, transparently decompressing zstd/bzip2/gzip/xz/CISO/WBFS images
+        let mut reader = crate::iso::open_image(&self.source_path)?;
+
         // Open target device
         let mut target = OpenOptions::new()
             .write(true)
@@ -62,127 +142,232 @@ impl ImageBurner {
             .map_err(|e| UsbBootHutError::Device(
                 format!("Failed to open device for writing: {}", e)
             ))?;
-            
-        // Burn the image
+
+        // Preallocate so trailing zero blocks we skip below still leave the
+        // target at its final length if it's a regular file; block devices
+        // reject this (ENOTTY) and already have a fixed size, so ignore errors.
+        let _ = target.set_len(source_size.unwrap_or(target_size));
+
+        // On a block device, discard it before the sparse-write loop below
+        // starts seeking over zero blocks instead of writing them -- without
+        // this, a previously-used drive leaves whatever stale bytes it had
+        // under every skipped region, which is both a data-remnant leak and
+        // a source of spurious readback mismatches in verify(). Best-effort:
+        // not every USB stick supports BLKDISCARD/BLKSECDISCARD.
+        if is_block_device(&self.target_device) {
+            let _ = crate::disk::SecureWipe::new(&self.target_device).hardware_erase();
+        }
+
+        // Burn the image, hashing the decompressed stream as it's written so
+        // verification doesn't need to decompress the source a second time.
+        // Runs of zero bytes are skipped with a seek instead of written,
+        // which is dramatically faster for sparse disk images.
+        let mut hasher = StreamHasher::new(self.checksum_algo);
         let mut buffer = vec![0u8; self.buffer_size];
         let mut total_written = 0u64;
-        
+
         loop {
             let bytes_read = reader.read(&mut buffer)
                 .map_err(|e| UsbBootHutError::Io(e))?;
-                
+
             if bytes_read == 0 {
                 break;
             }
-            
-            target.write_all(&buffer[..bytes_read])
-                .map_err(|e| UsbBootHutError::Device(
-                    format!("Failed to write to device: {}", e)
-                ))?;
-                
+
+            let block = &buffer[..bytes_read];
+            if is_all_zero(block) {
+                target.seek(SeekFrom::Current(bytes_read as i64))
+                    .map_err(|e| UsbBootHutError::Device(
+                        format!("Failed to seek over zero block: {}", e)
+                    ))?;
+            } else {
+                target.write_all(block)
+                    .map_err(|e| UsbBootHutError::Device(
+                        format!("Failed to write to device: {}", e)
+                    ))?;
+            }
+            hasher.update(block);
+
             total_written += bytes_read as u64;
             pb.set_position(total_written);
         }
-        
+
         // Sync to ensure all data is written
         target.sync_all()
             .map_err(|e| UsbBootHutError::Device(
                 format!("Failed to sync data: {}", e)
             ))?;
-            
+
         pb.finish_with_message("Image burned successfully");
-        
+
+        let actual_checksum = hasher.finalize_hex();
+
+        if let Some(expected) = &self.expected_checksum {
+            if !actual_checksum.eq_ignore_ascii_case(expected) {
+                return Err(UsbBootHutError::SourceDigestMismatch {
+                    expected: expected.clone(),
+                    actual: actual_checksum,
+                });
+            }
+        }
+
+        *self.source_checksum.lock().unwrap() = Some(actual_checksum);
+        *self.source_byte_count.lock().unwrap() = Some(total_written);
+
         Ok(())
     }
-    
-    pub fn verify(&self) -> Result<bool> {
+
+    /// Verifies a burn by reading the image back off the target device and
+    /// comparing digests rather than buffering both sides in lockstep. If
+    /// this `ImageBurner` just performed the burn, the source digest and
+    /// byte count computed in `burn()` are reused; otherwise the source is
+    /// re-hashed to recover both (compressed formats don't reveal their
+    /// decompressed size without being fully read).
+    pub fn verify(&self) -> Result<()> {
         println!("Verifying burned image...");
-        
-        let source_size = self.get_source_size()?;
-        
-        // Create progress bar
-        let pb = ProgressBar::new(source_size);
+
+        let cached = self.source_checksum.lock().unwrap().clone()
+            .zip(*self.source_byte_count.lock().unwrap());
+        let (source_checksum, source_size) = match cached {
+            Some(pair) => pair,
+            None => self.checksum_source()?,
+        };
+
+        // Re-open target and hash exactly `source_size` bytes read back from it.
+        let mut target = File::open(&self.target_device)
+            .map_err(|e| UsbBootHutError::Device(
+                format!("Failed to open device for verification: {}", e)
+            ))?;
+
+        let pb = self.create_progress_bar(Some(source_size), "Verifying");
         pb.set_style(
             ProgressStyle::default_bar()
                 .template("[{elapsed_precise}] {bar:40.green/red} {bytes}/{total_bytes} ({bytes_per_sec})")
                 .unwrap()
                 .progress_chars("█▓▒░ ")
         );
-        pb.set_message("Verifying");
-        
-        // Open source
-        let source = File::open(&self.source_path)
-            .map_err(|e| UsbBootHutError::Io(e))?;
-            
-        let mut source_reader: Box<dyn Read> = if self.source_path.to_string_lossy().ends_with(".gz") {
-            Box::new(GzDecoder::new(source))
-        } else {
-            Box::new(source)
-        };
-        
-        // Open target
-        let mut target = File::open(&self.target_device)
-            .map_err(|e| UsbBootHutError::Device(
-                format!("Failed to open device for verification: {}", e)
-            ))?;
-            
-        // Compare data
-        let mut source_buffer = vec![0u8; self.buffer_size];
-        let mut target_buffer = vec![0u8; self.buffer_size];
-        let mut total_verified = 0u64;
-        
-        loop {
-            let source_bytes = source_reader.read(&mut source_buffer)
-                .map_err(|e| UsbBootHutError::Io(e))?;
-                
-            if source_bytes == 0 {
-                break;
-            }
-            
-            let target_bytes = target.read(&mut target_buffer[..source_bytes])
+
+        let mut hasher = StreamHasher::new(self.checksum_algo);
+        let mut buffer = vec![0u8; self.buffer_size];
+        let mut remaining = source_size;
+
+        while remaining > 0 {
+            let to_read = remaining.min(buffer.len() as u64) as usize;
+            target.read_exact(&mut buffer[..to_read])
                 .map_err(|e| UsbBootHutError::Device(
                     format!("Failed to read from device: {}", e)
                 ))?;
-                
-            if target_bytes != source_bytes {
-                pb.abandon();
-                return Ok(false);
-            }
-            
-            if source_buffer[..source_bytes] != target_buffer[..source_bytes] {
-                pb.abandon();
-                return Ok(false);
-            }
-            
-            total_verified += source_bytes as u64;
-            pb.set_position(total_verified);
+            hasher.update(&buffer[..to_read]);
+            remaining -= to_read as u64;
+            pb.set_position(source_size - remaining);
         }
-        
+
+        let target_checksum = hasher.finalize_hex();
+
+        if target_checksum != source_checksum {
+            pb.abandon();
+            return Err(UsbBootHutError::DeviceReadbackMismatch {
+                expected: source_checksum,
+                actual: target_checksum,
+            });
+        }
+
         pb.finish_with_message("Verification complete");
-        Ok(true)
+        Ok(())
     }
-    
-    fn get_source_size(&self) -> Result<u64> {
-        let metadata = std::fs::metadata(&self.source_path)
-            .map_err(|e| UsbBootHutError::Io(e))?;
-            
-        if self.source_path.to_string_lossy().ends_with(".gz") {
-            // For gzipped files, we need to read the uncompressed size
-            // This is stored in the last 4 bytes of the file
-            let mut file = File::open(&self.source_path)
-                .map_err(|e| UsbBootHutError::Io(e))?;
-                
-            file.seek(SeekFrom::End(-4))
-                .map_err(|e| UsbBootHutError::Io(e))?;
-                
-            let mut size_bytes = [0u8; 4];
-            file.read_exact(&mut size_bytes)
+
+    /// Hashes the decompressed source image, returning both the digest and
+    /// the true decompressed byte count. Used when `verify()` is called on
+    /// an `ImageBurner` that didn't just perform the burn itself (e.g. a
+    /// fresh instance built for re-verification).
+    fn checksum_source(&self) -> Result<(String, u64)> {
+        let pb = self.create_progress_bar(self.get_source_size()?, "Checksumming source");
+
+        let mut reader = crate::iso::open_image(&self.source_path)?;
+        let mut hasher = StreamHasher::new(self.checksum_algo);
+        let mut buffer = vec![0u8; self.buffer_size];
+        let mut total = 0u64;
+
+        loop {
+            let bytes_read = reader.read(&mut buffer)
                 .map_err(|e| UsbBootHutError::Io(e))?;
-                
-            Ok(u32::from_le_bytes(size_bytes) as u64)
-        } else {
-            Ok(metadata.len())
+            if bytes_read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..bytes_read]);
+            total += bytes_read as u64;
+            pb.set_position(total);
+        }
+
+        pb.finish_and_clear();
+        Ok((hasher.finalize_hex(), total))
+    }
+
+    /// Builds a progress bar for a streaming pass over `total` bytes, or an
+    /// indeterminate spinner when `total` is `None` — the case for gzip/
+    /// bzip2/xz/zstd sources, whose decompressed size isn't known upfront.
+    fn create_progress_bar(&self, total: Option<u64>, message: &'static str) -> ProgressBar {
+        let pb = match total {
+            Some(total) => {
+                let pb = ProgressBar::new(total);
+                pb.set_style(
+                    ProgressStyle::default_bar()
+                        .template("[{elapsed_precise}] {bar:40.cyan/blue} {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                        .unwrap()
+                        .progress_chars("█▓▒░ ")
+                );
+                pb
+            }
+            None => {
+                let pb = ProgressBar::new_spinner();
+                pb.set_style(
+                    ProgressStyle::default_spinner()
+                        .template("{spinner:.cyan} {msg} ({bytes}, {bytes_per_sec})")
+                        .unwrap()
+                );
+                pb
+            }
+        };
+        pb.set_message(message);
+        pb
+    }
+
+    /// Verifies `signature_path` against `self.source_path` with `gpg
+    /// --verify`, pinning a specific keyring if one was configured.
+    fn verify_signature(&self, signature_path: &Path) -> Result<()> {
+        println!("🔏 Verifying source image signature...");
+
+        let mut cmd = Command::new("gpg");
+        cmd.arg("--batch");
+
+        if let Some(keyring) = &self.trusted_keyring {
+            cmd.args(["--no-default-keyring", "--keyring"]).arg(keyring);
+        }
+
+        cmd.arg("--verify")
+            .arg(signature_path)
+            .arg(&self.source_path);
+
+        let output = cmd.output()
+            .map_err(|e| UsbBootHutError::SignatureVerification(format!("Failed to run gpg: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::SignatureVerification(
+                format!("Image signature did not verify: {}", String::from_utf8_lossy(&output.stderr))
+            ));
         }
+
+        println!("✅ Signature verified");
+        Ok(())
+    }
+
+    /// Best-effort decompressed size of the source, sniffed from its magic
+    /// bytes rather than its extension. `None` for gzip/bzip2/xz/zstd
+    /// streams: their true size isn't known until fully decompressed (the
+    /// gzip trailer's ISIZE is a modulo-2^32 count and lies for images over
+    /// 4 GiB, so it's not trusted here).
+    fn get_source_size(&self) -> Result<Option<u64>> {
+        crate::iso::known_image_size(&self.source_path)
     }
     
     fn get_device_size(&self) -> Result<u64> {
@@ -316,4 +501,17 @@ fn get_mount_point(partition: &Path) -> Result<PathBuf> {
     }
     
     Err(UsbBootHutError::Device("Partition not mounted".to_string()))
+}
+
+/// Whether `block` is entirely zero bytes, used to decide if a chunk of the
+/// burn can be skipped with a seek instead of written.
+fn is_all_zero(block: &[u8]) -> bool {
+    block.iter().all(|&b| b == 0)
+}
+
+/// Whether `path` names a block device, so the sparse-write loop only
+/// attempts a discard against a real drive, not a regular-file image target.
+fn is_block_device(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).map(|m| m.file_type().is_block_device()).unwrap_or(false)
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
 use crate::{Result, UsbBootHutError};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +11,18 @@ pub struct UsbDevice {
     pub vendor: String,
     pub removable: bool,
     pub partitions: Vec<Partition>,
+    pub bus: BusType,
+    pub rotational: Option<bool>,
+    pub serial: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BusType {
+    Usb,
+    Sata,
+    Nvme,
+    Sd,
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,17 +42,102 @@ impl UsbDevice {
                 "Device is not removable".to_string()
             ));
         }
-        
+
         if self.size < crate::MIN_DRIVE_SIZE {
             return Err(UsbBootHutError::Device(
-                format!("Device too small: {} bytes (minimum: {} bytes)", 
+                format!("Device too small: {} bytes (minimum: {} bytes)",
                     self.size, crate::MIN_DRIVE_SIZE)
             ));
         }
-        
+
+        let mounted = self.mounted_partitions()?;
+        if !mounted.is_empty() {
+            return Err(UsbBootHutError::Device(format!(
+                "Device is in use (mounted): {}",
+                mounted.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+            )));
+        }
+
         Ok(())
     }
-    
+
+    /// Returns the paths of the whole disk or any of its partitions that are
+    /// currently mounted, so callers can refuse to touch an in-use device.
+    #[cfg(target_os = "linux")]
+    pub fn mounted_partitions(&self) -> Result<Vec<PathBuf>> {
+        use std::os::unix::fs::MetadataExt;
+
+        let mounted_devnos = linux_mounted_device_numbers()?;
+        let mut mounted = Vec::new();
+
+        let mut candidates: Vec<&PathBuf> = vec![&self.path];
+        candidates.extend(self.partitions.iter().map(|p| &p.path));
+
+        for path in candidates {
+            if let Ok(metadata) = std::fs::metadata(path) {
+                if mounted_devnos.contains(&metadata.rdev()) {
+                    mounted.push(path.clone());
+                }
+            }
+        }
+
+        Ok(mounted)
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn mounted_partitions(&self) -> Result<Vec<PathBuf>> {
+        use std::process::Command;
+
+        let mut mounted = Vec::new();
+
+        let mut candidates: Vec<&PathBuf> = vec![&self.path];
+        candidates.extend(self.partitions.iter().map(|p| &p.path));
+
+        for path in candidates {
+            let output = Command::new("diskutil")
+                .args(["info", path.to_str().unwrap_or_default()])
+                .output()
+                .map_err(|e| UsbBootHutError::Device(format!("Failed to run diskutil: {}", e)))?;
+
+            if !output.status.success() {
+                continue;
+            }
+
+            let info = String::from_utf8_lossy(&output.stdout);
+            for line in info.lines() {
+                let line = line.trim();
+                if line.starts_with("Mounted:") && line.contains("Yes") {
+                    mounted.push(path.clone());
+                    break;
+                }
+            }
+        }
+
+        Ok(mounted)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    pub fn mounted_partitions(&self) -> Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    /// Warns when a device isn't removable but still looks like an internal
+    /// NVMe/SATA disk, so a future override flag doesn't silently target a
+    /// system drive without at least telling the user what it's doing.
+    pub fn internal_bus_warning(&self) -> Option<String> {
+        if self.removable {
+            return None;
+        }
+
+        match self.bus {
+            BusType::Nvme | BusType::Sata => Some(format!(
+                "{} is a non-removable {:?} disk; this is very likely a system drive",
+                self.path.display(), self.bus
+            )),
+            _ => None,
+        }
+    }
+
     pub fn has_system_files(&self) -> bool {
         // Check for signs this might be a system drive
         for partition in &self.partitions {
@@ -58,6 +155,55 @@ impl UsbDevice {
     }
 }
 
+/// Resolves a `--device`/partition argument given as `LABEL=`/`PARTLABEL=`/
+/// `UUID=` (the same specs `fstab`/`mount` accept, backed by the
+/// `/dev/disk/by-*` symlinks udev maintains) to the partition device node
+/// they point at, so `unlock`/`lock` aren't tied to a `/dev/sdXN` name that
+/// can shuffle between boots. A plain path comes back unchanged.
+pub fn resolve_device_path(spec: &Path) -> Result<PathBuf> {
+    let Some(spec_str) = spec.to_str() else { return Ok(spec.to_path_buf()) };
+
+    let (by_dir, name) = if let Some(label) = spec_str.strip_prefix("PARTLABEL=") {
+        ("/dev/disk/by-partlabel", label)
+    } else if let Some(label) = spec_str.strip_prefix("LABEL=") {
+        ("/dev/disk/by-label", label)
+    } else if let Some(uuid) = spec_str.strip_prefix("UUID=") {
+        ("/dev/disk/by-uuid", uuid)
+    } else {
+        return Ok(spec.to_path_buf());
+    };
+
+    let link = Path::new(by_dir).join(name);
+    std::fs::canonicalize(&link)
+        .map_err(|e| UsbBootHutError::Device(format!("No device found for {}: {}", spec_str, e)))
+}
+
+#[cfg(target_os = "linux")]
+fn linux_mounted_device_numbers() -> Result<std::collections::HashSet<u64>> {
+    use std::fs;
+
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo")
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to read mountinfo: {}", e)))?;
+
+    let mut devnos = std::collections::HashSet::new();
+
+    for line in mountinfo.lines() {
+        // The major:minor of the mounted filesystem's backing device is the
+        // 3rd whitespace-separated field of each line (after mount ID and
+        // parent ID).
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(major_minor) = fields.get(2) else { continue };
+
+        if let Some((major, minor)) = major_minor.split_once(':') {
+            if let (Ok(major), Ok(minor)) = (major.parse::<u32>(), minor.parse::<u32>()) {
+                devnos.insert(libc::makedev(major, minor) as u64);
+            }
+        }
+    }
+
+    Ok(devnos)
+}
+
 #[cfg(target_os = "linux")]
 pub fn enumerate_usb_devices() -> Result<Vec<UsbDevice>> {
     use std::fs;
@@ -111,10 +257,14 @@ pub fn enumerate_usb_devices() -> Result<Vec<UsbDevice>> {
             .unwrap_or_else(|_| "Unknown".to_string())
             .trim()
             .to_string();
-        
+
         // Get partitions
         let partitions = enumerate_partitions(&device_path)?;
-        
+
+        let rotational = read_rotational(&sys_path);
+        let bus = detect_bus(&sys_path);
+        let serial = read_serial(&sys_path);
+
         devices.push(UsbDevice {
             path: device_path,
             name: device_name_str.to_string(),
@@ -123,59 +273,169 @@ pub fn enumerate_usb_devices() -> Result<Vec<UsbDevice>> {
             vendor,
             removable,
             partitions,
+            bus,
+            rotational,
+            serial,
         });
     }
-    
+
     Ok(devices)
 }
 
+#[cfg(target_os = "linux")]
+fn read_rotational(sys_path: &Path) -> Option<bool> {
+    // 0 => SSD/flash, 1 => spinning
+    std::fs::read_to_string(sys_path.join("queue/rotational"))
+        .ok()
+        .and_then(|s| s.trim().parse::<u8>().ok())
+        .map(|v| v == 1)
+}
+
+#[cfg(target_os = "linux")]
+fn read_serial(sys_path: &Path) -> Option<String> {
+    std::fs::read_to_string(sys_path.join("device/serial"))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(target_os = "linux")]
+fn detect_bus(sys_path: &Path) -> BusType {
+    let canonical = std::fs::canonicalize(sys_path.join("device")).unwrap_or_default();
+    let path_str = canonical.to_string_lossy();
+
+    if path_str.contains("/usb") {
+        BusType::Usb
+    } else if path_str.contains("nvme") {
+        BusType::Nvme
+    } else if path_str.contains("/ata") || path_str.contains("/scsi") {
+        BusType::Sata
+    } else if path_str.contains("mmc") {
+        BusType::Sd
+    } else {
+        BusType::Unknown
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn enumerate_partitions(device_path: &Path) -> Result<Vec<Partition>> {
-    use std::process::Command;
-    
-    let output = Command::new("lsblk")
-        .args([
-            "-J", // JSON output
-            "-b", // Bytes
-            "-o", "NAME,SIZE,FSTYPE,LABEL,UUID,TYPE",
-            device_path.to_str().unwrap()
-        ])
-        .output()
-        .map_err(|e| UsbBootHutError::Device(format!("Failed to run lsblk: {}", e)))?;
-        
-    if !output.status.success() {
-        return Ok(Vec::new()); // Device might not have partitions yet
+    // Read the partition table directly from the block device rather than
+    // shelling out to lsblk, which may be unavailable or report stale info.
+    if let Some(partitions) = read_gpt_partitions(device_path)? {
+        return Ok(partitions);
     }
-    
-    // Parse lsblk JSON output
-    let json: serde_json::Value = serde_json::from_slice(&output.stdout)
-        .map_err(|e| UsbBootHutError::Device(format!("Failed to parse lsblk output: {}", e)))?;
-        
+
+    if let Some(partitions) = read_mbr_partitions(device_path)? {
+        return Ok(partitions);
+    }
+
+    Ok(Vec::new()) // No recognizable partition table yet
+}
+
+#[cfg(target_os = "linux")]
+fn read_gpt_partitions(device_path: &Path) -> Result<Option<Vec<Partition>>> {
+    use std::fs::File;
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = File::open(device_path)
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to open {}: {}", device_path.display(), e)))?;
+
+    // The protective MBR's first entry carries type 0xEE when a GPT follows.
+    let mut protective_mbr = [0u8; 512];
+    file.read_exact(&mut protective_mbr)
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to read MBR: {}", e)))?;
+
+    if protective_mbr[450] != 0xEE {
+        return Ok(None);
+    }
+
+    file.seek(SeekFrom::Start(0))
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to seek: {}", e)))?;
+
+    // Validates the "EFI PART" signature and header CRC32 internally, and
+    // reads the partition entry array at the LBA the header points to.
+    let gpt = gptman::GPT::find_from(&mut file)
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to read GPT: {}", e)))?;
+
     let mut partitions = Vec::new();
-    
-    if let Some(devices) = json["blockdevices"].as_array() {
-        for device in devices {
-            if let Some(children) = device["children"].as_array() {
-                for (idx, child) in children.iter().enumerate() {
-                    if child["type"].as_str() == Some("part") {
-                        let name = child["name"].as_str().unwrap_or("");
-                        partitions.push(Partition {
-                            path: PathBuf::from(format!("/dev/{}", name)),
-                            number: (idx + 1) as u32,
-                            size: child["size"].as_str()
-                                .and_then(|s| s.parse::<u64>().ok())
-                                .unwrap_or(0),
-                            filesystem: child["fstype"].as_str().map(String::from),
-                            label: child["label"].as_str().map(String::from),
-                            uuid: child["uuid"].as_str().map(String::from),
-                        });
-                    }
-                }
-            }
+
+    for (number, entry) in gpt.iter() {
+        if !entry.is_used() {
+            continue;
         }
+
+        let size = (entry.ending_lba - entry.starting_lba + 1) * gpt.sector_size;
+        let label = entry.partition_name.as_str().trim().to_string();
+
+        partitions.push(Partition {
+            path: partition_device_path(device_path, number),
+            number,
+            size,
+            filesystem: None,
+            label: if label.is_empty() { None } else { Some(label) },
+            uuid: Some(uuid::Uuid::from_bytes(entry.unique_partition_guid).to_string()),
+        });
+    }
+
+    Ok(Some(partitions))
+}
+
+#[cfg(target_os = "linux")]
+fn read_mbr_partitions(device_path: &Path) -> Result<Option<Vec<Partition>>> {
+    use std::fs::File;
+    use std::io::Read;
+
+    let mut file = File::open(device_path)
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to open {}: {}", device_path.display(), e)))?;
+
+    let mut mbr = [0u8; 512];
+    file.read_exact(&mut mbr)
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to read MBR: {}", e)))?;
+
+    if mbr[510] != 0x55 || mbr[511] != 0xAA {
+        return Ok(None); // No boot signature, nothing to parse
+    }
+
+    let mut partitions = Vec::new();
+
+    // Four fixed 16-byte partition entries starting at offset 0x1BE
+    for i in 0..4u32 {
+        let entry = &mbr[0x1BE + (i as usize) * 16..0x1BE + (i as usize + 1) * 16];
+        let partition_type = entry[4];
+
+        if partition_type == 0x00 {
+            continue; // Empty entry
+        }
+
+        let start_lba = u32::from_le_bytes(entry[8..12].try_into().unwrap()) as u64;
+        let sector_count = u32::from_le_bytes(entry[12..16].try_into().unwrap()) as u64;
+
+        if start_lba == 0 || sector_count == 0 {
+            continue;
+        }
+
+        partitions.push(Partition {
+            path: partition_device_path(device_path, i + 1),
+            number: i + 1,
+            size: sector_count * 512,
+            filesystem: None,
+            label: None,
+            uuid: None,
+        });
+    }
+
+    Ok(Some(partitions))
+}
+
+#[cfg(target_os = "linux")]
+fn partition_device_path(device_path: &Path, number: u32) -> PathBuf {
+    let device_str = device_path.to_string_lossy();
+
+    if device_str.contains("nvme") || device_str.contains("mmcblk") {
+        PathBuf::from(format!("{}p{}", device_str, number))
+    } else {
+        PathBuf::from(format!("{}{}", device_str, number))
     }
-    
-    Ok(partitions)
 }
 
 #[cfg(target_os = "windows")]
@@ -275,7 +535,9 @@ fn get_macos_device_info(device_path: &str) -> Result<UsbDevice> {
     
     // Get partitions
     let partitions = enumerate_macos_partitions(device_path)?;
-    
+
+    let bus = if vendor == "USB Device" { BusType::Usb } else { BusType::Unknown };
+
     Ok(UsbDevice {
         path: PathBuf::from(device_path),
         name: device_path.trim_start_matches("/dev/").to_string(),
@@ -284,6 +546,9 @@ fn get_macos_device_info(device_path: &str) -> Result<UsbDevice> {
         vendor,
         removable,
         partitions,
+        bus,
+        rotational: None,
+        serial: None,
     })
 }
 
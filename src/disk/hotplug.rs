@@ -0,0 +1,195 @@
+use crate::{Result, UsbBootHutError};
+use crate::disk::{enumerate_usb_devices, UsbDevice};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const NETLINK_KOBJECT_UEVENT: i32 = 15;
+
+/// A hotplug transition reported by `DeviceEvents::next_event`.
+#[derive(Debug, Clone)]
+pub enum DeviceEvent {
+    Added(UsbDevice),
+    Removed(PathBuf),
+}
+
+/// A live stream of device add/remove events opened by `watch_devices`.
+///
+/// On Linux this holds an open `kobject_uevent` netlink socket and parses
+/// uevents as they arrive. Elsewhere (no netlink equivalent) it falls back
+/// to diffing `enumerate_usb_devices()` snapshots on each `next_event` poll.
+pub struct DeviceEvents {
+    #[cfg(target_os = "linux")]
+    fd: std::os::unix::io::RawFd,
+    known: HashSet<PathBuf>,
+}
+
+#[cfg(target_os = "linux")]
+impl DeviceEvents {
+    pub fn next_event(&mut self, timeout: Duration) -> Result<DeviceEvent> {
+        use std::mem;
+
+        let deadline = Instant::now() + timeout;
+        let mut buf = vec![0u8; 8192];
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(UsbBootHutError::Device("Timed out waiting for a device event".to_string()));
+            }
+            set_recv_timeout(self.fd, remaining.min(Duration::from_secs(1)));
+
+            let n = unsafe { libc::recv(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+            if n <= 0 {
+                continue; // Recv timeout (SO_RCVTIMEO) or transient error, keep polling the deadline
+            }
+
+            let fields: Vec<&str> = buf[..n as usize].split(|&b| b == 0).filter_map(|s| std::str::from_utf8(s).ok()).collect();
+
+            let is_block_disk = fields.contains(&"SUBSYSTEM=block") && fields.contains(&"DEVTYPE=disk");
+            if !is_block_disk {
+                continue;
+            }
+
+            let Some(devname) = fields.iter().find_map(|f| f.strip_prefix("DEVNAME=")) else { continue };
+            if !is_removable(devname) {
+                continue;
+            }
+            let device_path = PathBuf::from("/dev").join(devname);
+
+            if fields.contains(&"ACTION=add") {
+                // Give the kernel a moment to finish creating device nodes before re-enumerating.
+                std::thread::sleep(Duration::from_millis(300));
+                if let Some(device) = enumerate_usb_devices()?.into_iter().find(|d| d.path == device_path) {
+                    self.known.insert(device.path.clone());
+                    return Ok(DeviceEvent::Added(device));
+                }
+            } else if fields.contains(&"ACTION=remove") {
+                self.known.remove(&device_path);
+                return Ok(DeviceEvent::Removed(device_path));
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl DeviceEvents {
+    pub fn next_event(&mut self, timeout: Duration) -> Result<DeviceEvent> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let current: HashSet<PathBuf> = enumerate_usb_devices()?.into_iter().map(|d| d.path).collect();
+
+            if let Some(path) = self.known.difference(&current).next().cloned() {
+                self.known.remove(&path);
+                return Ok(DeviceEvent::Removed(path));
+            }
+            if let Some(path) = current.difference(&self.known).next().cloned() {
+                if let Some(device) = enumerate_usb_devices()?.into_iter().find(|d| d.path == path) {
+                    self.known.insert(path);
+                    return Ok(DeviceEvent::Added(device));
+                }
+            }
+
+            if Instant::now() >= deadline {
+                return Err(UsbBootHutError::Device("Timed out waiting for a device event".to_string()));
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for DeviceEvents {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+/// Opens a live stream of device add/remove events. On Linux this binds a
+/// `kobject_uevent` netlink socket; elsewhere it snapshots the current
+/// device set to diff against on each `next_event` poll.
+#[cfg(target_os = "linux")]
+pub fn watch_devices() -> Result<DeviceEvents> {
+    use std::mem;
+
+    let known: HashSet<_> = enumerate_usb_devices()?.into_iter().map(|d| d.path).collect();
+
+    let fd = unsafe {
+        libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, NETLINK_KOBJECT_UEVENT)
+    };
+    if fd < 0 {
+        return Err(UsbBootHutError::Device("Failed to open netlink socket".to_string()));
+    }
+
+    let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as u16;
+    addr.nl_pid = 0;
+    addr.nl_groups = 1; // kernel kobject_uevent multicast group
+
+    let bound = unsafe {
+        libc::bind(
+            fd,
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_nl>() as u32,
+        )
+    };
+    if bound < 0 {
+        unsafe { libc::close(fd) };
+        return Err(UsbBootHutError::Device("Failed to bind netlink socket".to_string()));
+    }
+
+    Ok(DeviceEvents { fd, known })
+}
+
+/// No netlink equivalent on macOS/Windows; `next_event` diffs enumeration
+/// snapshots instead.
+#[cfg(not(target_os = "linux"))]
+pub fn watch_devices() -> Result<DeviceEvents> {
+    let known: HashSet<_> = enumerate_usb_devices()?.into_iter().map(|d| d.path).collect();
+    Ok(DeviceEvents { known })
+}
+
+#[cfg(target_os = "linux")]
+fn set_recv_timeout(fd: std::os::unix::io::RawFd, timeout: Duration) {
+    let tv = libc::timeval { tv_sec: timeout.as_secs() as libc::time_t, tv_usec: timeout.subsec_micros() as libc::suseconds_t };
+    unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_RCVTIMEO,
+            &tv as *const libc::timeval as *const libc::c_void,
+            std::mem::size_of::<libc::timeval>() as u32,
+        );
+    }
+}
+
+/// Checks `/sys/block/<name>/removable` so hotplug events for internal disks
+/// (and partitions, which don't carry `DEVTYPE=disk`) are filtered out, the
+/// same filter `enumerate_usb_devices` applies when building its own list.
+#[cfg(target_os = "linux")]
+fn is_removable(devname: &str) -> bool {
+    std::fs::read_to_string(format!("/sys/block/{}/removable", devname))
+        .map(|s| s.trim() == "1")
+        .unwrap_or(false)
+}
+
+/// Blocks until a new removable device shows up, or `timeout` elapses.
+/// Built on top of `watch_devices`/`next_event`, ignoring any `Removed`
+/// events seen while waiting for the next `Added`.
+pub fn wait_for_device(timeout: Duration) -> Result<UsbDevice> {
+    let mut events = watch_devices()?;
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(UsbBootHutError::Device("Timed out waiting for a USB device to be inserted".to_string()));
+        }
+
+        match events.next_event(remaining)? {
+            DeviceEvent::Added(device) => return Ok(device),
+            DeviceEvent::Removed(_) => continue,
+        }
+    }
+}
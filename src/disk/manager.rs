@@ -1,13 +1,18 @@
 use crate::{Result, UsbBootHutError};
 use crate::disk::{UsbDevice, SecureWipe};
-use crate::partition::PartitionManager;
-use crate::crypto::LuksManager;
+use crate::partition::{DataFilesystem, PartitionLayout, PartitionManager, PartitionTableLayout};
+use crate::crypto::{LuksKeySource, LuksManager};
+use crate::bootloader::{BootCrypto, BootloaderKind, EspBuilder};
 use std::path::{Path, PathBuf};
+use std::fs;
 use indicatif::{ProgressBar, ProgressStyle};
+use tempfile::TempDir;
 
 pub struct DriveManager {
     device: UsbDevice,
     encryption_enabled: bool,
+    boot_encryption_enabled: bool,
+    bootloader: BootloaderKind,
 }
 
 impl DriveManager {
@@ -15,66 +20,167 @@ impl DriveManager {
         Self {
             device,
             encryption_enabled: false,
+            boot_encryption_enabled: false,
+            bootloader: BootloaderKind::Grub,
         }
     }
-    
+
+    /// For `build_image`, which has no real USB hardware to enumerate.
+    /// `device` is never read by `build_image` -- it formats and installs
+    /// into a plain image file -- so this placeholder only exists to give
+    /// it a `DriveManager` to carry the `with_encryption`/`with_bootloader`
+    /// builder flags `format_and_setup` and `build_image` share.
+    pub fn for_image() -> Self {
+        Self {
+            device: UsbDevice {
+                path: PathBuf::new(),
+                name: "image".to_string(),
+                size: 0,
+                model: String::new(),
+                vendor: String::new(),
+                removable: false,
+                partitions: Vec::new(),
+                bus: crate::disk::BusType::Unknown,
+                rotational: None,
+                serial: None,
+            },
+            encryption_enabled: false,
+            boot_encryption_enabled: false,
+            bootloader: BootloaderKind::Grub,
+        }
+    }
+
     pub fn with_encryption(mut self) -> Self {
         self.encryption_enabled = true;
         self
     }
-    
-    pub fn format_and_setup(&self, passphrase: Option<&str>) -> Result<()> {
+
+    /// Also LUKS-encrypts the boot (ext4) partition, not just the data
+    /// partition, and installs GRUB with a `cryptomount` preamble that
+    /// unlocks it at power-on. Requires `BootloaderKind::Grub`: legacy
+    /// syslinux can't unlock LUKS itself.
+    pub fn with_boot_encryption(mut self) -> Self {
+        self.boot_encryption_enabled = true;
+        self
+    }
+
+    pub fn with_bootloader(mut self, bootloader: BootloaderKind) -> Self {
+        self.bootloader = bootloader;
+        self
+    }
+
+    /// `passphrase` is the slot-0 key required whenever `self.encryption_enabled`;
+    /// `key_file`, if given, is additionally added as slot 1 (`luksAddKey`)
+    /// and then preferred over the passphrase to open the partition for
+    /// formatting, matching the way `unlock` tries a key file before
+    /// falling back to a passphrase prompt.
+    pub fn format_and_setup(&self, passphrase: Option<&str>, key_file: Option<&Path>, layout: &PartitionLayout) -> Result<()> {
         // Validate device
         self.device.is_valid_for_boot()?;
-        
+
         // Safety check
         if self.device.has_system_files() {
             return Err(UsbBootHutError::Device(
                 "Device appears to contain system files. Please confirm this is the correct device.".to_string()
             ));
         }
-        
+
+        if layout.persistence.is_some() && layout.data_size_mb.is_none() {
+            return Err(UsbBootHutError::Partition(
+                "--data-size-mb must be set when requesting a persistence partition, so the persistence partition knows how much space is left to claim".to_string()
+            ));
+        }
+
+        if self.boot_encryption_enabled {
+            if !matches!(self.bootloader, BootloaderKind::Grub) {
+                return Err(UsbBootHutError::Bootloader(
+                    "Encrypted /boot requires the GRUB bootloader; syslinux's MBR boot code can't unlock LUKS".to_string()
+                ));
+            }
+            if passphrase.is_none() {
+                return Err(UsbBootHutError::Encryption(
+                    "Passphrase required to encrypt /boot".to_string()
+                ));
+            }
+        }
+
         println!("Preparing to format device: {}", self.device.path.display());
         println!("Model: {} {}", self.device.vendor, self.device.model);
         println!("Size: {} GB", self.device.size / 1_000_000_000);
-        
+
         // Create partition manager
-        let partition_mgr = PartitionManager::new(&self.device.path);
-        
+        let table_layout = match &layout.persistence {
+            Some(persistence) => PartitionTableLayout::with_persistence(layout.data_filesystem, layout.data_size_mb, persistence),
+            None => PartitionTableLayout::standard(layout.data_filesystem, layout.data_size_mb),
+        };
+        let partition_mgr = PartitionManager::new(&self.device.path, table_layout);
+
         // Step 1: Wipe partition table
         println!("\n[1/6] Wiping partition table...");
         partition_mgr.wipe_partition_table()?;
-        
+
         // Step 2: Create GPT
         println!("[2/6] Creating GPT partition table...");
         partition_mgr.create_gpt()?;
-        
+
         // Step 3: Create partitions
         println!("[3/6] Creating partitions...");
-        let esp_part = partition_mgr.create_esp_partition()?;
-        let boot_part = partition_mgr.create_boot_partition()?;
-        let data_part = partition_mgr.create_data_partition()?;
-        
+        let parts = partition_mgr.create_partitions()?;
+        let esp_part = parts[0].clone();
+        let boot_part = parts[1].clone();
+        let data_part = parts[2].clone();
+        let persistence_part = parts.get(3).cloned();
+
         // Step 4: Format partitions
         println!("[4/6] Formatting partitions...");
         self.format_esp(&esp_part)?;
-        self.format_boot(&boot_part)?;
-        
+
+        // If /boot is encrypted, it's LUKS-formatted and opened here so
+        // `format_boot` lays ext4 onto the mapped device, not the raw LUKS
+        // partition; the mapping is left open (closed at the very end) so
+        // step 6 can mount it to install GRUB. `boot_crypto` is then passed
+        // to `install_bootloader` so GRUB knows to emit the cryptomount
+        // preamble and which device to write the bootloader files onto.
+        let luks_mgr = LuksManager::new();
+        let (boot_write_target, boot_mapped_name, boot_crypto) = if self.boot_encryption_enabled {
+            println!("      Encrypting boot partition...");
+            let passphrase_key = LuksKeySource::Passphrase(passphrase.unwrap().to_string());
+            luks_mgr.create_encrypted_partition(&boot_part, &passphrase_key)?;
+
+            let luks_uuid = luks_mgr.luks_uuid(&boot_part)?;
+            let mapped_name = format!("usb_boot_hut_boot_{}", uuid::Uuid::new_v4());
+            luks_mgr.open_encrypted_partition(&boot_part, &passphrase_key, &mapped_name)?;
+            let mapped_path = PathBuf::from(format!("/dev/mapper/{}", mapped_name));
+
+            self.format_boot(&mapped_path)?;
+            (mapped_path, Some(mapped_name), Some(BootCrypto { luks_uuid }))
+        } else {
+            self.format_boot(&boot_part)?;
+            (boot_part.clone(), None, None)
+        };
+
         // Step 5: Setup encryption if enabled
         if self.encryption_enabled {
             if let Some(pass) = passphrase {
                 println!("[5/6] Setting up LUKS encryption...");
-                let luks_mgr = LuksManager::new();
-                luks_mgr.create_encrypted_partition(&data_part, pass)?;
-                
-                // Open the encrypted partition
+                let passphrase_key = LuksKeySource::Passphrase(pass.to_string());
+                luks_mgr.create_encrypted_partition(&data_part, &passphrase_key)?;
+
+                if let Some(key_file) = key_file {
+                    println!("      Adding key file to slot 1...");
+                    luks_mgr.add_key_slot(&data_part, &passphrase_key, &LuksKeySource::KeyFile(key_file.to_path_buf()))?;
+                }
+
+                // Open the encrypted partition, preferring the key file if
+                // one was just added.
                 let mapped_name = format!("usb_boot_hut_{}", uuid::Uuid::new_v4());
-                luks_mgr.open_encrypted_partition(&data_part, pass, &mapped_name)?;
-                
+                let open_key = key_file.map(|path| LuksKeySource::KeyFile(path.to_path_buf())).unwrap_or(passphrase_key);
+                luks_mgr.open_encrypted_partition(&data_part, &open_key, &mapped_name)?;
+
                 // Format the opened LUKS device
                 let mapped_path = PathBuf::from(format!("/dev/mapper/{}", mapped_name));
-                self.format_data(&mapped_path)?;
-                
+                self.format_data(&mapped_path, layout.data_filesystem)?;
+
                 // Close the encrypted partition
                 luks_mgr.close_encrypted_partition(&mapped_name)?;
             } else {
@@ -84,61 +190,188 @@ impl DriveManager {
             }
         } else {
             println!("[5/6] Formatting data partition...");
-            self.format_data(&data_part)?;
+            self.format_data(&data_part, layout.data_filesystem)?;
         }
-        
+
+        // Persistence is never encrypted: live distros write to it from an
+        // initramfs overlay that has no LUKS support of its own.
+        if let (Some(persistence_part), Some(persistence_layout)) = (&persistence_part, &layout.persistence) {
+            println!("      Formatting persistence partition ({})...", persistence_layout.label);
+            self.format_persistence(persistence_part, &persistence_layout.label)?;
+            self.write_persistence_conf(persistence_part)?;
+        }
+
         // Step 6: Install bootloader
-        println!("[6/6] Installing GRUB bootloader...");
-        self.install_grub(&esp_part, &boot_part)?;
-        
+        println!("[6/6] Installing {} bootloader...", match self.bootloader {
+            BootloaderKind::Grub => "GRUB",
+            BootloaderKind::Syslinux => "Syslinux",
+        });
+        let install_result = self.install_bootloader(&self.device.path, &esp_part, &boot_write_target, boot_crypto.as_ref());
+
+        if let Some(mapped_name) = boot_mapped_name {
+            luks_mgr.close_encrypted_partition(&mapped_name)?;
+        }
+        install_result?;
+
         println!("\n✓ USB drive successfully formatted and configured!");
         Ok(())
     }
-    
-    pub fn secure_format(&self, passphrase: Option<&str>) -> Result<()> {
+
+    pub fn secure_format(&self, passphrase: Option<&str>, key_file: Option<&Path>, layout: &PartitionLayout) -> Result<()> {
         // First do a secure wipe
         println!("Performing secure wipe (this may take a while)...");
         let wiper = SecureWipe::new(&self.device.path);
-        
-        let pb = ProgressBar::new(100);
+
+        let pb = ProgressBar::new(0);
         pb.set_style(
             ProgressStyle::default_bar()
-                .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}% {msg}")
+                .template("[{elapsed_precise}] {bar:40.cyan/blue} {bytes}/{total_bytes} ({bytes_per_sec}, ETA {eta}) {msg}")
                 .unwrap()
                 .progress_chars("##-")
         );
-        
-        wiper.wipe_with_progress(|progress| {
-            pb.set_position(progress as u64);
+
+        wiper.wipe_with_progress(|written, total| {
+            pb.set_length(total);
+            pb.set_position(written);
         })?;
-        
+
         pb.finish_with_message("Secure wipe complete");
-        
+
         // Then format normally
-        self.format_and_setup(passphrase)
+        self.format_and_setup(passphrase, key_file, layout)
     }
-    
-    fn format_esp(&self, partition: &Path) -> Result<()> {
-        use std::process::Command;
-        
-        let output = Command::new("mkfs.fat")
-            .args(["-F", "32", "-n", "USB_ESP"])
-            .arg(partition)
-            .output()
-            .map_err(|e| UsbBootHutError::Partition(format!("Failed to format ESP: {}", e)))?;
-            
-        if !output.status.success() {
+
+    /// Builds a complete GPT+ESP+boot+data image into `output` with no USB
+    /// hardware attached, so it can be prepared offline (CI included) and
+    /// flashed later with `burn`. Mirrors `format_and_setup` step for step,
+    /// except there's no `UsbDevice` to validate and no partition device
+    /// nodes to format directly: `PartitionManager::create_image` lays down
+    /// the GPT and partitions inside `output` itself and hands back their
+    /// byte ranges, which this loop-mounts (`losetup --offset/--sizelimit`)
+    /// one at a time so the existing `format_esp`/`format_boot`/`format_data`/
+    /// `install_bootloader` can run against them completely unchanged.
+    pub fn build_image(&self, output: &Path, size_mb: u64, passphrase: Option<&str>, key_file: Option<&Path>, layout: &PartitionLayout) -> Result<()> {
+        if layout.persistence.is_some() {
             return Err(UsbBootHutError::Partition(
-                format!("mkfs.fat failed: {}", String::from_utf8_lossy(&output.stderr))
+                "create-image doesn't support --persistence yet; format a real device for a persistence partition".to_string()
             ));
         }
-        
+
+        if self.boot_encryption_enabled {
+            if !matches!(self.bootloader, BootloaderKind::Grub) {
+                return Err(UsbBootHutError::Bootloader(
+                    "Encrypted /boot requires the GRUB bootloader; syslinux's MBR boot code can't unlock LUKS".to_string()
+                ));
+            }
+            if passphrase.is_none() {
+                return Err(UsbBootHutError::Encryption(
+                    "Passphrase required to encrypt /boot".to_string()
+                ));
+            }
+        }
+
+        println!("Preparing to build image: {}", output.display());
+
+        let table_layout = PartitionTableLayout::standard(layout.data_filesystem, layout.data_size_mb);
+        let partition_mgr = PartitionManager::new(output, table_layout);
+
+        // Step 1: Lay down the GPT and partitions directly in the image file
+        println!("\n[1/4] Creating partition table...");
+        let ranges = partition_mgr.create_image(size_mb * 1024 * 1024)?;
+        let (esp_range, boot_range, data_range) = (ranges[0], ranges[1], ranges[2]);
+
+        // Step 2: Loop-mount every partition's byte range so the rest of
+        // this mirrors format_and_setup against ordinary device paths.
+        println!("[2/4] Attaching loop devices...");
+        let esp_loop = attach_loop(output, &esp_range)?;
+        let boot_loop = attach_loop(output, &boot_range)?;
+        let data_loop = attach_loop(output, &data_range)?;
+
+        let result = self.build_image_contents(output, &esp_loop, &boot_loop, &data_loop, passphrase, key_file, layout);
+
+        let _ = detach_loop(&data_loop);
+        let _ = detach_loop(&boot_loop);
+        let _ = detach_loop(&esp_loop);
+
+        result?;
+        println!("\n✓ Disk image successfully built at {}", output.display());
         Ok(())
     }
+
+    /// The part of `build_image` that actually formats/installs, once every
+    /// partition has a loop device path to be addressed by -- split out so
+    /// `build_image` can detach the loop devices in every case, success or
+    /// error, via the same "run body, clean up, then propagate" shape
+    /// `format_and_setup` uses for the boot LUKS mapping.
+    fn build_image_contents(&self, output: &Path, esp_loop: &Path, boot_loop: &Path, data_loop: &Path, passphrase: Option<&str>, key_file: Option<&Path>, layout: &PartitionLayout) -> Result<()> {
+        println!("[3/4] Formatting partitions...");
+        self.format_esp(esp_loop)?;
+
+        let luks_mgr = LuksManager::new();
+        let (boot_write_target, boot_mapped_name, boot_crypto) = if self.boot_encryption_enabled {
+            let passphrase_key = LuksKeySource::Passphrase(passphrase.unwrap().to_string());
+            luks_mgr.create_encrypted_partition(boot_loop, &passphrase_key)?;
+
+            let luks_uuid = luks_mgr.luks_uuid(boot_loop)?;
+            let mapped_name = format!("usb_boot_hut_boot_{}", uuid::Uuid::new_v4());
+            luks_mgr.open_encrypted_partition(boot_loop, &passphrase_key, &mapped_name)?;
+            let mapped_path = PathBuf::from(format!("/dev/mapper/{}", mapped_name));
+
+            self.format_boot(&mapped_path)?;
+            (mapped_path, Some(mapped_name), Some(BootCrypto { luks_uuid }))
+        } else {
+            self.format_boot(boot_loop)?;
+            (boot_loop.to_path_buf(), None, None)
+        };
+
+        if self.encryption_enabled {
+            let pass = passphrase.ok_or_else(|| UsbBootHutError::Encryption(
+                "Passphrase required for encryption".to_string()
+            ))?;
+            let passphrase_key = LuksKeySource::Passphrase(pass.to_string());
+            luks_mgr.create_encrypted_partition(data_loop, &passphrase_key)?;
+
+            if let Some(key_file) = key_file {
+                luks_mgr.add_key_slot(data_loop, &passphrase_key, &LuksKeySource::KeyFile(key_file.to_path_buf()))?;
+            }
+
+            let mapped_name = format!("usb_boot_hut_{}", uuid::Uuid::new_v4());
+            let open_key = key_file.map(|path| LuksKeySource::KeyFile(path.to_path_buf())).unwrap_or(passphrase_key);
+            luks_mgr.open_encrypted_partition(data_loop, &open_key, &mapped_name)?;
+
+            let mapped_path = PathBuf::from(format!("/dev/mapper/{}", mapped_name));
+            self.format_data(&mapped_path, layout.data_filesystem)?;
+            luks_mgr.close_encrypted_partition(&mapped_name)?;
+        } else {
+            self.format_data(data_loop, layout.data_filesystem)?;
+        }
+
+        println!("[4/4] Installing {} bootloader...", match self.bootloader {
+            BootloaderKind::Grub => "GRUB",
+            BootloaderKind::Syslinux => "Syslinux",
+        });
+        let install_result = self.install_bootloader(output, esp_loop, &boot_write_target, boot_crypto.as_ref());
+
+        if let Some(mapped_name) = boot_mapped_name {
+            luks_mgr.close_encrypted_partition(&mapped_name)?;
+        }
+        install_result
+    }
+
+    /// Formats the ESP FAT32 with `EspBuilder`/`fatfs` -- the same pure-Rust
+    /// path `GrubInstaller` uses to populate it -- instead of shelling out to
+    /// `mkfs.fat`, so `dosfstools` isn't a hard runtime dependency.
+    fn format_esp(&self, partition: &Path) -> Result<()> {
+        EspBuilder::new(partition)?.mkfs()?.finish()
+    }
     
+    /// Still shells out to `mkfs.ext4`: unlike FAT32, there's no pure-Rust
+    /// crate mature enough to replace `e2fsprogs` here, so ext4 paths
+    /// (`format_boot`, `format_data`'s `Ext4` arm, `format_persistence`)
+    /// keep the external-binary dependency `format_esp` just dropped.
     fn format_boot(&self, partition: &Path) -> Result<()> {
         use std::process::Command;
-        
+
         let output = Command::new("mkfs.ext4")
             .args(["-L", "USB_BOOT", "-F"])
             .arg(partition)
@@ -154,30 +387,140 @@ impl DriveManager {
         Ok(())
     }
     
-    fn format_data(&self, partition: &Path) -> Result<()> {
+    fn format_data(&self, partition: &Path, filesystem: DataFilesystem) -> Result<()> {
         use std::process::Command;
-        
-        let output = Command::new("mkfs.ext4")
-            .args(["-L", "USB_DATA", "-F"])
+
+        let (program, args): (&str, Vec<&str>) = match filesystem {
+            DataFilesystem::Ext4 => ("mkfs.ext4", vec!["-L", "USB_DATA", "-F"]),
+            DataFilesystem::Exfat => ("mkfs.exfat", vec!["-n", "USB_DATA"]),
+            DataFilesystem::Btrfs => ("mkfs.btrfs", vec!["-L", "USB_DATA", "-f"]),
+        };
+
+        let output = Command::new(program)
+            .args(&args)
             .arg(partition)
             .output()
             .map_err(|e| UsbBootHutError::Partition(format!("Failed to format data: {}", e)))?;
-            
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::Partition(
+                format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Persistence partitions are always ext4 (what `persistence.conf`/casper's
+    /// overlay support expects), labeled to match the GPT name so live distros
+    /// that look up `persistence`/`casper-rw` by filesystem label find it.
+    fn format_persistence(&self, partition: &Path, label: &str) -> Result<()> {
+        use std::process::Command;
+
+        let output = Command::new("mkfs.ext4")
+            .args(["-L", label, "-F"])
+            .arg(partition)
+            .output()
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to format persistence partition: {}", e)))?;
+
         if !output.status.success() {
             return Err(UsbBootHutError::Partition(
                 format!("mkfs.ext4 failed: {}", String::from_utf8_lossy(&output.stderr))
             ));
         }
-        
+
         Ok(())
     }
-    
-    fn install_grub(&self, esp_partition: &Path, boot_partition: &Path) -> Result<()> {
-        use crate::bootloader::GrubInstaller;
-        
-        let installer = GrubInstaller::new(&self.device.path);
-        installer.install(esp_partition, boot_partition)?;
-        
+
+    /// Mounts the freshly-formatted persistence partition just long enough to
+    /// write `persistence.conf` (`/ union`), which is what tells Debian/Ubuntu
+    /// live images to use the whole partition as a writable overlay.
+    fn write_persistence_conf(&self, partition: &Path) -> Result<()> {
+        use std::process::Command;
+
+        let temp_dir = TempDir::new()
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to create temp dir: {}", e)))?;
+
+        let output = Command::new("mount")
+            .args([partition.to_str().unwrap(), temp_dir.path().to_str().unwrap()])
+            .output()
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to mount persistence partition: {}", e)))?;
+        if !output.status.success() {
+            return Err(UsbBootHutError::Partition(
+                format!("Mount failed: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        let result = fs::write(temp_dir.path().join("persistence.conf"), "/ union\n")
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to write persistence.conf: {}", e)));
+
+        let unmount = Command::new("umount")
+            .arg(temp_dir.path())
+            .output()
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to unmount persistence partition: {}", e)))?;
+        if !unmount.status.success() {
+            return Err(UsbBootHutError::Partition(
+                format!("Unmount failed: {}", String::from_utf8_lossy(&unmount.stderr))
+            ));
+        }
+
+        result
+    }
+
+    /// `whole_disk_path` is what `SyslinuxInstaller` marks active and writes
+    /// MBR boot code to -- the real `/dev/sdX` for a flashed drive, or the
+    /// image file itself for `build_image` (never a loop device scoped to a
+    /// single partition, which has no MBR of its own to speak of).
+    fn install_bootloader(&self, whole_disk_path: &Path, esp_partition: &Path, boot_partition: &Path, boot_crypto: Option<&BootCrypto>) -> Result<()> {
+        use crate::bootloader::create_bootloader;
+
+        let installer = create_bootloader(self.bootloader, whole_disk_path);
+        installer.install(esp_partition, boot_partition, boot_crypto)?;
+
         Ok(())
     }
+}
+
+/// Attaches `range` of `image_path` as its own loop device (`losetup
+/// --offset/--sizelimit --show -f`), giving `build_image` an ordinary
+/// device path for one partition inside the image file so it can reuse
+/// `format_esp`/`format_boot`/`format_data`/`install_bootloader` exactly as
+/// they run against a real flashed drive's partitions.
+fn attach_loop(image_path: &Path, range: &crate::partition::PartitionByteRange) -> Result<PathBuf> {
+    use std::process::Command;
+
+    let output = Command::new("losetup")
+        .args([
+            "--offset", &range.start.to_string(),
+            "--sizelimit", &(range.end - range.start).to_string(),
+            "--show", "-f",
+        ])
+        .arg(image_path)
+        .output()
+        .map_err(|e| UsbBootHutError::Partition(format!("Failed to run losetup: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(UsbBootHutError::Partition(
+            format!("losetup failed: {}", String::from_utf8_lossy(&output.stderr))
+        ));
+    }
+
+    Ok(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+fn detach_loop(loop_device: &Path) -> Result<()> {
+    use std::process::Command;
+
+    let output = Command::new("losetup")
+        .args(["-d", loop_device.to_str().unwrap()])
+        .output()
+        .map_err(|e| UsbBootHutError::Partition(format!("Failed to run losetup -d: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(UsbBootHutError::Partition(
+            format!("losetup -d failed: {}", String::from_utf8_lossy(&output.stderr))
+        ));
+    }
+
+    Ok(())
 }
\ No newline at end of file
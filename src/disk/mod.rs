@@ -2,8 +2,16 @@ pub mod device;
 pub mod manager;
 pub mod wipe;
 pub mod burn;
+pub mod smart;
+pub mod hotplug;
+pub mod preflight;
+pub mod provision;
 
 pub use device::*;
 pub use manager::*;
 pub use wipe::*;
-pub use burn::*;
\ No newline at end of file
+pub use burn::*;
+pub use smart::*;
+pub use hotplug::*;
+pub use preflight::*;
+pub use provision::*;
\ No newline at end of file
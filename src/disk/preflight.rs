@@ -0,0 +1,152 @@
+use crate::{Result, UsbBootHutError};
+use std::path::Path;
+
+/// Preflight guard run immediately before `ImageBurner::burn` opens its
+/// target for writing, modeled on grml2usb's `--rw-blockdev` handling and
+/// coreos-installer's block-device checks. Unless `force` is set, refuses
+/// to write to a device that has mounted partitions, refuses what looks
+/// like the disk backing the running system (`/`), and refuses a device
+/// the kernel has marked read-only rather than silently clearing it --
+/// an accidental `burn` to the wrong `/dev/sdX` should be hard to do.
+pub struct WritePreflight {
+    force: bool,
+}
+
+impl WritePreflight {
+    pub fn new(force: bool) -> Self {
+        Self { force }
+    }
+
+    pub fn check(&self, device_path: &Path) -> Result<()> {
+        if let Some(device) = crate::disk::enumerate_usb_devices()?
+            .into_iter()
+            .find(|d| d.path == device_path)
+        {
+            let mounted = device.mounted_partitions()?;
+            if !mounted.is_empty() && !self.force {
+                return Err(UsbBootHutError::Device(format!(
+                    "Refusing to write: device is in use (mounted): {}",
+                    mounted.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+                )));
+            }
+
+            if is_system_disk(&device)? && !self.force {
+                return Err(UsbBootHutError::Device(format!(
+                    "Refusing to write: {} appears to back the running system (/)",
+                    device.path.display()
+                )));
+            }
+        }
+
+        self.check_read_only(device_path)
+    }
+
+    /// Reads the kernel's read-only flag for `device_path` via `BLKROGET`
+    /// and, if set, only clears it with `BLKROSET` when `force` was passed.
+    /// Not a block device (e.g. a regular file used in tests)? `BLKROGET`
+    /// fails and there's nothing to guard against.
+    #[cfg(target_os = "linux")]
+    fn check_read_only(&self, device_path: &Path) -> Result<()> {
+        use std::fs::OpenOptions;
+        use std::os::unix::io::AsRawFd;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .open(device_path)
+            .map_err(|e| UsbBootHutError::Device(
+                format!("Failed to open {} to check read-only flag: {}", device_path.display(), e)
+            ))?;
+        let fd = file.as_raw_fd();
+
+        let mut read_only: libc::c_int = 0;
+        let rc = unsafe { libc::ioctl(fd, BLKROGET, &mut read_only as *mut libc::c_int) };
+        if rc != 0 || read_only == 0 {
+            return Ok(());
+        }
+
+        if !self.force {
+            return Err(UsbBootHutError::Device(format!(
+                "{} is marked read-only by the kernel; pass --force to clear it",
+                device_path.display()
+            )));
+        }
+
+        let mut clear: libc::c_int = 0;
+        let rc = unsafe { libc::ioctl(fd, BLKROSET, &mut clear as *mut libc::c_int) };
+        if rc != 0 {
+            return Err(UsbBootHutError::Device(format!(
+                "Failed to clear read-only flag on {}: {}",
+                device_path.display(), std::io::Error::last_os_error()
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn check_read_only(&self, _device_path: &Path) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `BLKROGET`/`BLKROSET`: `_IO(0x12, 94)` / `_IO(0x12, 93)` from
+/// `linux/fs.h`, read and set a block device's kernel-enforced read-only bit.
+#[cfg(target_os = "linux")]
+const BLKROGET: libc::c_ulong = 0x125E;
+#[cfg(target_os = "linux")]
+const BLKROSET: libc::c_ulong = 0x125D;
+
+/// Whether `device` (the whole disk or any of its partitions) backs the
+/// running system's root filesystem.
+#[cfg(target_os = "linux")]
+fn is_system_disk(device: &crate::disk::UsbDevice) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let Some(root_devno) = root_device_number()? else {
+        return Ok(false);
+    };
+
+    let mut candidates: Vec<&Path> = vec![device.path.as_path()];
+    candidates.extend(device.partitions.iter().map(|p| p.path.as_path()));
+
+    for path in candidates {
+        if let Ok(metadata) = std::fs::metadata(path) {
+            if metadata.rdev() == root_devno {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_system_disk(_device: &crate::disk::UsbDevice) -> Result<bool> {
+    Ok(false)
+}
+
+/// The major:minor device number backing the `/` mount, read from
+/// `/proc/self/mountinfo` the same way `UsbDevice::mounted_partitions` maps
+/// mount points to their backing devices.
+#[cfg(target_os = "linux")]
+fn root_device_number() -> Result<Option<u64>> {
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo")
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to read mountinfo: {}", e)))?;
+
+    for line in mountinfo.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(mount_point) = fields.get(4) else { continue };
+        if *mount_point != "/" {
+            continue;
+        }
+
+        let Some(major_minor) = fields.get(2) else { continue };
+        if let Some((major, minor)) = major_minor.split_once(':') {
+            if let (Ok(major), Ok(minor)) = (major.parse::<u32>(), minor.parse::<u32>()) {
+                return Ok(Some(libc::makedev(major, minor) as u64));
+            }
+        }
+    }
+
+    Ok(None)
+}
@@ -0,0 +1,247 @@
+use crate::{Result, UsbBootHutError};
+use crate::utils::atomic_write;
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// First-boot configuration format `inject_provisioning` writes into a
+/// burned image's boot partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvisioningFormat {
+    /// cloud-init (`user-data`/`network-config`), used by cloud and most
+    /// generic-Linux images.
+    CloudInit,
+    /// Ignition (`config.ign`), used by Fedora CoreOS/Flatcar images.
+    Ignition,
+}
+
+/// Delimits the block `inject_serial_console` owns in the burned image's
+/// `grub.cfg`; re-running `burn` on the same image won't duplicate it.
+const CONSOLE_SETTINGS_START: &str = "# CONSOLE-SETTINGS-START";
+const CONSOLE_SETTINGS_END: &str = "# CONSOLE-SETTINGS-END";
+const SERIAL_CONSOLE_ARGS: &[&str] = &["console=tty0", "console=ttyS0,115200n8"];
+
+/// A temporary mount of a burned image's boot partition, unmounted on drop
+/// so callers can't forget.
+struct MountedBootPartition {
+    mount_point: PathBuf,
+    _temp_dir: TempDir,
+}
+
+impl MountedBootPartition {
+    fn mount(device_path: &Path) -> Result<Self> {
+        let partition = find_boot_partition(device_path)?;
+
+        let temp_dir = TempDir::new()
+            .map_err(|e| UsbBootHutError::Device(format!("Failed to create temp dir: {}", e)))?;
+
+        let output = Command::new("mount")
+            .args([partition.to_str().unwrap(), temp_dir.path().to_str().unwrap()])
+            .output()
+            .map_err(|e| UsbBootHutError::Device(format!("Failed to mount boot partition: {}", e)))?;
+        if !output.status.success() {
+            return Err(UsbBootHutError::Device(
+                format!("Mount failed: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(Self {
+            mount_point: temp_dir.path().to_path_buf(),
+            _temp_dir: temp_dir,
+        })
+    }
+}
+
+impl Drop for MountedBootPartition {
+    fn drop(&mut self) {
+        let _ = Command::new("umount").arg(&self.mount_point).output();
+    }
+}
+
+/// The first partition of `device_path`, using the same "p" infix
+/// convention for NVMe/MMC block devices as
+/// `PartitionManager::get_partition_path`.
+fn find_boot_partition(device_path: &Path) -> Result<PathBuf> {
+    let device_str = device_path.to_str()
+        .ok_or_else(|| UsbBootHutError::Device("Invalid device path".to_string()))?;
+
+    let candidate = if device_str.contains("nvme") || device_str.contains("mmcblk") {
+        PathBuf::from(format!("{}p1", device_str))
+    } else {
+        PathBuf::from(format!("{}1", device_str))
+    };
+
+    if candidate.exists() {
+        Ok(candidate)
+    } else {
+        Err(UsbBootHutError::Device(format!("Could not find boot partition at {}", candidate.display())))
+    }
+}
+
+/// Detects which first-boot config format `boot_mount` expects: Ignition if
+/// a `config.ign`/`ignition` marker is already there or the source image's
+/// filename names CoreOS/Flatcar, cloud-init otherwise.
+pub fn detect_provisioning_format(image_path: &Path, boot_mount: &Path) -> ProvisioningFormat {
+    let name = image_path.file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    if boot_mount.join("config.ign").exists()
+        || boot_mount.join("ignition").exists()
+        || name.contains("coreos")
+        || name.contains("flatcar")
+    {
+        ProvisioningFormat::Ignition
+    } else {
+        ProvisioningFormat::CloudInit
+    }
+}
+
+/// Mounts `device_path`'s boot partition and writes structured first-boot
+/// provisioning into it: `user-data`/`network-config` for cloud-init images,
+/// `config.ign` for Ignition images. A no-op if neither `enable_ssh` nor
+/// `wifi` was requested. Writes overwrite rather than duplicate, so
+/// re-running `burn` on the same image is safe.
+pub fn inject_provisioning(
+    image_path: &Path,
+    device_path: &Path,
+    enable_ssh: bool,
+    wifi: Option<(&str, &str)>,
+) -> Result<()> {
+    if !enable_ssh && wifi.is_none() {
+        return Ok(());
+    }
+
+    let boot = MountedBootPartition::mount(device_path)?;
+
+    match detect_provisioning_format(image_path, &boot.mount_point) {
+        ProvisioningFormat::CloudInit => {
+            println!("☁️  Writing cloud-init first-boot configuration...");
+            write_cloud_init(&boot.mount_point, enable_ssh, wifi)
+        }
+        ProvisioningFormat::Ignition => {
+            println!("🔥 Writing Ignition first-boot configuration...");
+            write_ignition(&boot.mount_point, enable_ssh, wifi)
+        }
+    }
+}
+
+fn write_cloud_init(boot_mount: &Path, enable_ssh: bool, wifi: Option<(&str, &str)>) -> Result<()> {
+    let user_data = format!("#cloud-config\nssh_pwauth: {}\n", enable_ssh);
+    atomic_write(&boot_mount.join("user-data"), user_data.as_bytes())?;
+
+    // meta-data is required alongside user-data but carries nothing
+    // meaningful here; only seed it if a previous run hasn't already.
+    let meta_data_path = boot_mount.join("meta-data");
+    if !meta_data_path.exists() {
+        atomic_write(&meta_data_path, b"instance-id: usb-boot-hut\nlocal-hostname: usb-boot-hut\n")?;
+    }
+
+    if let Some((ssid, psk)) = wifi {
+        let network_config = format!(
+            "version: 2\nwifis:\n  wlan0:\n    dhcp4: true\n    optional: true\n    access-points:\n      \"{}\":\n        password: \"{}\"\n",
+            ssid, psk
+        );
+        atomic_write(&boot_mount.join("network-config"), network_config.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn write_ignition(boot_mount: &Path, enable_ssh: bool, wifi: Option<(&str, &str)>) -> Result<()> {
+    let mut units = Vec::new();
+    if enable_ssh {
+        units.push(serde_json::json!({ "name": "sshd.service", "enabled": true }));
+    }
+
+    let mut files = Vec::new();
+    if let Some((ssid, psk)) = wifi {
+        let nm_profile = format!(
+            "[connection]\nid={ssid}\ntype=wifi\n\n[wifi]\nssid={ssid}\nmode=infrastructure\n\n[wifi-security]\nkey-mgmt=wpa-psk\npsk={psk}\n\n[ipv4]\nmethod=auto\n\n[ipv6]\nmethod=auto\n",
+            ssid = ssid, psk = psk
+        );
+        files.push(serde_json::json!({
+            "path": format!("/etc/NetworkManager/system-connections/{}.nmconnection", ssid),
+            "mode": 0o600,
+            "contents": { "source": format!("data:,{}", percent_encode(&nm_profile)) }
+        }));
+    }
+
+    let config = serde_json::json!({
+        "ignition": { "version": "3.4.0" },
+        "systemd": { "units": units },
+        "storage": { "files": files }
+    });
+
+    let content = serde_json::to_string_pretty(&config)
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to serialize Ignition config: {}", e)))?;
+
+    atomic_write(&boot_mount.join("config.ign"), content.as_bytes())
+}
+
+/// Percent-encodes `input` for use in an Ignition `data:` URL.
+fn percent_encode(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Mounts `device_path`'s boot partition and appends serial-console kernel
+/// arguments to every `linux`/`linuxefi` line in its `grub.cfg`, so headless
+/// boards booted from the burned image are reachable over serial. Marks the
+/// file with `CONSOLE-SETTINGS-START`/`END` on first run; a no-op if the
+/// image has no `grub.cfg` (e.g. plain Raspberry Pi OS boot firmware).
+pub fn inject_serial_console(device_path: &Path) -> Result<()> {
+    let boot = MountedBootPartition::mount(device_path)?;
+
+    let candidates = ["grub/grub.cfg", "grub2/grub.cfg", "EFI/BOOT/grub.cfg", "boot/grub/grub.cfg"];
+    let grub_cfg = candidates.iter()
+        .map(|rel| boot.mount_point.join(rel))
+        .find(|p| p.exists());
+
+    let Some(grub_cfg) = grub_cfg else {
+        println!("ℹ️  No grub.cfg found on boot partition; skipping serial console setup");
+        return Ok(());
+    };
+
+    let content = fs::read_to_string(&grub_cfg)
+        .map_err(|e| UsbBootHutError::Bootloader(format!("Failed to read {}: {}", grub_cfg.display(), e)))?;
+
+    let mut new_config = String::new();
+    if !content.contains(CONSOLE_SETTINGS_START) {
+        new_config.push_str(CONSOLE_SETTINGS_START);
+        new_config.push('\n');
+        new_config.push_str("# usb-boot-hut: serial console args appended to linux/linuxefi lines below by `burn`\n");
+        new_config.push_str(CONSOLE_SETTINGS_END);
+        new_config.push('\n');
+    }
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("linux ") || trimmed.starts_with("linuxefi ") {
+            let indent = &line[..line.len() - trimmed.len()];
+            let mut tokens: Vec<String> = trimmed.split_whitespace().map(String::from).collect();
+            for arg in SERIAL_CONSOLE_ARGS {
+                if !tokens.iter().any(|t| t == arg) {
+                    tokens.push(arg.to_string());
+                }
+            }
+            new_config.push_str(indent);
+            new_config.push_str(&tokens.join(" "));
+        } else {
+            new_config.push_str(line);
+        }
+        new_config.push('\n');
+    }
+
+    atomic_write(&grub_cfg, new_config.as_bytes())?;
+
+    println!("✅ Serial console kernel arguments added to {}", grub_cfg.display());
+    Ok(())
+}
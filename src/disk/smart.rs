@@ -0,0 +1,128 @@
+use crate::{Result, UsbBootHutError};
+use crate::disk::UsbDevice;
+use std::process::Command;
+
+/// SMART attribute IDs we surface to the user.
+const ATTR_REALLOCATED_SECTOR_COUNT: u64 = 5;
+const ATTR_CURRENT_PENDING_SECTOR: u64 = 197;
+const ATTR_REPORTED_UNCORRECTABLE: u64 = 187;
+
+#[derive(Debug, Clone, Default)]
+pub struct SmartHealth {
+    pub passed: bool,
+    pub wear_percent: Option<u8>,
+    pub reallocated: Option<u64>,
+    pub pending: Option<u64>,
+    pub warnings: Vec<String>,
+}
+
+/// Runs `smartctl --json=c -H -A <device>` and parses the overall health
+/// verdict plus the attributes that matter for a flashing tool: reallocated
+/// and pending sector counts, and (for flash media) the wear indicator.
+/// Degrades gracefully to an "unknown" result when smartctl is missing or
+/// the USB bridge doesn't pass SMART data through.
+pub fn smart_health(device: &UsbDevice) -> Result<SmartHealth> {
+    let output = match Command::new("smartctl")
+        .args(["--json=c", "-H", "-A", device.path.to_str().unwrap_or_default()])
+        .output()
+    {
+        Ok(output) => output,
+        Err(_) => {
+            return Ok(SmartHealth {
+                passed: true,
+                warnings: vec!["smartctl not found; SMART status unknown".to_string()],
+                ..Default::default()
+            });
+        }
+    };
+
+    let json: serde_json::Value = match serde_json::from_slice(&output.stdout) {
+        Ok(json) => json,
+        Err(_) => {
+            return Ok(SmartHealth {
+                passed: true,
+                warnings: vec!["Could not read SMART data from this device".to_string()],
+                ..Default::default()
+            });
+        }
+    };
+
+    let mut health = SmartHealth {
+        passed: json["smart_status"]["passed"].as_bool().unwrap_or(true),
+        ..Default::default()
+    };
+
+    if let Some(attrs) = json["ata_smart_attributes"]["table"].as_array() {
+        for attr in attrs {
+            let id = attr["id"].as_u64().unwrap_or(0);
+            let raw = attr["raw"]["value"].as_u64();
+
+            match id {
+                ATTR_REALLOCATED_SECTOR_COUNT => health.reallocated = raw,
+                ATTR_CURRENT_PENDING_SECTOR => health.pending = raw,
+                ATTR_REPORTED_UNCORRECTABLE => {
+                    if let Some(count) = raw {
+                        if count > 0 {
+                            health.warnings.push(format!("{} uncorrectable sector(s) reported", count));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(wear) = json["nvme_smart_health_information_log"]["percentage_used"].as_u64() {
+        health.wear_percent = Some(wear as u8);
+    }
+
+    if let Some(reallocated) = health.reallocated {
+        if reallocated > 0 {
+            health.warnings.push(format!("{} reallocated sector(s)", reallocated));
+        }
+    }
+
+    if let Some(pending) = health.pending {
+        if pending > 0 {
+            health.warnings.push(format!("{} sector(s) pending reallocation", pending));
+        }
+    }
+
+    if let Some(wear) = health.wear_percent {
+        if wear >= 90 {
+            health.warnings.push(format!("Flash wear indicator at {}%", wear));
+        }
+    }
+
+    if !health.passed {
+        health.warnings.insert(0, "SMART overall health check: FAILED".to_string());
+    }
+
+    Ok(health)
+}
+
+/// When `require_healthy` is set, returns `Err` if the drive failed SMART or
+/// its wear indicator is at or above 90%, so the CLI can abort before
+/// writing gigabytes to a dying drive. Without it, always returns the
+/// health report and leaves the warning to the caller.
+pub fn check_health_or_abort(device: &UsbDevice, require_healthy: bool) -> Result<SmartHealth> {
+    let health = smart_health(device)?;
+
+    if !health.passed && require_healthy {
+        return Err(UsbBootHutError::Device(
+            "SMART health check failed; refusing to continue with --require-healthy".to_string()
+        ));
+    }
+
+    if require_healthy {
+        if let Some(wear) = health.wear_percent {
+            if wear >= 90 {
+                return Err(UsbBootHutError::Device(
+                    format!("Flash wear at {}%; refusing to continue with --require-healthy", wear)
+                ));
+            }
+        }
+    }
+
+    Ok(health)
+}
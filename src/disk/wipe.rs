@@ -5,6 +5,155 @@ use std::io::{Write, Seek, SeekFrom, Read};
 use std::path::Path;
 // use colored::*; // Not needed currently
 
+const SECTOR_SIZE: u64 = 512;
+
+/// What `verify_wiped` found still on the device after a wipe, so callers
+/// can report exactly what survived instead of a bare pass/fail.
+#[derive(Debug, Default)]
+pub struct WipeReport {
+    /// Filesystem/partition signatures found by substring scan of the
+    /// first 1 MiB (e.g. "NTFS filesystem").
+    pub signatures: Vec<String>,
+    /// CRC32-validated GPT headers still present, describing where each
+    /// was found (e.g. "primary GPT header at LBA 1").
+    pub gpt_headers: Vec<String>,
+}
+
+impl WipeReport {
+    pub fn is_clean(&self) -> bool {
+        self.signatures.is_empty() && self.gpt_headers.is_empty()
+    }
+}
+
+/// A parsed, CRC32-validated GPT header (primary or backup); fields needed
+/// to locate and destroy both copies of the table and their entry arrays.
+struct GptHeader {
+    my_lba: u64,
+    alternate_lba: u64,
+    partition_entry_lba: u64,
+    num_partition_entries: u32,
+    size_of_partition_entry: u32,
+}
+
+/// Parses a 512-byte LBA as a GPT header: checks the `EFI PART` signature
+/// and recomputes the header CRC32 (over `HeaderSize` bytes with the
+/// stored CRC32 field itself zeroed, per the UEFI spec) before trusting
+/// any field. Returns `None` if the signature or checksum doesn't match,
+/// which is exactly what a wiped or never-partitioned LBA looks like.
+fn parse_gpt_header(sector: &[u8]) -> Option<GptHeader> {
+    if &sector[0..8] != b"EFI PART" {
+        return None;
+    }
+
+    let header_size = u32::from_le_bytes(sector[12..16].try_into().unwrap()) as usize;
+    if header_size < 92 || header_size > sector.len() {
+        return None;
+    }
+
+    let stored_crc32 = u32::from_le_bytes(sector[16..20].try_into().unwrap());
+    let mut header = sector[..header_size].to_vec();
+    header[16..20].copy_from_slice(&[0, 0, 0, 0]);
+    if crc32(&header) != stored_crc32 {
+        return None;
+    }
+
+    Some(GptHeader {
+        my_lba: u64::from_le_bytes(sector[24..32].try_into().unwrap()),
+        alternate_lba: u64::from_le_bytes(sector[32..40].try_into().unwrap()),
+        partition_entry_lba: u64::from_le_bytes(sector[72..80].try_into().unwrap()),
+        num_partition_entries: u32::from_le_bytes(sector[80..84].try_into().unwrap()),
+        size_of_partition_entry: u32::from_le_bytes(sector[84..88].try_into().unwrap()),
+    })
+}
+
+/// Reads `lba` and tries to parse it as a GPT header.
+fn read_gpt_header(file: &mut File, lba: u64) -> Result<Option<GptHeader>> {
+    file.seek(SeekFrom::Start(lba * SECTOR_SIZE))
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to seek to LBA {}: {}", lba, e)))?;
+
+    let mut sector = vec![0u8; SECTOR_SIZE as usize];
+    file.read_exact(&mut sector)
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to read LBA {}: {}", lba, e)))?;
+
+    Ok(parse_gpt_header(&sector))
+}
+
+fn entry_array_sectors(header: &GptHeader) -> u64 {
+    let bytes = header.num_partition_entries as u64 * header.size_of_partition_entry as u64;
+    (bytes + SECTOR_SIZE - 1) / SECTOR_SIZE
+}
+
+/// Byte ranges that must be overwritten to destroy every GPT structure on
+/// the device: the protective MBR, the primary header with its partition
+/// entry array, and the backup header with its own entry array (which
+/// immediately precedes it, at the very last LBA). Falls back to the
+/// historical "zero the first and last 1 MiB" behavior when no valid GPT
+/// is found at all, so plain-MBR devices are still wiped.
+fn gpt_wipe_regions(file: &mut File, size: u64) -> Result<Vec<(u64, u64)>> {
+    let mut regions = vec![(0u64, SECTOR_SIZE)];
+
+    let primary = read_gpt_header(file, 1)?;
+    let last_lba = size / SECTOR_SIZE - 1;
+    let backup = read_gpt_header(file, last_lba)?;
+
+    if primary.is_none() && backup.is_none() {
+        regions.push((0, size.min(1024 * 1024)));
+        let tail_start = size.saturating_sub(1024 * 1024);
+        regions.push((tail_start, size - tail_start));
+        return Ok(regions);
+    }
+
+    if let Some(header) = &primary {
+        regions.push((header.my_lba * SECTOR_SIZE, SECTOR_SIZE));
+        regions.push((
+            header.partition_entry_lba * SECTOR_SIZE,
+            entry_array_sectors(header) * SECTOR_SIZE,
+        ));
+    }
+
+    // A well-formed GPT mirrors the entry count/size in both headers, so
+    // whichever header is still intact tells us where the backup's entry
+    // array and header sit, even if the other copy is already destroyed.
+    if let Some(header) = backup.as_ref().or(primary.as_ref()) {
+        let backup_lba = backup.as_ref().map_or(header.alternate_lba, |b| b.my_lba);
+        let entry_sectors = entry_array_sectors(header);
+        regions.push((
+            backup_lba.saturating_sub(entry_sectors) * SECTOR_SIZE,
+            entry_sectors * SECTOR_SIZE,
+        ));
+        regions.push((backup_lba * SECTOR_SIZE, SECTOR_SIZE));
+    }
+
+    Ok(regions)
+}
+
+fn wipe_region(file: &mut File, offset: u64, len: u64) -> Result<()> {
+    if len == 0 {
+        return Ok(());
+    }
+
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to seek to {}: {}", offset, e)))?;
+    file.write_all(&vec![0u8; len as usize])
+        .map_err(|e| UsbBootHutError::Device(format!("Failed to wipe {} bytes at {}: {}", len, offset, e)))?;
+
+    Ok(())
+}
+
+/// CRC-32/ISO-HDLC (the variant GPT headers use): reflected input/output,
+/// polynomial `0xEDB88320`, initial and final XOR of all-ones.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFFFFFF;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB88320 & mask);
+        }
+    }
+    !crc
+}
+
 pub struct SecureWipe<'a> {
     device_path: &'a Path,
 }
@@ -14,38 +163,33 @@ impl<'a> SecureWipe<'a> {
         Self { device_path }
     }
     
+    /// Locates the real GPT structures — protective MBR, primary header
+    /// and entry array, backup header and entry array at the true last
+    /// LBA — and overwrites exactly those, rather than assuming they all
+    /// live within the first/last 1 MiB.
     pub fn quick_wipe(&self) -> Result<()> {
-        // Just wipe the first and last 1MB to destroy partition tables
         let mut file = OpenOptions::new()
+            .read(true)
             .write(true)
             .open(self.device_path)
             .map_err(|e| UsbBootHutError::Device(format!("Failed to open device: {}", e)))?;
-            
-        // Wipe first 1MB
-        let zeros = vec![0u8; 1024 * 1024];
-        file.write_all(&zeros)
-            .map_err(|e| UsbBootHutError::Device(format!("Failed to wipe start: {}", e)))?;
-            
-        // Get device size and wipe last 1MB
+
         let size = file.seek(SeekFrom::End(0))
             .map_err(|e| UsbBootHutError::Device(format!("Failed to seek: {}", e)))?;
-            
-        if size > 1024 * 1024 {
-            file.seek(SeekFrom::Start(size - 1024 * 1024))
-                .map_err(|e| UsbBootHutError::Device(format!("Failed to seek to end: {}", e)))?;
-            file.write_all(&zeros)
-                .map_err(|e| UsbBootHutError::Device(format!("Failed to wipe end: {}", e)))?;
+
+        for (offset, len) in gpt_wipe_regions(&mut file, size)? {
+            wipe_region(&mut file, offset, len)?;
         }
-        
+
         file.sync_all()
             .map_err(|e| UsbBootHutError::Device(format!("Failed to sync: {}", e)))?;
-            
+
         Ok(())
     }
     
     pub fn wipe_with_progress<F>(&self, mut progress_callback: F) -> Result<()>
     where
-        F: FnMut(u8), // Progress from 0-100
+        F: FnMut(u64, u64), // (bytes_written, total_bytes)
     {
         let mut file = OpenOptions::new()
             .write(true)
@@ -82,98 +226,191 @@ impl<'a> SecureWipe<'a> {
                 .map_err(|e| UsbBootHutError::Device(format!("Failed to write: {}", e)))?;
                 
             written += to_write as u64;
-            
-            // Update progress
-            let progress = ((written as f64 / size as f64) * 100.0) as u8;
-            progress_callback(progress);
+            progress_callback(written, size);
         }
-        
+
         file.sync_all()
             .map_err(|e| UsbBootHutError::Device(format!("Failed to sync: {}", e)))?;
-            
+
         Ok(())
     }
-    
-    pub fn verify_wiped(&self) -> Result<bool> {
-        // Read first 1MB and check if it's all zeros or random
+
+    /// Scans for residual filesystem signatures in the first 1 MiB, and
+    /// separately validates the primary and backup GPT headers (at LBA 1
+    /// and the true last LBA) by recomputing their CRC32, rather than
+    /// just grepping for the `"EFI PART"` substring. Returns a report of
+    /// everything still found instead of a bare bool, so callers can show
+    /// the operator exactly what survived.
+    pub fn verify_wiped(&self) -> Result<WipeReport> {
         let mut file = File::open(self.device_path)
             .map_err(|e| UsbBootHutError::Device(format!("Failed to open device: {}", e)))?;
-            
+
+        let size = file.seek(SeekFrom::End(0))
+            .map_err(|e| UsbBootHutError::Device(format!("Failed to seek: {}", e)))?;
+        file.seek(SeekFrom::Start(0))
+            .map_err(|e| UsbBootHutError::Device(format!("Failed to seek: {}", e)))?;
+
+        let mut report = WipeReport::default();
+
         let mut buffer = vec![0u8; 1024 * 1024];
         file.read_exact(&mut buffer)
             .map_err(|e| UsbBootHutError::Device(format!("Failed to read: {}", e)))?;
-            
-        // Check for common partition signatures
-        let signatures = [
-            &b"EFI PART"[..], // GPT
-            &b"\x55\xAA"[..], // MBR boot signature at offset 510
-            &b"NTFS"[..],     // NTFS
-            &b"FAT32"[..],    // FAT32
-            &b"\x53\xEF"[..], // ext2/3/4 at offset 0x438
+
+        // Common partition/filesystem signatures; GPT is checked
+        // separately below via header CRC32, not a bare substring match.
+        let signatures: [(&[u8], &str); 4] = [
+            (&b"\x55\xAA"[..], "MBR boot signature"), // offset 510
+            (&b"NTFS"[..], "NTFS filesystem"),
+            (&b"FAT32"[..], "FAT32 filesystem"),
+            (&b"\x53\xEF"[..], "ext2/3/4 superblock magic"), // offset 0x438
         ];
-        
-        for sig in &signatures {
+
+        for (sig, name) in &signatures {
             if buffer.windows(sig.len()).any(|w| w == *sig) {
-                return Ok(false); // Found a signature, not wiped
+                report.signatures.push(name.to_string());
             }
         }
-        
-        Ok(true)
+
+        if let Some(header) = read_gpt_header(&mut file, 1)? {
+            report.gpt_headers.push(format!(
+                "primary GPT header at LBA {} (header CRC32 valid)", header.my_lba
+            ));
+        }
+
+        let last_lba = size / SECTOR_SIZE - 1;
+        if let Some(header) = read_gpt_header(&mut file, last_lba)? {
+            report.gpt_headers.push(format!(
+                "backup GPT header at LBA {} (header CRC32 valid)", header.my_lba
+            ));
+        }
+
+        Ok(report)
     }
     
-    pub fn nuke_drive(&self, pattern: WipePattern, passes: u8, progress_callback: impl Fn(u8, u8, &str)) -> Result<()> {
+    pub fn nuke_drive(&self, pattern: WipePattern, passes: u8, progress_callback: impl Fn(u8, u8, &str, u64, u64)) -> Result<()> {
         match pattern {
             WipePattern::Random => self.nuke_random(passes, progress_callback),
             WipePattern::Zeros => self.nuke_zeros(passes, progress_callback),
             WipePattern::Dod => self.nuke_dod(progress_callback),
             WipePattern::Gutmann => self.nuke_gutmann(progress_callback),
+            WipePattern::HardwareSecure => self.nuke_hardware_secure(passes, progress_callback),
+        }
+    }
+
+    /// Tries `hardware_erase()` first; multi-pass overwrite is meaningless
+    /// (and just adds wear) on wear-levelled flash, so this is strictly
+    /// preferred when the kernel supports it. Falls back to a random
+    /// overwrite, with a warning, when the device has no discard/sanitize
+    /// support at all.
+    fn nuke_hardware_secure(&self, passes: u8, progress_callback: impl Fn(u8, u8, &str, u64, u64)) -> Result<()> {
+        progress_callback(1, 1, "Checking hardware erase support (discard/secure discard)...", 0, 0);
+
+        match self.hardware_erase() {
+            Ok(method) => {
+                progress_callback(1, 1, &format!("Hardware erase complete ({})", method), 0, 0);
+                Ok(())
+            }
+            Err(e) => {
+                progress_callback(1, 1, &format!(
+                    "Hardware erase unavailable ({}); falling back to random overwrite", e
+                ), 0, 0);
+                self.nuke_random(passes, progress_callback)
+            }
         }
     }
+
+    /// Issues a block-discard/firmware-sanitize command instead of streaming
+    /// overwrite bytes: `BLKSECDISCARD` (the kernel's secure-discard path,
+    /// which SATA/NVMe drivers translate into ATA `SECURITY ERASE UNIT` or
+    /// an NVMe secure `Format NVM`, respectively) if the device advertises
+    /// discard support, falling back to plain `BLKDISCARD` (TRIM) if the
+    /// device doesn't support the secure variant. Returns which method was
+    /// used, or an error (callers should fall back to overwrite) if the
+    /// device supports neither.
+    #[cfg(target_os = "linux")]
+    pub fn hardware_erase(&self) -> Result<&'static str> {
+        use std::os::unix::io::AsRawFd;
+
+        if !discard_supported(self.device_path) {
+            return Err(UsbBootHutError::Device(
+                "Device does not advertise discard support".to_string()
+            ));
+        }
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(self.device_path)
+            .map_err(|e| UsbBootHutError::Device(format!("Failed to open device: {}", e)))?;
+
+        let size = file.seek(SeekFrom::End(0))
+            .map_err(|e| UsbBootHutError::Device(format!("Failed to get device size: {}", e)))?;
+        let range: [u64; 2] = [0, size];
+        let fd = file.as_raw_fd();
+
+        let rc = unsafe { libc::ioctl(fd, BLKSECDISCARD, range.as_ptr()) };
+        if rc == 0 {
+            return Ok("BLKSECDISCARD");
+        }
+
+        let rc = unsafe { libc::ioctl(fd, BLKDISCARD, range.as_ptr()) };
+        if rc == 0 {
+            return Ok("BLKDISCARD");
+        }
+
+        Err(UsbBootHutError::Device(format!(
+            "Hardware erase ioctls failed: {}", std::io::Error::last_os_error()
+        )))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn hardware_erase(&self) -> Result<&'static str> {
+        Err(UsbBootHutError::Device("Hardware erase is only supported on Linux".to_string()))
+    }
     
-    fn nuke_random(&self, passes: u8, progress_callback: impl Fn(u8, u8, &str)) -> Result<()> {
+    fn nuke_random(&self, passes: u8, progress_callback: impl Fn(u8, u8, &str, u64, u64)) -> Result<()> {
         for pass in 1..=passes {
-            progress_callback(pass, passes, &format!("Pass {}/{}: Writing random data", pass, passes));
-            self.wipe_with_random(|percent| {
-                progress_callback(pass, passes, &format!("Pass {}/{}: {}%", pass, passes, percent));
+            progress_callback(pass, passes, "Writing random data", 0, 0);
+            self.wipe_with_random(|written, total| {
+                progress_callback(pass, passes, "Writing random data", written, total);
             })?;
         }
         Ok(())
     }
-    
-    fn nuke_zeros(&self, passes: u8, progress_callback: impl Fn(u8, u8, &str)) -> Result<()> {
+
+    fn nuke_zeros(&self, passes: u8, progress_callback: impl Fn(u8, u8, &str, u64, u64)) -> Result<()> {
         for pass in 1..=passes {
-            progress_callback(pass, passes, &format!("Pass {}/{}: Writing zeros", pass, passes));
-            self.wipe_with_zeros(|percent| {
-                progress_callback(pass, passes, &format!("Pass {}/{}: {}%", pass, passes, percent));
+            progress_callback(pass, passes, "Writing zeros", 0, 0);
+            self.wipe_with_zeros(|written, total| {
+                progress_callback(pass, passes, "Writing zeros", written, total);
             })?;
         }
         Ok(())
     }
-    
-    fn nuke_dod(&self, progress_callback: impl Fn(u8, u8, &str)) -> Result<()> {
+
+    fn nuke_dod(&self, progress_callback: impl Fn(u8, u8, &str, u64, u64)) -> Result<()> {
         // DoD 5220.22-M: 3 passes
         // Pass 1: Write zeros
-        progress_callback(1, 3, "Pass 1/3: Writing zeros (DoD 5220.22-M)");
-        self.wipe_with_zeros(|percent| {
-            progress_callback(1, 3, &format!("Pass 1/3: {}%", percent));
+        progress_callback(1, 3, "Writing zeros (DoD 5220.22-M)", 0, 0);
+        self.wipe_with_zeros(|written, total| {
+            progress_callback(1, 3, "Writing zeros (DoD 5220.22-M)", written, total);
         })?;
-        
+
         // Pass 2: Write ones (0xFF)
-        progress_callback(2, 3, "Pass 2/3: Writing ones (DoD 5220.22-M)");
-        self.wipe_with_pattern(0xFF, |percent| {
-            progress_callback(2, 3, &format!("Pass 2/3: {}%", percent));
+        progress_callback(2, 3, "Writing ones (DoD 5220.22-M)", 0, 0);
+        self.wipe_with_pattern(0xFF, |written, total| {
+            progress_callback(2, 3, "Writing ones (DoD 5220.22-M)", written, total);
         })?;
-        
+
         // Pass 3: Write random
-        progress_callback(3, 3, "Pass 3/3: Writing random data (DoD 5220.22-M)");
-        self.wipe_with_random(|percent| {
-            progress_callback(3, 3, &format!("Pass 3/3: {}%", percent));
+        progress_callback(3, 3, "Writing random data (DoD 5220.22-M)", 0, 0);
+        self.wipe_with_random(|written, total| {
+            progress_callback(3, 3, "Writing random data (DoD 5220.22-M)", written, total);
         })?;
-        
+
         Ok(())
     }
     
-    fn nuke_gutmann(&self, progress_callback: impl Fn(u8, u8, &str)) -> Result<()> {
+    fn nuke_gutmann(&self, progress_callback: impl Fn(u8, u8, &str, u64, u64)) -> Result<()> {
         // Gutmann method: 35 passes with specific patterns
         let patterns: Vec<Vec<u8>> = vec![
             // Random passes
@@ -215,15 +452,15 @@ impl<'a> SecureWipe<'a> {
             
             if pattern.is_empty() {
                 // Random pass
-                progress_callback(pass, 35, &format!("Pass {}/35: Writing random (Gutmann)", pass));
-                self.wipe_with_random(|percent| {
-                    progress_callback(pass, 35, &format!("Pass {}/35: {}%", pass, percent));
+                progress_callback(pass, 35, "Writing random data (Gutmann)", 0, 0);
+                self.wipe_with_random(|written, total| {
+                    progress_callback(pass, 35, "Writing random data (Gutmann)", written, total);
                 })?;
             } else {
                 // Pattern pass
-                progress_callback(pass, 35, &format!("Pass {}/35: Writing pattern (Gutmann)", pass));
-                self.wipe_with_repeating_pattern(pattern, |percent| {
-                    progress_callback(pass, 35, &format!("Pass {}/35: {}%", pass, percent));
+                progress_callback(pass, 35, "Writing pattern (Gutmann)", 0, 0);
+                self.wipe_with_repeating_pattern(pattern, |written, total| {
+                    progress_callback(pass, 35, "Writing pattern (Gutmann)", written, total);
                 })?;
             }
         }
@@ -233,14 +470,14 @@ impl<'a> SecureWipe<'a> {
     
     fn wipe_with_zeros<F>(&self, progress_callback: F) -> Result<()>
     where
-        F: FnMut(u8),
+        F: FnMut(u64, u64),
     {
         self.wipe_with_pattern(0x00, progress_callback)
     }
-    
+
     fn wipe_with_pattern<F>(&self, byte: u8, mut progress_callback: F) -> Result<()>
     where
-        F: FnMut(u8),
+        F: FnMut(u64, u64),
     {
         let mut file = OpenOptions::new()
             .write(true)
@@ -262,19 +499,18 @@ impl<'a> SecureWipe<'a> {
                 .map_err(|e| UsbBootHutError::Device(format!("Failed to write: {}", e)))?;
                 
             written += to_write as u64;
-            let progress = ((written as f64 / size as f64) * 100.0) as u8;
-            progress_callback(progress);
+            progress_callback(written, size);
         }
-        
+
         file.sync_all()
             .map_err(|e| UsbBootHutError::Device(format!("Failed to sync: {}", e)))?;
-            
+
         Ok(())
     }
-    
+
     fn wipe_with_repeating_pattern<F>(&self, pattern: &[u8], mut progress_callback: F) -> Result<()>
     where
-        F: FnMut(u8),
+        F: FnMut(u64, u64),
     {
         if pattern.is_empty() {
             return Err(UsbBootHutError::Device("Empty pattern".to_string()));
@@ -306,20 +542,43 @@ impl<'a> SecureWipe<'a> {
                 .map_err(|e| UsbBootHutError::Device(format!("Failed to write: {}", e)))?;
                 
             written += to_write as u64;
-            let progress = ((written as f64 / size as f64) * 100.0) as u8;
-            progress_callback(progress);
+            progress_callback(written, size);
         }
-        
+
         file.sync_all()
             .map_err(|e| UsbBootHutError::Device(format!("Failed to sync: {}", e)))?;
-            
+
         Ok(())
     }
-    
+
     fn wipe_with_random<F>(&self, progress_callback: F) -> Result<()>
     where
-        F: FnMut(u8),
+        F: FnMut(u64, u64),
     {
         self.wipe_with_progress(progress_callback)
     }
+}
+
+/// `BLKDISCARD`/`BLKSECDISCARD`: `_IO(0x12,119)` / `_IO(0x12,125)` from
+/// `linux/fs.h`. Both take a `uint64_t[2]` of `[offset, length]` naming the
+/// byte range to discard/sanitize.
+#[cfg(target_os = "linux")]
+const BLKDISCARD: libc::c_ulong = 0x1277;
+#[cfg(target_os = "linux")]
+const BLKSECDISCARD: libc::c_ulong = 0x127D;
+
+/// Whether the kernel advertises discard (TRIM) support for `device_path`,
+/// read from `/sys/block/<name>/queue/discard_max_bytes` the same way
+/// `enumerate_usb_devices` reads other per-device sysfs attributes.
+#[cfg(target_os = "linux")]
+fn discard_supported(device_path: &Path) -> bool {
+    let Some(name) = device_path.to_str().and_then(|p| p.strip_prefix("/dev/")) else {
+        return false;
+    };
+
+    std::fs::read_to_string(format!("/sys/block/{}/queue/discard_max_bytes", name))
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(|max| max > 0)
+        .unwrap_or(false)
 }
\ No newline at end of file
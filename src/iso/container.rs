@@ -0,0 +1,303 @@
+use crate::{Result, UsbBootHutError, IoContext};
+use sha2::{Sha256, Digest};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Magic bytes identifying our chunked container format; checked by
+/// `iso::image::open_image` alongside CISO/WBFS/gzip/etc, so archived ISOs
+/// get transparently decompressed by every reader that already goes through
+/// `open_image`/`materialize` (validation, checksumming, burning).
+pub const MAGIC: &[u8; 4] = b"UBHC";
+const CONTAINER_VERSION: u8 = 1;
+
+/// Chunks are compressed independently, trading a little ratio for the
+/// ability to decompress (or, eventually, seek to) any one chunk without
+/// touching its neighbours.
+const CHUNK_SIZE: u32 = 2 * 1024 * 1024;
+
+/// Header fields preceding the chunk table in a container file, in the
+/// order they're written: magic, version, codec, 2 reserved bytes,
+/// chunk_size, uncompressed_len, sha256 of the uncompressed ISO, chunk_count.
+const HEADER_LEN: u64 = 4 + 1 + 1 + 2 + 4 + 8 + 32 + 4;
+
+/// Compression codec for an archived ISO container, selectable per-archive
+/// and gated behind the same cargo features as `iso::image`'s source
+/// decompression (`zstd`/`bzip2`/`xz`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum ContainerCodec {
+    /// No compression; used for already-dense sources or when no codec
+    /// feature is compiled in.
+    None,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl ContainerCodec {
+    fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Zstd),
+            2 => Ok(Self::Bzip2),
+            3 => Ok(Self::Xz),
+            other => Err(UsbBootHutError::Iso(format!("Unknown container codec tag: {}", other))),
+        }
+    }
+
+    fn tag(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zstd => 1,
+            Self::Bzip2 => 2,
+            Self::Xz => 3,
+        }
+    }
+}
+
+/// Recorded in `IsoMetadata` once an ISO has been archived into our
+/// container format, so `list` can report real space savings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCompression {
+    pub codec: ContainerCodec,
+    pub compressed_size: u64,
+}
+
+impl StoredCompression {
+    /// Compressed size as a fraction of `uncompressed_size` (e.g. `0.4` for
+    /// a container taking 40% of the original ISO's space).
+    pub fn ratio(&self, uncompressed_size: u64) -> f64 {
+        if uncompressed_size == 0 {
+            return 1.0;
+        }
+        self.compressed_size as f64 / uncompressed_size as f64
+    }
+}
+
+/// Splits `src_path` into `CHUNK_SIZE` chunks, compresses each independently
+/// with `codec`, and writes the result to `dest_path` as a container file:
+/// a header (codec, chunk size, uncompressed length, SHA-256 of the
+/// uncompressed bytes), a table of per-chunk compressed lengths, then the
+/// compressed chunks themselves.
+pub fn write_container(src_path: &Path, dest_path: &Path, codec: ContainerCodec) -> Result<StoredCompression> {
+    let mut src = File::open(src_path).io_context("open ISO for archiving", src_path)?;
+    let uncompressed_len = src.metadata().io_context("stat ISO", src_path)?.len();
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE as usize];
+    let mut chunk_sizes: Vec<u32> = Vec::new();
+    let mut compressed_chunks: Vec<Vec<u8>> = Vec::new();
+
+    loop {
+        let bytes_read = src.read(&mut buffer).io_context("read ISO", src_path)?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        let chunk = &buffer[..bytes_read];
+        hasher.update(chunk);
+
+        let compressed = compress_chunk(codec, chunk)?;
+        chunk_sizes.push(compressed.len() as u32);
+        compressed_chunks.push(compressed);
+    }
+
+    let checksum: [u8; 32] = hasher.finalize().into();
+
+    let mut out = File::create(dest_path).io_context("create container", dest_path)?;
+    out.write_all(MAGIC).io_context("write container header", dest_path)?;
+    out.write_all(&[CONTAINER_VERSION, codec.tag(), 0, 0]).io_context("write container header", dest_path)?;
+    out.write_all(&CHUNK_SIZE.to_le_bytes()).io_context("write container header", dest_path)?;
+    out.write_all(&uncompressed_len.to_le_bytes()).io_context("write container header", dest_path)?;
+    out.write_all(&checksum).io_context("write container header", dest_path)?;
+    out.write_all(&(chunk_sizes.len() as u32).to_le_bytes()).io_context("write container header", dest_path)?;
+    for size in &chunk_sizes {
+        out.write_all(&size.to_le_bytes()).io_context("write container chunk table", dest_path)?;
+    }
+    for chunk in &compressed_chunks {
+        out.write_all(chunk).io_context("write container chunk", dest_path)?;
+    }
+    out.sync_all().io_context("sync container", dest_path)?;
+
+    let compressed_size = HEADER_LEN + chunk_sizes.len() as u64 * 4 + chunk_sizes.iter().map(|&s| s as u64).sum::<u64>();
+
+    Ok(StoredCompression { codec, compressed_size })
+}
+
+struct ContainerHeader {
+    codec: ContainerCodec,
+    chunk_size: u32,
+    uncompressed_len: u64,
+    chunk_table: Vec<u32>,
+    /// Byte offset of each chunk's compressed data within the file.
+    chunk_offsets: Vec<u64>,
+}
+
+fn read_header(file: &mut File) -> Result<ContainerHeader> {
+    let mut fixed = [0u8; HEADER_LEN as usize];
+    file.read_exact(&mut fixed).map_err(|e| UsbBootHutError::Iso(format!("Failed to read container header: {}", e)))?;
+
+    if &fixed[0..4] != MAGIC {
+        return Err(UsbBootHutError::Iso("Not a usb-boot-hut container".to_string()));
+    }
+
+    let codec = ContainerCodec::from_tag(fixed[5])?;
+    let chunk_size = u32::from_le_bytes(fixed[8..12].try_into().unwrap());
+    let uncompressed_len = u64::from_le_bytes(fixed[12..20].try_into().unwrap());
+    let chunk_count = u32::from_le_bytes(fixed[52..56].try_into().unwrap()) as usize;
+
+    let mut table_bytes = vec![0u8; chunk_count * 4];
+    file.read_exact(&mut table_bytes).map_err(|e| UsbBootHutError::Iso(format!("Failed to read container chunk table: {}", e)))?;
+    let chunk_table: Vec<u32> = table_bytes.chunks_exact(4).map(|b| u32::from_le_bytes(b.try_into().unwrap())).collect();
+
+    let mut offset = HEADER_LEN + chunk_count as u64 * 4;
+    let mut chunk_offsets = Vec::with_capacity(chunk_count);
+    for &size in &chunk_table {
+        chunk_offsets.push(offset);
+        offset += size as u64;
+    }
+
+    Ok(ContainerHeader { codec, chunk_size, uncompressed_len, chunk_table, chunk_offsets })
+}
+
+/// The decompressed size a container holds, read straight from its header
+/// without decompressing a single chunk (used by `known_image_size`).
+pub fn uncompressed_len(file: &mut File) -> Result<u64> {
+    Ok(read_header(file)?.uncompressed_len)
+}
+
+/// Sequentially decompresses a container one chunk at a time, so every
+/// reader that already goes through `open_image` (validation, checksumming,
+/// burning) can treat an archived ISO exactly like a plain one.
+pub struct ContainerReader {
+    file: File,
+    header: ContainerHeader,
+    position: u64,
+    current_chunk: Option<(usize, Vec<u8>)>,
+}
+
+impl ContainerReader {
+    pub fn new(mut file: File) -> Result<Self> {
+        let header = read_header(&mut file)?;
+        Ok(Self { file, header, position: 0, current_chunk: None })
+    }
+
+    fn load_chunk(&mut self, index: usize) -> Result<()> {
+        if self.current_chunk.as_ref().is_some_and(|(i, _)| *i == index) {
+            return Ok(());
+        }
+
+        use std::io::{Seek, SeekFrom};
+        self.file.seek(SeekFrom::Start(self.header.chunk_offsets[index]))
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek container chunk: {}", e)))?;
+
+        let compressed_len = self.header.chunk_table[index] as usize;
+        let mut compressed = vec![0u8; compressed_len];
+        self.file.read_exact(&mut compressed)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read container chunk: {}", e)))?;
+
+        let chunk_start = index as u64 * self.header.chunk_size as u64;
+        let expected_len = (self.header.uncompressed_len - chunk_start).min(self.header.chunk_size as u64) as usize;
+        let decompressed = decompress_chunk(self.header.codec, &compressed, expected_len)?;
+
+        // `expected_len` is only a capacity hint to the bzip2/xz decoders,
+        // not an enforced output size; a truncated or corrupt chunk could
+        // still decompress to something shorter, which would otherwise
+        // underflow `chunk.len() - offset_in_chunk` in `read` below.
+        if decompressed.len() != expected_len {
+            return Err(UsbBootHutError::Iso(format!(
+                "Container chunk {} decompressed to {} bytes, expected {}",
+                index, decompressed.len(), expected_len
+            )));
+        }
+
+        self.current_chunk = Some((index, decompressed));
+        Ok(())
+    }
+}
+
+impl Read for ContainerReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position >= self.header.uncompressed_len {
+            return Ok(0);
+        }
+
+        let chunk_index = (self.position / self.header.chunk_size as u64) as usize;
+        self.load_chunk(chunk_index).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+        let (_, chunk) = self.current_chunk.as_ref().unwrap();
+        let offset_in_chunk = (self.position % self.header.chunk_size as u64) as usize;
+        let to_copy = buf.len().min(chunk.len() - offset_in_chunk);
+        buf[..to_copy].copy_from_slice(&chunk[offset_in_chunk..offset_in_chunk + to_copy]);
+
+        self.position += to_copy as u64;
+        Ok(to_copy)
+    }
+}
+
+fn compress_chunk(codec: ContainerCodec, data: &[u8]) -> Result<Vec<u8>> {
+    match codec {
+        ContainerCodec::None => Ok(data.to_vec()),
+        ContainerCodec::Zstd => {
+            #[cfg(feature = "zstd")]
+            { zstd::bulk::compress(data, 0).map_err(|e| UsbBootHutError::Iso(format!("zstd compress failed: {}", e))) }
+            #[cfg(not(feature = "zstd"))]
+            { Err(UsbBootHutError::Iso("zstd support not compiled in (enable the \"zstd\" feature)".to_string())) }
+        }
+        ContainerCodec::Bzip2 => {
+            #[cfg(feature = "bzip2")]
+            {
+                let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(data).map_err(|e| UsbBootHutError::Iso(format!("bzip2 compress failed: {}", e)))?;
+                encoder.finish().map_err(|e| UsbBootHutError::Iso(format!("bzip2 compress failed: {}", e)))
+            }
+            #[cfg(not(feature = "bzip2"))]
+            { Err(UsbBootHutError::Iso("bzip2 support not compiled in (enable the \"bzip2\" feature)".to_string())) }
+        }
+        ContainerCodec::Xz => {
+            #[cfg(feature = "xz")]
+            {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(data).map_err(|e| UsbBootHutError::Iso(format!("xz compress failed: {}", e)))?;
+                encoder.finish().map_err(|e| UsbBootHutError::Iso(format!("xz compress failed: {}", e)))
+            }
+            #[cfg(not(feature = "xz"))]
+            { Err(UsbBootHutError::Iso("xz/lzma support not compiled in (enable the \"xz\" feature)".to_string())) }
+        }
+    }
+}
+
+fn decompress_chunk(codec: ContainerCodec, data: &[u8], expected_len: usize) -> Result<Vec<u8>> {
+    match codec {
+        ContainerCodec::None => Ok(data.to_vec()),
+        ContainerCodec::Zstd => {
+            #[cfg(feature = "zstd")]
+            { zstd::bulk::decompress(data, expected_len).map_err(|e| UsbBootHutError::Iso(format!("zstd decompress failed: {}", e))) }
+            #[cfg(not(feature = "zstd"))]
+            { Err(UsbBootHutError::Iso("zstd support not compiled in (enable the \"zstd\" feature)".to_string())) }
+        }
+        ContainerCodec::Bzip2 => {
+            #[cfg(feature = "bzip2")]
+            {
+                let mut decoder = bzip2::read::BzDecoder::new(data);
+                let mut out = Vec::with_capacity(expected_len);
+                decoder.read_to_end(&mut out).map_err(|e| UsbBootHutError::Iso(format!("bzip2 decompress failed: {}", e)))?;
+                Ok(out)
+            }
+            #[cfg(not(feature = "bzip2"))]
+            { Err(UsbBootHutError::Iso("bzip2 support not compiled in (enable the \"bzip2\" feature)".to_string())) }
+        }
+        ContainerCodec::Xz => {
+            #[cfg(feature = "xz")]
+            {
+                let mut decoder = xz2::read::XzDecoder::new(data);
+                let mut out = Vec::with_capacity(expected_len);
+                decoder.read_to_end(&mut out).map_err(|e| UsbBootHutError::Iso(format!("xz decompress failed: {}", e)))?;
+                Ok(out)
+            }
+            #[cfg(not(feature = "xz"))]
+            { Err(UsbBootHutError::Iso("xz/lzma support not compiled in (enable the \"xz\" feature)".to_string())) }
+        }
+    }
+}
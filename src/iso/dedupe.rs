@@ -0,0 +1,143 @@
+use crate::{Result, UsbBootHutError};
+use crate::iso::MetadataStore;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use sha2::{Sha256, Digest};
+
+/// How much of a file to read for the cheap pre-filter hash, before
+/// falling back to a full SHA256. Large enough to split most false
+/// collisions from the size bucketing, small enough to stay cheap even
+/// on a spinning disk.
+const PARTIAL_HASH_SIZE: usize = 64 * 1024;
+
+/// A set of files under the ISO directory with byte-identical content,
+/// found by `IsoManager::find_duplicates`.
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub checksum: String,
+    pub size: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// Bytes reclaimable by keeping a single copy and deleting the rest.
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+/// Finds byte-identical files under `iso_dir` using a three-stage filter:
+/// exact size, then a partial hash of the first 64KB, then a full SHA256.
+/// Each stage only re-checks files that collided in the previous one, so a
+/// directory of same-sized-but-different ISOs never pays for a full hash.
+///
+/// Checksums already recorded in `metadata_store` are reused instead of
+/// rehashing an ISO that's already known.
+pub fn find_duplicates(iso_dir: &Path, metadata_store: &MetadataStore) -> Result<Vec<DuplicateGroup>> {
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+
+    let entries = fs::read_dir(iso_dir)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to read ISO dir: {}", e)))?;
+
+    for entry in entries {
+        let entry = entry
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read dir entry: {}", e)))?;
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let size = entry
+            .metadata()
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to stat {}: {}", path.display(), e)))?
+            .len();
+
+        by_size.entry(size).or_default().push(path);
+    }
+
+    let known_checksums = known_checksums_by_filename(iso_dir, metadata_store);
+
+    let mut groups = Vec::new();
+
+    for (size, paths) in by_size {
+        // A unique size can never collide with anything else; skip hashing.
+        if paths.len() < 2 {
+            continue;
+        }
+
+        let mut by_partial: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for path in paths {
+            let partial = partial_hash(&path, size)?;
+            by_partial.entry(partial).or_default().push(path);
+        }
+
+        for (_, candidates) in by_partial {
+            if candidates.len() < 2 {
+                continue;
+            }
+
+            let mut by_full: HashMap<String, Vec<PathBuf>> = HashMap::new();
+            for path in candidates {
+                let checksum = match known_checksums.get(&path) {
+                    Some(checksum) => checksum.clone(),
+                    None => full_hash(&path)?,
+                };
+                by_full.entry(checksum).or_default().push(path);
+            }
+
+            for (checksum, paths) in by_full {
+                if paths.len() > 1 {
+                    groups.push(DuplicateGroup { checksum, size, paths });
+                }
+            }
+        }
+    }
+
+    Ok(groups)
+}
+
+/// Maps each ISO's on-disk path to its already-recorded checksum, so
+/// `find_duplicates` can skip rehashing files `MetadataStore` already knows.
+fn known_checksums_by_filename(iso_dir: &Path, metadata_store: &MetadataStore) -> HashMap<PathBuf, String> {
+    metadata_store
+        .list_all()
+        .iter()
+        .map(|m| (iso_dir.join(&m.filename), m.checksum.clone()))
+        .collect()
+}
+
+fn partial_hash(path: &Path, size: u64) -> Result<String> {
+    let mut file = File::open(path)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to open {}: {}", path.display(), e)))?;
+
+    let to_read = (size as usize).min(PARTIAL_HASH_SIZE);
+    let mut buffer = vec![0u8; to_read];
+    file.read_exact(&mut buffer)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to read {}: {}", path.display(), e)))?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(&buffer);
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn full_hash(path: &Path) -> Result<String> {
+    let mut file = File::open(path)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to open {}: {}", path.display(), e)))?;
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 4 * 1024 * 1024];
+
+    loop {
+        let bytes_read = file
+            .read(&mut buffer)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read {}: {}", path.display(), e)))?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
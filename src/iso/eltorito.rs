@@ -0,0 +1,158 @@
+use crate::{Result, UsbBootHutError};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const SECTOR_SIZE: u64 = 2048;
+const BOOT_RECORD_LBA: u64 = 17;
+const CATALOG_POINTER_OFFSET: usize = 0x47;
+
+/// Platform ID from an El Torito validation entry or section header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    X86,
+    PowerPc,
+    Mac,
+    Uefi,
+    Unknown(u8),
+}
+
+impl Platform {
+    fn from_byte(byte: u8) -> Self {
+        match byte {
+            0x00 => Platform::X86,
+            0x01 => Platform::PowerPc,
+            0x02 => Platform::Mac,
+            0xEF => Platform::Uefi,
+            other => Platform::Unknown(other),
+        }
+    }
+}
+
+/// Emulation mode from a boot entry's media type byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmulationMode {
+    NoEmulation,
+    Floppy1_2Mb,
+    Floppy1_44Mb,
+    Floppy2_88Mb,
+    HardDisk,
+    Unknown(u8),
+}
+
+impl EmulationMode {
+    fn from_byte(byte: u8) -> Self {
+        match byte & 0x0F {
+            0x00 => EmulationMode::NoEmulation,
+            0x01 => EmulationMode::Floppy1_2Mb,
+            0x02 => EmulationMode::Floppy1_44Mb,
+            0x03 => EmulationMode::Floppy2_88Mb,
+            0x04 => EmulationMode::HardDisk,
+            other => EmulationMode::Unknown(other),
+        }
+    }
+}
+
+/// One bootable entry from an ISO's El Torito boot catalog: the
+/// default/initial entry (platform from the validation entry) or a
+/// section entry (any platform,
+/// `0xEF` for UEFI).
+#[derive(Debug, Clone)]
+pub struct BootEntry {
+    pub platform: Platform,
+    pub emulation: EmulationMode,
+    pub image_lba: u32,
+    pub bootable: bool,
+}
+
+/// Parses the El Torito boot catalog referenced by the Boot Record Volume
+/// Descriptor at LBA 17, returning every default/initial and section entry
+/// found. Returns an empty list (not an error) if the ISO has no El Torito
+/// boot record at all, since plain-UEFI ISOs often skip El Torito entirely.
+pub fn parse_boot_catalog(iso_path: &Path) -> Result<Vec<BootEntry>> {
+    let mut file = File::open(iso_path)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to open ISO: {}", e)))?;
+
+    let mut boot_record = vec![0u8; SECTOR_SIZE as usize];
+    file.seek(SeekFrom::Start(BOOT_RECORD_LBA * SECTOR_SIZE))
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek boot record: {}", e)))?;
+    file.read_exact(&mut boot_record)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to read boot record: {}", e)))?;
+
+    // Boot Record Volume Descriptor: type 0, "CD001", then the
+    // "EL TORITO SPECIFICATION" boot system identifier at offset 7.
+    if boot_record[0] != 0 || &boot_record[1..6] != b"CD001" || !boot_record[7..39].starts_with(b"EL TORITO SPECIFICATION") {
+        return Ok(Vec::new());
+    }
+
+    let catalog_lba = u32::from_le_bytes([
+        boot_record[CATALOG_POINTER_OFFSET],
+        boot_record[CATALOG_POINTER_OFFSET + 1],
+        boot_record[CATALOG_POINTER_OFFSET + 2],
+        boot_record[CATALOG_POINTER_OFFSET + 3],
+    ]);
+
+    let mut catalog = vec![0u8; SECTOR_SIZE as usize];
+    file.seek(SeekFrom::Start(catalog_lba as u64 * SECTOR_SIZE))
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek boot catalog: {}", e)))?;
+    file.read_exact(&mut catalog)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to read boot catalog: {}", e)))?;
+
+    // Validation entry: 32 bytes, header ID 0x01; every 16-bit little-endian
+    // word in the entry must sum to zero (mod 0x10000).
+    let validation = &catalog[0..32];
+    if validation[0] != 0x01 {
+        return Ok(Vec::new());
+    }
+    let checksum: u16 = validation.chunks_exact(2)
+        .fold(0u16, |acc, word| acc.wrapping_add(u16::from_le_bytes([word[0], word[1]])));
+    if checksum != 0 {
+        return Err(UsbBootHutError::Iso("El Torito validation entry checksum mismatch".to_string()));
+    }
+
+    let mut entries = Vec::new();
+    let mut offset = 32usize;
+
+    // The default/initial entry immediately follows the validation entry
+    // and is always for the platform named in that validation entry.
+    entries.push(parse_entry(&catalog[offset..offset + 32], Platform::from_byte(validation[1])));
+    offset += 32;
+
+    // Remaining entries alternate a section header (announces the
+    // platform and entry count for the section that follows) and that
+    // many section entries, until a final section header (id 0x91).
+    while offset + 32 <= catalog.len() {
+        let header = &catalog[offset..offset + 32];
+        let header_id = header[0];
+        if header_id != 0x90 && header_id != 0x91 {
+            break;
+        }
+
+        let platform = Platform::from_byte(header[1]);
+        let entry_count = u16::from_le_bytes([header[2], header[3]]);
+        offset += 32;
+
+        for _ in 0..entry_count {
+            if offset + 32 > catalog.len() {
+                break;
+            }
+            entries.push(parse_entry(&catalog[offset..offset + 32], platform));
+            offset += 32;
+        }
+
+        if header_id == 0x91 {
+            break;
+        }
+    }
+
+    Ok(entries)
+}
+
+fn parse_entry(entry: &[u8], platform: Platform) -> BootEntry {
+    BootEntry {
+        platform,
+        emulation: EmulationMode::from_byte(entry[1]),
+        image_lba: u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]),
+        bootable: entry[0] == 0x88,
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{Result, UsbBootHutError};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+const SECTOR_SIZE: u64 = 2048;
+const ROOT_DIR_RECORD_OFFSET: usize = 156;
+const ROOT_DIR_RECORD_LEN: usize = 34;
+
+/// One parsed ISO 9660 directory record: enough to recurse into it (if a
+/// directory) or report it (if a file).
+struct DirRecord {
+    extent_lba: u32,
+    data_length: u32,
+    is_directory: bool,
+    name: String,
+}
+
+/// Reads an ISO 9660 image's directory tree directly, so
+/// `IsoValidator::check_bootable` can confirm UEFI bootability by checking
+/// for real `EFI/BOOT/BOOT*.EFI` paths instead of guessing.
+///
+/// Prefers a Joliet Supplementary Volume Descriptor's root directory (proper
+/// long/Unicode names) over the Primary Volume Descriptor's; when reading
+/// from the PVD, Rock Ridge `NM` System Use entries are used in place of the
+/// truncated 8.3-style name wherever present.
+pub struct Iso9660Reader {
+    file: File,
+    root_extent: u32,
+    root_length: u32,
+    joliet: bool,
+}
+
+impl Iso9660Reader {
+    pub fn open(iso_path: &Path) -> Result<Self> {
+        let mut file = File::open(iso_path)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to open ISO: {}", e)))?;
+
+        if let Some((root_extent, root_length)) = Self::find_joliet_root(&mut file)? {
+            return Ok(Self { file, root_extent, root_length, joliet: true });
+        }
+
+        let (root_extent, root_length) = Self::read_root_dir_record(&mut file, 16)?;
+        Ok(Self { file, root_extent, root_length, joliet: false })
+    }
+
+    /// Lists every regular file in the image as an absolute path (e.g.
+    /// `/EFI/BOOT/BOOTX64.EFI`), recursing breadth-first through
+    /// subdirectories.
+    pub fn list_files(&mut self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut dirs = VecDeque::new();
+        dirs.push_back((PathBuf::from("/"), self.root_extent, self.root_length));
+
+        while let Some((prefix, extent, length)) = dirs.pop_front() {
+            for entry in self.read_directory(extent, length)? {
+                let path = prefix.join(&entry.name);
+                if entry.is_directory {
+                    dirs.push_back((path, entry.extent_lba, entry.data_length));
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// True if `path` (case-insensitive, `/`-separated) names a regular file
+    /// somewhere in the image. Directories are not matched, since only
+    /// `list_files` entries are compared.
+    pub fn has_path(&mut self, path: &str) -> Result<bool> {
+        let target = path.trim_start_matches('/').to_lowercase();
+        Ok(self.list_files()?.iter().any(|f| {
+            f.to_string_lossy().trim_start_matches('/').to_lowercase() == target
+        }))
+    }
+
+    /// Reads the full contents of the file at `path` (e.g.
+    /// `EFI/BOOT/BOOTX64.EFI`), walking each path component's directory in
+    /// turn rather than requiring a prior `list_files` call.
+    pub fn read_file(&mut self, path: &str) -> Result<Vec<u8>> {
+        let components: Vec<&str> = path.trim_start_matches('/').split('/').filter(|c| !c.is_empty()).collect();
+        if components.is_empty() {
+            return Err(UsbBootHutError::Iso(format!("Not a file path: {}", path)));
+        }
+
+        let mut extent = self.root_extent;
+        let mut length = self.root_length;
+
+        for (i, component) in components.iter().enumerate() {
+            let entries = self.read_directory(extent, length)?;
+            let entry = entries.iter()
+                .find(|e| e.name.eq_ignore_ascii_case(component))
+                .ok_or_else(|| UsbBootHutError::Iso(format!("Path not found in ISO: {}", path)))?;
+
+            if i == components.len() - 1 {
+                if entry.is_directory {
+                    return Err(UsbBootHutError::Iso(format!("{} is a directory", path)));
+                }
+
+                let mut buffer = vec![0u8; entry.data_length as usize];
+                self.file.seek(SeekFrom::Start(entry.extent_lba as u64 * SECTOR_SIZE))
+                    .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek file: {}", e)))?;
+                self.file.read_exact(&mut buffer)
+                    .map_err(|e| UsbBootHutError::Iso(format!("Failed to read file: {}", e)))?;
+                return Ok(buffer);
+            }
+
+            if !entry.is_directory {
+                return Err(UsbBootHutError::Iso(format!("Path not found in ISO: {}", path)));
+            }
+            extent = entry.extent_lba;
+            length = entry.data_length;
+        }
+
+        Err(UsbBootHutError::Iso(format!("Path not found in ISO: {}", path)))
+    }
+
+    /// Walks every Volume Descriptor starting at LBA 16 until the Volume
+    /// Descriptor Set Terminator (type 255), looking for a Supplementary
+    /// Volume Descriptor (type 2) whose escape sequence marks it as Joliet
+    /// (UCS-2 Level 1/2/3, `%/@`, `%/C`, `%/E` respectively).
+    fn find_joliet_root(file: &mut File) -> Result<Option<(u32, u32)>> {
+        let mut lba = 16u64;
+        loop {
+            let mut sector = vec![0u8; SECTOR_SIZE as usize];
+            file.seek(SeekFrom::Start(lba * SECTOR_SIZE))
+                .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek volume descriptor: {}", e)))?;
+            file.read_exact(&mut sector)
+                .map_err(|e| UsbBootHutError::Iso(format!("Failed to read volume descriptor: {}", e)))?;
+
+            if &sector[1..6] != b"CD001" || sector[0] == 255 {
+                break;
+            }
+
+            if sector[0] == 2 {
+                let escape = &sector[88..120];
+                let is_joliet = escape.starts_with(b"%/@") || escape.starts_with(b"%/C") || escape.starts_with(b"%/E");
+                if is_joliet {
+                    let record = &sector[ROOT_DIR_RECORD_OFFSET..ROOT_DIR_RECORD_OFFSET + ROOT_DIR_RECORD_LEN];
+                    return Ok(Some((read_both_endian_u32(&record[2..10]), read_both_endian_u32(&record[10..18]))));
+                }
+            }
+
+            lba += 1;
+        }
+        Ok(None)
+    }
+
+    fn read_root_dir_record(file: &mut File, lba: u64) -> Result<(u32, u32)> {
+        let mut sector = vec![0u8; SECTOR_SIZE as usize];
+        file.seek(SeekFrom::Start(lba * SECTOR_SIZE))
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek PVD: {}", e)))?;
+        file.read_exact(&mut sector)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read PVD: {}", e)))?;
+
+        if &sector[1..6] != b"CD001" {
+            return Err(UsbBootHutError::Iso("Invalid ISO 9660 format".to_string()));
+        }
+
+        let record = &sector[ROOT_DIR_RECORD_OFFSET..ROOT_DIR_RECORD_OFFSET + ROOT_DIR_RECORD_LEN];
+        Ok((read_both_endian_u32(&record[2..10]), read_both_endian_u32(&record[10..18])))
+    }
+
+    fn read_directory(&mut self, extent_lba: u32, data_length: u32) -> Result<Vec<DirRecord>> {
+        let mut buffer = vec![0u8; data_length as usize];
+        self.file.seek(SeekFrom::Start(extent_lba as u64 * SECTOR_SIZE))
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek directory extent: {}", e)))?;
+        self.file.read_exact(&mut buffer)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read directory extent: {}", e)))?;
+
+        let mut entries = Vec::new();
+        let mut offset = 0usize;
+
+        while offset < buffer.len() {
+            let record_len = buffer[offset] as usize;
+
+            if record_len == 0 {
+                // A zero length byte means "no more records in this
+                // sector"; directory records never straddle a sector
+                // boundary, so skip ahead to the next one.
+                let sector_remainder = SECTOR_SIZE as usize - (offset % SECTOR_SIZE as usize);
+                offset += sector_remainder;
+                continue;
+            }
+            if offset + record_len > buffer.len() {
+                break;
+            }
+
+            let record = &buffer[offset..offset + record_len];
+
+            // A directory record has 33 fixed bytes before the (variable
+            // length) name field, and the name itself must still fit inside
+            // the record length just read; a corrupt or truncated image could
+            // claim otherwise, so bail out rather than index past either.
+            if record_len < 33 {
+                return Err(UsbBootHutError::Iso("Truncated ISO 9660 directory record".to_string()));
+            }
+            let file_flags = record[25];
+            let is_directory = file_flags & 0x02 != 0;
+            let name_length = record[32] as usize;
+            if 33 + name_length > record_len {
+                return Err(UsbBootHutError::Iso("Truncated ISO 9660 directory record name".to_string()));
+            }
+            let name_bytes = &record[33..33 + name_length];
+
+            // Skip the "." and ".." self/parent entries: name length 1,
+            // name byte 0x00 or 0x01.
+            let is_dot_entry = name_length == 1 && (name_bytes[0] == 0x00 || name_bytes[0] == 0x01);
+
+            if !is_dot_entry {
+                let extent_lba = read_both_endian_u32(&record[2..10]);
+                let entry_data_length = read_both_endian_u32(&record[10..18]);
+
+                let name = if self.joliet {
+                    decode_ucs2_be(name_bytes)
+                } else {
+                    // The name field is padded to an even length; the System
+                    // Use Area (Rock Ridge entries) starts right after.
+                    let system_use_start = (33 + name_length + if name_length % 2 == 0 { 1 } else { 0 }).min(record.len());
+                    parse_rock_ridge_name(&record[system_use_start..])
+                        .unwrap_or_else(|| strip_version_suffix(&String::from_utf8_lossy(name_bytes)))
+                };
+
+                entries.push(DirRecord { extent_lba, data_length: entry_data_length, is_directory, name });
+            }
+
+            offset += record_len;
+        }
+
+        Ok(entries)
+    }
+}
+
+/// Lists every file in `iso_path`'s ISO 9660 tree.
+pub fn list_files(iso_path: &Path) -> Result<Vec<PathBuf>> {
+    Iso9660Reader::open(iso_path)?.list_files()
+}
+
+/// True if `path` names a regular file somewhere in `iso_path`'s tree;
+/// directories are not matched.
+pub fn has_path(iso_path: &Path, path: &str) -> Result<bool> {
+    Iso9660Reader::open(iso_path)?.has_path(path)
+}
+
+/// Reads a "both-endian" field (little-endian half followed by a
+/// big-endian half, as ISO 9660 stores LBAs and data lengths) by trusting
+/// the little-endian half.
+fn read_both_endian_u32(field: &[u8]) -> u32 {
+    u32::from_le_bytes([field[0], field[1], field[2], field[3]])
+}
+
+/// Strips the `;1` version suffix ISO 9660 appends to plain (non-Joliet,
+/// non-Rock-Ridge) file names.
+fn strip_version_suffix(name: &str) -> String {
+    name.split(';').next().unwrap_or(name).to_string()
+}
+
+/// Decodes a Joliet name: UCS-2, big-endian, BMP-only (the surrogate pairs
+/// needed for names outside the BMP aren't expected in practice, and are
+/// dropped rather than mis-decoded).
+fn decode_ucs2_be(bytes: &[u8]) -> String {
+    bytes.chunks_exact(2)
+        .filter_map(|pair| char::from_u32(u16::from_be_bytes([pair[0], pair[1]]) as u32))
+        .collect()
+}
+
+/// Scans a directory record's System Use Area for Rock Ridge `NM` (alternate
+/// name) System Use entries, concatenating continuation entries (`NM` flag
+/// bit 0 set) into the full long name.
+fn parse_rock_ridge_name(system_use: &[u8]) -> Option<String> {
+    let mut offset = 0usize;
+    let mut name = String::new();
+    let mut found = false;
+
+    while offset + 4 <= system_use.len() {
+        let signature = &system_use[offset..offset + 2];
+        let len = system_use[offset + 2] as usize;
+        if len < 4 || offset + len > system_use.len() {
+            break;
+        }
+
+        if signature == b"NM" {
+            let flags = system_use[offset + 4];
+            let content = &system_use[offset + 5..offset + len];
+            name.push_str(&String::from_utf8_lossy(content));
+            found = true;
+            if flags & 0x01 == 0 {
+                break;
+            }
+        } else if signature == b"ST" {
+            break;
+        }
+
+        offset += len;
+    }
+
+    found.then_some(name)
+}
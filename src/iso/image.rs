@@ -0,0 +1,352 @@
+use crate::{Result, UsbBootHutError};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use flate2::read::GzDecoder;
+#[cfg(feature = "bzip2")]
+use bzip2::read::BzDecoder;
+#[cfg(feature = "xz")]
+use xz2::read::XzDecoder;
+
+const XZ_MAGIC: [u8; 6] = [0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+/// Sniffs the magic bytes of a source image and returns a reader that
+/// transparently decompresses it, so a compressed archive can be streamed
+/// straight to the target device without a separate extract step.
+pub fn open_image(path: &Path) -> Result<Box<dyn Read + Send>> {
+    let mut file = File::open(path)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to open image: {}", e)))?;
+
+    let magic = read_magic(&mut file)?;
+
+    if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        #[cfg(feature = "zstd")]
+        {
+            let decoder = zstd::Decoder::new(file)
+                .map_err(|e| UsbBootHutError::Iso(format!("Failed to open zstd stream: {}", e)))?;
+            return Ok(Box::new(decoder));
+        }
+        #[cfg(not(feature = "zstd"))]
+        return Err(UsbBootHutError::Iso(
+            "zstd support not compiled in (enable the \"zstd\" feature)".to_string()
+        ));
+    }
+
+    if magic.starts_with(b"BZh") {
+        #[cfg(feature = "bzip2")]
+        return Ok(Box::new(BzDecoder::new(file)));
+        #[cfg(not(feature = "bzip2"))]
+        return Err(UsbBootHutError::Iso(
+            "bzip2 support not compiled in (enable the \"bzip2\" feature)".to_string()
+        ));
+    }
+
+    if magic.starts_with(&[0x1f, 0x8b]) {
+        return Ok(Box::new(GzDecoder::new(file)));
+    }
+
+    if magic.starts_with(&XZ_MAGIC) {
+        #[cfg(feature = "xz")]
+        return Ok(Box::new(XzDecoder::new(file)));
+        #[cfg(not(feature = "xz"))]
+        return Err(UsbBootHutError::Iso(
+            "xz/lzma support not compiled in (enable the \"xz\" feature)".to_string()
+        ));
+    }
+
+    if magic.starts_with(b"CISO") {
+        return Ok(Box::new(CisoReader::new(file)?));
+    }
+
+    if magic.starts_with(b"WBFS") {
+        return Ok(Box::new(WbfsReader::new(file)?));
+    }
+
+    if magic.starts_with(crate::iso::container::MAGIC) {
+        return Ok(Box::new(crate::iso::container::ContainerReader::new(file)?));
+    }
+
+    Ok(Box::new(file))
+}
+
+/// Returns the decompressed size of the image at `path` when the format
+/// encodes it up front — a raw/uncompressed image, or a CISO/WBFS header's
+/// `total_bytes` field — so callers can size a progress bar before
+/// streaming starts. `None` for gzip/bzip2/xz/zstd streams, whose true
+/// size isn't known until the whole stream has been decompressed; callers
+/// should fall back to an indeterminate progress indicator for those.
+pub fn known_image_size(path: &Path) -> Result<Option<u64>> {
+    let mut file = File::open(path)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to open image: {}", e)))?;
+
+    let magic = read_magic(&mut file)?;
+
+    if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd])
+        || magic.starts_with(b"BZh")
+        || magic.starts_with(&[0x1f, 0x8b])
+        || magic.starts_with(&XZ_MAGIC)
+    {
+        return Ok(None);
+    }
+
+    if magic.starts_with(b"CISO") {
+        let mut total_bytes = [0u8; 8];
+        file.seek(SeekFrom::Start(8))
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek CISO header: {}", e)))?;
+        file.read_exact(&mut total_bytes)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read CISO header: {}", e)))?;
+        return Ok(Some(u64::from_le_bytes(total_bytes)));
+    }
+
+    if magic.starts_with(b"WBFS") {
+        let mut header = [0u8; 10];
+        file.read_exact(&mut header)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read WBFS header: {}", e)))?;
+        let n_hd_sectors = u32::from_be_bytes(header[4..8].try_into().unwrap());
+        let hd_sector_size = 1u64 << header[8];
+        return Ok(Some(n_hd_sectors as u64 * hd_sector_size));
+    }
+
+    if magic.starts_with(crate::iso::container::MAGIC) {
+        return Ok(Some(crate::iso::container::uncompressed_len(&mut file)?));
+    }
+
+    Ok(Some(file.metadata()
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to stat image: {}", e)))?
+        .len()))
+}
+
+/// True if `path` is a gzip/bzip2/xz/zstd stream, or one of our own
+/// archived containers, that needs decompressing before its contents can be
+/// validated or sized. Deliberately excludes CISO/WBFS: those are sparse
+/// disc-image containers relevant to `burn`, not compressed ISO sources for
+/// the catalog.
+pub fn is_compressed(path: &Path) -> Result<bool> {
+    let mut file = File::open(path)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to open image: {}", e)))?;
+
+    let magic = read_magic(&mut file)?;
+
+    Ok(magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd])
+        || magic.starts_with(b"BZh")
+        || magic.starts_with(&[0x1f, 0x8b])
+        || magic.starts_with(&XZ_MAGIC)
+        || magic.starts_with(crate::iso::container::MAGIC))
+}
+
+/// A path to a plain, seekable ISO 9660 stream: either the original file
+/// (already uncompressed) or a decompressed temporary copy, kept alive for
+/// as long as this value lives.
+pub enum MaterializedImage {
+    Original(PathBuf),
+    Decompressed(tempfile::NamedTempFile),
+}
+
+impl MaterializedImage {
+    pub fn path(&self) -> &Path {
+        match self {
+            MaterializedImage::Original(path) => path,
+            MaterializedImage::Decompressed(file) => file.path(),
+        }
+    }
+}
+
+/// Decompresses `path` into a temporary file and returns it, or returns
+/// `path` unchanged if it's already a plain ISO 9660 image. Lets seek-based
+/// readers (`IsoValidator::validate_iso`, `Iso9660Reader`) work transparently
+/// on zstd/bzip2/xz-compressed sources, since decompressing readers only
+/// support forward reads.
+pub fn materialize(path: &Path) -> Result<MaterializedImage> {
+    if !is_compressed(path)? {
+        return Ok(MaterializedImage::Original(path.to_path_buf()));
+    }
+
+    let mut reader = open_image(path)?;
+    let mut temp = tempfile::NamedTempFile::new()
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to create temp file: {}", e)))?;
+    std::io::copy(&mut reader, &mut temp)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to decompress image: {}", e)))?;
+
+    Ok(MaterializedImage::Decompressed(temp))
+}
+
+/// Reads the leading magic bytes used to identify the image's container
+/// format, rewinding the file afterwards so the caller can reopen decoders
+/// from the start.
+fn read_magic(file: &mut File) -> Result<[u8; 8]> {
+    let mut magic = [0u8; 8];
+    let read = file.read(&mut magic)
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to read image header: {}", e)))?;
+    file.seek(SeekFrom::Start(0))
+        .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek image: {}", e)))?;
+
+    if read < magic.len() {
+        magic[read..].fill(0);
+    }
+
+    Ok(magic)
+}
+
+/// CISO is a block-sparse container: a fixed header gives the block size,
+/// followed by a table of per-block offsets. A sentinel offset marks a
+/// block as absent, in which case we emit zeros instead of reading it.
+struct CisoReader {
+    file: File,
+    block_size: u32,
+    total_bytes: u64,
+    block_table: Vec<u32>, // byte offset / block_size into the file, or u32::MAX if absent
+    position: u64,
+}
+
+const CISO_ABSENT_BLOCK: u32 = u32::MAX;
+
+impl CisoReader {
+    fn new(mut file: File) -> Result<Self> {
+        let mut header = [0u8; 0x18];
+        file.read_exact(&mut header)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read CISO header: {}", e)))?;
+
+        if &header[0..4] != b"CISO" {
+            return Err(UsbBootHutError::Iso("Not a CISO image".to_string()));
+        }
+
+        let header_size = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        let total_bytes = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        let block_size = u32::from_le_bytes(header[16..20].try_into().unwrap());
+
+        if block_size == 0 {
+            return Err(UsbBootHutError::Iso("Invalid CISO block size".to_string()));
+        }
+
+        let total_blocks = total_bytes.div_ceil(block_size as u64) as usize;
+
+        // Skip any header padding before the block index table
+        if header_size as usize > header.len() {
+            file.seek(SeekFrom::Start(header_size as u64))
+                .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek past CISO header: {}", e)))?;
+        }
+
+        let mut table = vec![0u8; total_blocks * 4];
+        file.read_exact(&mut table)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read CISO block table: {}", e)))?;
+
+        let block_table = table
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+
+        Ok(Self { file, block_size, total_bytes, block_table, position: 0 })
+    }
+}
+
+impl Read for CisoReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position >= self.total_bytes {
+            return Ok(0);
+        }
+
+        let block_size = self.block_size as u64;
+        let block_index = (self.position / block_size) as usize;
+        let offset_in_block = self.position % block_size;
+
+        let remaining_in_block = block_size - offset_in_block;
+        let remaining_in_image = self.total_bytes - self.position;
+        let to_read = buf.len().min(remaining_in_block as usize).min(remaining_in_image as usize);
+
+        match self.block_table.get(block_index).copied() {
+            Some(CISO_ABSENT_BLOCK) | None => {
+                buf[..to_read].fill(0);
+            }
+            Some(block_offset) => {
+                let file_offset = block_offset as u64 * block_size + offset_in_block;
+                self.file.seek(SeekFrom::Start(file_offset))?;
+                self.file.read_exact(&mut buf[..to_read])?;
+            }
+        }
+
+        self.position += to_read as u64;
+        Ok(to_read)
+    }
+}
+
+/// WBFS (Wii Backup File System) stores a logical disc image as a sparse
+/// table of physical "WBFS sectors". We reconstruct the logical stream from
+/// the header's sector size shift and the disc's wlba block map, emitting
+/// zeros for any logical sector that was never allocated.
+struct WbfsReader {
+    file: File,
+    wbfs_sector_size: u32,
+    total_bytes: u64,
+    wlba_table: Vec<u16>, // physical WBFS sector index per logical sector, 0 = unmapped
+    position: u64,
+}
+
+const WBFS_DISC_HEADER_OFFSET: u64 = 0x200;
+const WBFS_WLBA_TABLE_OFFSET: u64 = 0x2E0;
+
+impl WbfsReader {
+    fn new(mut file: File) -> Result<Self> {
+        let mut header = [0u8; 12];
+        file.read_exact(&mut header)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read WBFS header: {}", e)))?;
+
+        if &header[0..4] != b"WBFS" {
+            return Err(UsbBootHutError::Iso("Not a WBFS image".to_string()));
+        }
+
+        let n_hd_sectors = u32::from_be_bytes(header[4..8].try_into().unwrap());
+        let hd_sec_sz_shift = header[8];
+        let wbfs_sec_sz_shift = header[9];
+
+        let hd_sector_size = 1u64 << hd_sec_sz_shift;
+        let wbfs_sector_size = 1u32 << wbfs_sec_sz_shift;
+        let total_bytes = n_hd_sectors as u64 * hd_sector_size;
+
+        // The wlba table for the first (only) disc maps logical 2MB sectors
+        // to physical WBFS sector numbers; 0 means the sector was never written.
+        file.seek(SeekFrom::Start(WBFS_DISC_HEADER_OFFSET + (WBFS_WLBA_TABLE_OFFSET - WBFS_DISC_HEADER_OFFSET)))
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek WBFS wlba table: {}", e)))?;
+
+        let num_logical_sectors = total_bytes.div_ceil(wbfs_sector_size as u64) as usize;
+        let mut raw = vec![0u8; num_logical_sectors * 2];
+        file.read_exact(&mut raw)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read WBFS wlba table: {}", e)))?;
+
+        let wlba_table = raw
+            .chunks_exact(2)
+            .map(|b| u16::from_be_bytes(b.try_into().unwrap()))
+            .collect();
+
+        Ok(Self { file, wbfs_sector_size, total_bytes, wlba_table, position: 0 })
+    }
+}
+
+impl Read for WbfsReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.position >= self.total_bytes {
+            return Ok(0);
+        }
+
+        let sector_size = self.wbfs_sector_size as u64;
+        let sector_index = (self.position / sector_size) as usize;
+        let offset_in_sector = self.position % sector_size;
+
+        let remaining_in_sector = sector_size - offset_in_sector;
+        let remaining_in_image = self.total_bytes - self.position;
+        let to_read = buf.len().min(remaining_in_sector as usize).min(remaining_in_image as usize);
+
+        match self.wlba_table.get(sector_index).copied() {
+            Some(0) | None => {
+                buf[..to_read].fill(0);
+            }
+            Some(physical_sector) => {
+                let file_offset = physical_sector as u64 * sector_size + offset_in_sector;
+                self.file.seek(SeekFrom::Start(file_offset))?;
+                self.file.read_exact(&mut buf[..to_read])?;
+            }
+        }
+
+        self.position += to_read as u64;
+        Ok(to_read)
+    }
+}
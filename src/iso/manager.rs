@@ -1,7 +1,12 @@
-use crate::{Result, UsbBootHutError};
-use crate::iso::{IsoValidator, IsoInfo, IsoMetadata, MetadataStore, IsoCategory};
+use crate::{Result, UsbBootHutError, IoContext};
+use crate::iso::{IsoValidator, IsoInfo, IsoMetadata, MetadataStore, IsoCategory, VerificationStatus};
+use crate::iso::signing::MetadataTrust;
+use crate::iso::dedupe::{self, DuplicateGroup};
+use crate::iso::verify::{self, KnownGoodDb};
+use crate::iso::snapshot::{Snapshot, ReconcileStats, SNAPSHOT_VERSION};
 use crate::bootloader::{GrubConfigManager, BootParams};
-use crate::utils::with_progress;
+use crate::cleanup::{CleanupEngine, CleanupStats};
+use crate::utils::{atomic_write, with_progress, ProgressManager};
 use std::path::{Path, PathBuf};
 use std::fs::{self, File};
 use std::io::{Read, Write};
@@ -11,95 +16,223 @@ pub struct IsoManager {
     data_mount: PathBuf,
     boot_mount: PathBuf,
     metadata_store: MetadataStore,
+    trust: Option<MetadataTrust>,
 }
 
 impl IsoManager {
     pub fn new(data_mount: &Path, boot_mount: &Path) -> Result<Self> {
-        let metadata_store = MetadataStore::new(data_mount)?;
-        
+        Self::with_trust(data_mount, boot_mount, None)
+    }
+
+    /// Same as `new`, but the ISO catalog is loaded/saved through `trust`
+    /// (see `MetadataStore::open`): a signed catalog is verified on load
+    /// and (if `trust` carries a signing key) re-signed after every
+    /// mutation.
+    pub fn with_trust(data_mount: &Path, boot_mount: &Path, trust: Option<MetadataTrust>) -> Result<Self> {
+        let metadata_store = MetadataStore::open(data_mount, trust.clone())?;
+
         // Ensure ISO directory exists
         let iso_dir = data_mount.join("isos");
-        fs::create_dir_all(&iso_dir)
-            .map_err(|e| UsbBootHutError::Iso(format!("Failed to create ISO dir: {}", e)))?;
-            
+        fs::create_dir_all(&iso_dir).io_context("create ISO directory", &iso_dir)?;
+
         Ok(Self {
             data_mount: data_mount.to_path_buf(),
             boot_mount: boot_mount.to_path_buf(),
             metadata_store,
+            trust,
         })
     }
     
-    pub fn add_iso(&mut self, iso_path: &Path, verify_checksum: Option<&str>) -> Result<()> {
-        println!("🔍 Validating ISO...");
-        
-        // Validate ISO
-        let iso_info = IsoValidator::validate_iso(iso_path)?;
-        if !iso_info.bootable {
-            println!("⚠️  Warning: ISO may not be bootable");
-        }
-        
-        // Calculate checksum
-        println!("🔐 Calculating checksum...");
-        let checksum = with_progress(iso_info.size, "Calculating SHA256", |pb| {
-            Self::calculate_checksum_with_progress(iso_path, pb)
-        })?;
-        
+    /// Extensions this module knows how to strip off a compressed source so
+    /// the stored `.iso` gets a sensible name (e.g. `ubuntu.iso.xz` -> `ubuntu.iso`).
+    const COMPRESSED_EXTENSIONS: [&'static str; 4] = [".gz", ".xz", ".bz2", ".zst"];
+
+    pub fn add_iso(
+        &mut self,
+        iso_path: &Path,
+        verify_checksum: Option<&str>,
+        category: Option<IsoCategory>,
+        tags: Option<Vec<String>>,
+        channel: Option<String>,
+    ) -> Result<()> {
+        let filename = Self::dest_filename(iso_path)?;
+        let dest_path = self.data_mount.join("isos").join(&filename);
+        let staging_path = self.data_mount.join("isos").join(format!("{}.part", filename));
+
+        let (iso_info, checksum) = if IsoValidator::is_compressed(iso_path)? {
+            // Decompressed size isn't known up front for a streaming
+            // gzip/xz/bzip2/zstd source, so the copy stage doubles as
+            // validation: decompress straight to the staging path, hash the
+            // decompressed bytes as they're written (matching how published
+            // SHA256SUMS are computed), then validate the now-materialized ISO.
+            println!("📦 Decompressing and copying ISO to USB drive...");
+            let copied_checksum = Self::decompress_copy_with_progress(iso_path, &staging_path)?;
+
+            println!("🔍 Validating decompressed ISO...");
+            let iso_info = IsoValidator::validate_iso(&staging_path)
+                .inspect_err(|_| { let _ = fs::remove_file(&staging_path); })?;
+            if !iso_info.bootable {
+                println!("⚠️  Warning: ISO may not be bootable");
+            }
+
+            (iso_info, copied_checksum)
+        } else {
+            println!("🔍 Validating ISO...");
+            let iso_info = IsoValidator::validate_iso(iso_path)?;
+            if !iso_info.bootable {
+                println!("⚠️  Warning: ISO may not be bootable");
+            }
+
+            // Check available space up front; for the compressed path above
+            // we don't know the decompressed size until the copy finishes.
+            let available_space = self.get_available_space()?;
+            if iso_info.size > available_space {
+                return Err(UsbBootHutError::Iso(
+                    format!("Not enough space. Need {} bytes, have {} bytes",
+                        iso_info.size, available_space)
+                ));
+            }
+
+            println!("🔐 Calculating checksum...");
+            let checksum = with_progress(iso_info.size, "Calculating SHA256", |pb| {
+                Self::calculate_checksum_with_progress(iso_path, pb)
+            })?;
+
+            // Copy ISO to a staging name first and only rename it into place
+            // once the copy is complete, so a crash mid-copy can never leave
+            // metadata or a GRUB entry pointing at a half-written ISO.
+            println!("📦 Copying ISO to USB drive...");
+            with_progress(iso_info.size, "Copying ISO", |pb| {
+                Self::copy_with_progress(iso_path, &staging_path, pb)
+            })?;
+
+            (iso_info, checksum)
+        };
+
         // Verify checksum if provided
         if let Some(expected) = verify_checksum {
             if !checksum.eq_ignore_ascii_case(expected) {
+                let _ = fs::remove_file(&staging_path);
                 return Err(UsbBootHutError::Iso(
                     "Checksum verification failed".to_string()
                 ));
             }
             println!("✅ Checksum verified");
         }
-        
-        // Generate destination path
-        let filename = iso_path.file_name()
-            .ok_or_else(|| UsbBootHutError::Iso("Invalid ISO filename".to_string()))?
-            .to_string_lossy()
-            .to_string();
-            
-        let dest_path = self.data_mount.join("isos").join(&filename);
-        
-        // Check available space
-        let available_space = self.get_available_space()?;
-        if iso_info.size > available_space {
-            return Err(UsbBootHutError::Iso(
-                format!("Not enough space. Need {} bytes, have {} bytes", 
-                    iso_info.size, available_space)
-            ));
+
+        // Windows install media is the one case that actually needs to cross
+        // the 4GiB FAT32 file-size limit, since install.wim is often larger.
+        const FAT32_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024 * 1024 - 1;
+        if iso_info.size > FAT32_MAX_FILE_SIZE && self.data_partition_is_fat32() {
+            let _ = fs::remove_file(&staging_path);
+            return Err(UsbBootHutError::Iso(format!(
+                "ISO is {} bytes, over FAT32's 4GiB file size limit, and the data partition is FAT32. \
+                 Reformat the data partition as exFAT/ext4, or pre-split install.wim with \
+                 `wimlib-imagex split` before adding it.",
+                iso_info.size
+            )));
         }
-        
-        // Copy ISO with progress
-        println!("📦 Copying ISO to USB drive...");
-        with_progress(iso_info.size, "Copying ISO", |pb| {
-            Self::copy_with_progress(iso_path, &dest_path, pb)
-        })?;
-        
+
+        fs::rename(&staging_path, &dest_path).io_context("finalize copied ISO", &dest_path)?;
+
         // Create metadata
-        let metadata = IsoMetadata::new(
+        let mut metadata = IsoMetadata::new(
             filename.clone(),
             iso_info.iso_type.clone(),
             iso_info.size,
             checksum,
         );
-        
+        if let Some(category) = category {
+            metadata.category = category;
+        }
+        if let Some(tags) = tags {
+            metadata.tags = tags;
+        }
+        metadata.channel = channel;
+        let display_name = metadata.display_name.clone();
+
         // Generate boot parameters
         let boot_params = self.generate_boot_params(&iso_info);
-        
-        // Update GRUB config
+
+        // Update GRUB config. If this fails, or the metadata save below does,
+        // roll back the staged copy rather than leave an ISO file on disk
+        // that nothing references.
         let grub_mgr = GrubConfigManager::new(&self.boot_mount);
         let iso_rel_path = format!("/isos/{}", filename);
-        grub_mgr.add_iso_entry(&metadata.display_name, &iso_rel_path, &boot_params)?;
-        
+        if let Err(e) = grub_mgr.add_iso_entry(&display_name, &iso_rel_path, &boot_params) {
+            let _ = fs::remove_file(&dest_path);
+            return Err(e);
+        }
+
         // Save metadata
-        self.metadata_store.add_iso(metadata)?;
-        
+        if let Err(e) = self.metadata_store.add_iso(metadata) {
+            let _ = grub_mgr.remove_iso_entry(&display_name);
+            let _ = fs::remove_file(&dest_path);
+            return Err(e);
+        }
+
         println!("✅ ISO added successfully: {}", filename);
         Ok(())
     }
-    
+
+    /// Same destination/rollback shape as `add_iso`, but the source is an
+    /// HTTP(S) URL: the body is streamed straight to a `.part` staging file
+    /// while `Self::download_with_resume` hashes it incrementally, so there's
+    /// no second read pass once the transfer finishes. Resumes automatically
+    /// if a `.part` from a previous attempt is already on disk.
+    pub fn add_iso_from_url(
+        &mut self,
+        url: &str,
+        verify_checksum: Option<&str>,
+        category: Option<IsoCategory>,
+        tags: Option<Vec<String>>,
+        channel: Option<String>,
+    ) -> Result<()> {
+        let filename = Self::url_filename(url)?;
+        let dest_path = self.data_mount.join("isos").join(&filename);
+        let staging_path = self.data_mount.join("isos").join(format!("{}.part", filename));
+
+        println!("🌐 Downloading ISO from {}...", url);
+        let checksum = Self::download_with_resume(url, &staging_path, verify_checksum)?;
+
+        println!("🔍 Validating downloaded ISO...");
+        let iso_info = IsoValidator::validate_iso(&staging_path)
+            .inspect_err(|_| { let _ = fs::remove_file(&staging_path); })?;
+        if !iso_info.bootable {
+            println!("⚠️  Warning: ISO may not be bootable");
+        }
+
+        fs::rename(&staging_path, &dest_path).io_context("finalize downloaded ISO", &dest_path)?;
+
+        let mut metadata = IsoMetadata::new(filename.clone(), iso_info.iso_type.clone(), iso_info.size, checksum);
+        if let Some(category) = category {
+            metadata.category = category;
+        }
+        if let Some(tags) = tags {
+            metadata.tags = tags;
+        }
+        metadata.channel = channel;
+        let display_name = metadata.display_name.clone();
+
+        let boot_params = self.generate_boot_params(&iso_info);
+
+        let grub_mgr = GrubConfigManager::new(&self.boot_mount);
+        let iso_rel_path = format!("/isos/{}", filename);
+        if let Err(e) = grub_mgr.add_iso_entry(&display_name, &iso_rel_path, &boot_params) {
+            let _ = fs::remove_file(&dest_path);
+            return Err(e);
+        }
+
+        if let Err(e) = self.metadata_store.add_iso(metadata) {
+            let _ = grub_mgr.remove_iso_entry(&display_name);
+            let _ = fs::remove_file(&dest_path);
+            return Err(e);
+        }
+
+        println!("✅ ISO added successfully: {}", filename);
+        Ok(())
+    }
+
     pub fn remove_iso(&mut self, iso_id: &str) -> Result<()> {
         // Get metadata
         let metadata = self.metadata_store.get_iso(iso_id)
@@ -113,8 +246,7 @@ impl IsoManager {
         // Delete ISO file
         let iso_path = self.data_mount.join("isos").join(&metadata.filename);
         if iso_path.exists() {
-            fs::remove_file(&iso_path)
-                .map_err(|e| UsbBootHutError::Iso(format!("Failed to delete ISO: {}", e)))?;
+            fs::remove_file(&iso_path).io_context("delete ISO", &iso_path)?;
         }
         
         // Remove metadata
@@ -123,7 +255,86 @@ impl IsoManager {
         println!("✅ ISO removed: {}", metadata.display_name);
         Ok(())
     }
-    
+
+    /// Replaces the raw ISO with a chunked, compressed container (see
+    /// `iso::container`) to free up space on the data partition. Archived
+    /// ISOs aren't directly bootable, so the GRUB entry is removed along
+    /// with the raw file; `restore_iso` reverses both.
+    pub fn archive_iso(&mut self, iso_id: &str, codec: crate::iso::container::ContainerCodec) -> Result<()> {
+        let metadata = self.metadata_store.get_iso(iso_id)
+            .ok_or_else(|| UsbBootHutError::Iso("ISO not found".to_string()))?
+            .clone();
+
+        if metadata.compression.is_some() {
+            return Err(UsbBootHutError::Iso(format!("'{}' is already archived", metadata.display_name)));
+        }
+
+        let iso_path = self.data_mount.join("isos").join(&metadata.filename);
+        let staging_path = self.data_mount.join("isos").join(format!("{}.archiving", metadata.filename));
+
+        println!("🗜️  Compressing {}...", metadata.display_name);
+        let compression = crate::iso::container::write_container(&iso_path, &staging_path, codec)
+            .inspect_err(|_| { let _ = fs::remove_file(&staging_path); })?;
+
+        let grub_mgr = GrubConfigManager::new(&self.boot_mount);
+        grub_mgr.remove_iso_entry(&metadata.display_name)?;
+
+        fs::remove_file(&iso_path).io_context("remove raw ISO", &iso_path)?;
+        fs::rename(&staging_path, &iso_path).io_context("finalize archived ISO", &iso_path)?;
+
+        let ratio = compression.ratio(metadata.size);
+        let mut updated = metadata.clone();
+        updated.compression = Some(compression);
+        self.metadata_store.update_iso(iso_id, updated)?;
+
+        println!("✅ Archived {} ({:.0}% of original size)", metadata.display_name, ratio * 100.0);
+        Ok(())
+    }
+
+    /// Decompresses an archived ISO back to a plain, bootable file and
+    /// re-adds its GRUB entry. Errors (and leaves the container in place)
+    /// if the decompressed checksum doesn't match what was recorded at
+    /// add-time, rather than swap in a file that might not boot.
+    pub fn restore_iso(&mut self, iso_id: &str) -> Result<()> {
+        let metadata = self.metadata_store.get_iso(iso_id)
+            .ok_or_else(|| UsbBootHutError::Iso("ISO not found".to_string()))?
+            .clone();
+
+        if metadata.compression.is_none() {
+            return Err(UsbBootHutError::Iso(format!("'{}' is not archived", metadata.display_name)));
+        }
+
+        let iso_path = self.data_mount.join("isos").join(&metadata.filename);
+        let staging_path = self.data_mount.join("isos").join(format!("{}.part", metadata.filename));
+
+        println!("📦 Decompressing {}...", metadata.display_name);
+        let checksum = Self::decompress_copy_with_progress(&iso_path, &staging_path)?;
+        if !checksum.eq_ignore_ascii_case(&metadata.checksum) {
+            let _ = fs::remove_file(&staging_path);
+            return Err(UsbBootHutError::Iso(
+                "Decompressed ISO doesn't match the checksum recorded at add-time".to_string()
+            ));
+        }
+
+        let iso_info = IsoValidator::validate_iso(&staging_path)
+            .inspect_err(|_| { let _ = fs::remove_file(&staging_path); })?;
+        let boot_params = self.generate_boot_params(&iso_info);
+
+        fs::remove_file(&iso_path).io_context("remove archived ISO", &iso_path)?;
+        fs::rename(&staging_path, &iso_path).io_context("finalize restored ISO", &iso_path)?;
+
+        let grub_mgr = GrubConfigManager::new(&self.boot_mount);
+        let iso_rel_path = format!("/isos/{}", metadata.filename);
+        grub_mgr.add_iso_entry(&metadata.display_name, &iso_rel_path, &boot_params)?;
+
+        let mut updated = metadata.clone();
+        updated.compression = None;
+        self.metadata_store.update_iso(iso_id, updated)?;
+
+        println!("✅ Restored {}", metadata.display_name);
+        Ok(())
+    }
+
     pub fn list_isos(&self, category: Option<IsoCategory>) -> Vec<&IsoMetadata> {
         if let Some(cat) = category {
             self.metadata_store.list_by_category(cat)
@@ -132,109 +343,611 @@ impl IsoManager {
         }
     }
     
-    pub fn verify_iso(&self, iso_id: &str) -> Result<bool> {
-        let metadata = self.metadata_store.get_iso(iso_id)
-            .ok_or_else(|| UsbBootHutError::Iso("ISO not found".to_string()))?;
-            
-        let iso_path = self.data_mount.join("isos").join(&metadata.filename);
-        
-        println!("🔍 Verifying ISO: {}", metadata.display_name);
-        let current_checksum = IsoValidator::calculate_checksum(&iso_path)?;
-        
-        let valid = current_checksum == metadata.checksum;
-        if valid {
-            println!("✅ ISO integrity verified");
-        } else {
-            println!("❌ ISO integrity check failed!");
+    /// Re-hashes the ISO through CRC32/MD5/SHA-1/SHA-256 at once (see
+    /// `iso::verify`), stamps the result into its metadata, and optionally
+    /// checks it against `known_good` by SHA-1/size.
+    pub fn verify_iso(&mut self, iso_id: &str, known_good: Option<&KnownGoodDb>) -> Result<bool> {
+        let display_name = self.metadata_store.get_iso(iso_id)
+            .ok_or_else(|| UsbBootHutError::Iso("ISO not found".to_string()))?
+            .display_name.clone();
+
+        println!("🔍 Verifying ISO: {}", display_name);
+        let status = verify::verify_and_stamp(&mut self.metadata_store, &self.data_mount, iso_id, known_good)?;
+
+        match status {
+            VerificationStatus::KnownGood => println!("✅ ISO integrity verified (known-good release)"),
+            VerificationStatus::Verified => println!("✅ ISO integrity verified"),
+            VerificationStatus::Mismatch => println!("❌ ISO integrity check failed!"),
+            VerificationStatus::Unverified => unreachable!("verify_and_stamp always returns a verified status"),
         }
-        
-        Ok(valid)
+
+        Ok(status != VerificationStatus::Mismatch)
+    }
+
+    /// Loads a known-good (redump-style) release database for `verify_iso`/
+    /// `verify_all` to match ISOs against by SHA-1/size.
+    pub fn load_known_good(path: &Path) -> Result<KnownGoodDb> {
+        KnownGoodDb::load(path)
     }
     
-    pub fn verify_all(&mut self) -> Result<()> {
-        let iso_ids: Vec<String> = self.metadata_store.list_all()
+    /// Re-hashes every stored ISO through CRC32/MD5/SHA-1/SHA-256 at once
+    /// (see `iso::verify`) and compares the SHA-256 against its recorded
+    /// checksum, hashing up to `worker_count` files concurrently (default:
+    /// available cores) so a drive full of multi-gigabyte images doesn't
+    /// verify one file at a time. Each worker gets its own bar in a shared
+    /// `MultiProgress` display; digests and verification status (including
+    /// a `known_good` match by SHA-1/size, if given) are stamped back into
+    /// metadata once every worker has finished.
+    pub fn verify_all(&mut self, worker_count: Option<usize>, known_good: Option<&KnownGoodDb>) -> Result<()> {
+        use std::collections::VecDeque;
+        use std::sync::{mpsc, Arc, Mutex};
+        use std::thread;
+        use indicatif::{MultiProgress, ProgressStyle};
+
+        let expected_checksums: std::collections::HashMap<String, String> = self.metadata_store.list_all()
             .iter()
-            .map(|m| m.id.clone())
+            .map(|m| (m.id.clone(), m.checksum.clone()))
             .collect();
-            
+
+        let targets: VecDeque<(String, PathBuf)> = self.metadata_store.list_all()
+            .iter()
+            .map(|m| (m.id.clone(), self.data_mount.join("isos").join(&m.filename)))
+            .collect();
+
+        if targets.is_empty() {
+            println!("\n✅ All ISOs verified successfully");
+            return Ok(());
+        }
+
+        // Bound concurrency: more workers than files is pointless, and an
+        // unbounded pool would thrash a slow USB bus with contending reads.
+        let worker_count = worker_count
+            .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+            .max(1)
+            .min(targets.len());
+
+        println!("🔍 Verifying {} ISO(s) with {} worker thread(s)...", targets.len(), worker_count);
+
+        let queue = Arc::new(Mutex::new(targets));
+        let multi = MultiProgress::new();
+        let (tx, rx) = mpsc::channel();
+
+        let handles: Vec<_> = (0..worker_count).map(|_| {
+            let queue = Arc::clone(&queue);
+            let multi = multi.clone();
+            let tx = tx.clone();
+            let expected_checksums = expected_checksums.clone();
+
+            thread::spawn(move || {
+                loop {
+                    let next = queue.lock().unwrap().pop_front();
+                    let Some((iso_id, path)) = next else { break; };
+
+                    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    let pb = multi.add(ProgressBar::new(size));
+                    pb.set_style(
+                        ProgressStyle::default_bar()
+                            .template("{msg}\n[{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes}")
+                            .unwrap()
+                            .progress_chars("#>-")
+                    );
+                    pb.set_message(path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default());
+
+                    let outcome = verify::compute_digests_with_progress(&path, &pb);
+                    let expected = expected_checksums.get(&iso_id).cloned().unwrap_or_default();
+
+                    pb.finish_with_message(match &outcome {
+                        Ok(digests) if digests.sha256.eq_ignore_ascii_case(&expected) => "✅ verified".to_string(),
+                        Ok(_) => "❌ checksum mismatch".to_string(),
+                        Err(e) => format!("⚠️  error: {}", e),
+                    });
+
+                    let _ = tx.send((iso_id, outcome));
+                }
+            })
+        }).collect();
+
+        drop(tx);
+        for handle in handles {
+            let _ = handle.join();
+        }
+
         let mut failed = Vec::new();
-        
-        for iso_id in iso_ids {
-            match self.verify_iso(&iso_id) {
-                Ok(true) => {},
-                Ok(false) => failed.push(iso_id),
+        for (iso_id, outcome) in rx {
+            match outcome {
+                Ok(digests) => {
+                    let Some(metadata) = self.metadata_store.get_iso(&iso_id) else { continue; };
+                    let mut updated = metadata.clone();
+                    let matches = digests.sha256.eq_ignore_ascii_case(&metadata.checksum);
+
+                    updated.verification_status = if !matches {
+                        failed.push(iso_id.clone());
+                        VerificationStatus::Mismatch
+                    } else if let Some(entry) = known_good.and_then(|db| db.lookup(&digests.sha1, metadata.size)) {
+                        println!("✅ {} matched known-good release: {}", metadata.display_name, entry.name);
+                        VerificationStatus::KnownGood
+                    } else {
+                        VerificationStatus::Verified
+                    };
+                    updated.digests = Some(digests);
+                    if matches {
+                        updated.last_verified = Some(chrono::Utc::now());
+                    }
+
+                    let _ = self.metadata_store.update_iso(&iso_id, updated);
+                }
                 Err(e) => {
                     println!("⚠️  Error verifying ISO {}: {}", iso_id, e);
                     failed.push(iso_id);
                 }
             }
         }
-        
+
         if failed.is_empty() {
             println!("\n✅ All ISOs verified successfully");
         } else {
             println!("\n❌ {} ISO(s) failed verification", failed.len());
         }
-        
+
         Ok(())
     }
     
+    /// Finds byte-identical ISOs stored under this drive's `isos/` directory,
+    /// e.g. the same image added twice under different filenames.
+    pub fn find_duplicates(&self) -> Result<Vec<DuplicateGroup>> {
+        let iso_dir = self.data_mount.join("isos");
+        dedupe::find_duplicates(&iso_dir, &self.metadata_store)
+    }
+
+    /// Deletes every path in each group except the first (the copy kept),
+    /// mirroring `CleanupEngine::clean`'s dry-run/stats behaviour so
+    /// `dedupe` can be previewed before anything is removed.
+    pub fn remove_duplicates(&mut self, groups: &[DuplicateGroup], dry_run: bool) -> Result<CleanupStats> {
+        let mut stats = CleanupStats::default();
+
+        for group in groups {
+            for path in group.paths.iter().skip(1) {
+                stats.matched += 1;
+
+                if dry_run {
+                    continue;
+                }
+
+                match fs::remove_file(path) {
+                    Ok(_) => {
+                        stats.deleted += 1;
+                        stats.bytes_freed += group.size;
+
+                        if let Some(filename) = path.file_name().and_then(|f| f.to_str()) {
+                            let iso_id = self.metadata_store.list_all().iter()
+                                .find(|m| m.filename == filename)
+                                .map(|m| m.id.clone());
+                            if let Some(iso_id) = iso_id {
+                                let _ = self.metadata_store.remove_iso(&iso_id);
+                            }
+                        }
+                    }
+                    Err(_) => stats.errors += 1,
+                }
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Exports a versioned snapshot of the metadata catalog, the cleanup
+    /// config at `cleanup_config_path` (if one exists there), and the live
+    /// `grub.cfg`, so the bookkeeping living on the same medium as the ISOs
+    /// can be rebuilt elsewhere without touching the ISO files themselves.
+    pub fn create_snapshot(&self, cleanup_config_path: Option<&Path>) -> Result<Snapshot> {
+        let cleanup_config = match cleanup_config_path {
+            Some(path) if path.exists() => Some(CleanupEngine::load_config(path)?),
+            _ => None,
+        };
+
+        let grub_cfg_path = self.boot_mount.join("grub/grub.cfg");
+        let grub_config = if grub_cfg_path.exists() {
+            Some(fs::read_to_string(&grub_cfg_path).io_context("read grub.cfg", &grub_cfg_path)?)
+        } else {
+            None
+        };
+
+        Ok(Snapshot {
+            version: SNAPSHOT_VERSION,
+            created_at: chrono::Utc::now(),
+            metadata: self.metadata_store.list_all().to_vec(),
+            cleanup_config,
+            grub_config,
+        })
+    }
+
+    /// Writes `snapshot` under `.usb-boot-hut/snapshots/<name>.json` and
+    /// returns the path it was written to.
+    pub fn save_snapshot(&self, snapshot: &Snapshot, name: &str) -> Result<PathBuf> {
+        let dir = self.data_mount.join(".usb-boot-hut/snapshots");
+        fs::create_dir_all(&dir).io_context("create snapshot directory", &dir)?;
+
+        let path = dir.join(format!("{}.json", name));
+        let json = serde_json::to_string_pretty(snapshot)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to serialize snapshot: {}", e)))?;
+        atomic_write(&path, json.as_bytes())?;
+
+        Ok(path)
+    }
+
+    /// Reads back a snapshot previously written by `save_snapshot`.
+    pub fn load_snapshot(path: &Path) -> Result<Snapshot> {
+        let content = fs::read_to_string(path).io_context("read snapshot", path)?;
+
+        let snapshot: Snapshot = serde_json::from_str(&content)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to parse snapshot: {}", e)))?;
+
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(UsbBootHutError::Iso(format!(
+                "Unsupported snapshot version {} (expected {})",
+                snapshot.version, SNAPSHOT_VERSION
+            )));
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Rebuilds the live metadata catalog, cleanup config (if present in
+    /// the snapshot and `cleanup_config_path` is given), and `grub.cfg` from
+    /// `snapshot`. Doesn't touch `/isos` itself — call `reconcile` afterwards
+    /// if the ISO files may have drifted from what the snapshot recorded
+    /// (e.g. an ISO was added or removed after the snapshot was taken).
+    pub fn restore_snapshot(&mut self, snapshot: &Snapshot, cleanup_config_path: Option<&Path>) -> Result<()> {
+        let store_path = self.data_mount.join(".usb-boot-hut/metadata.json");
+        let sig_path = store_path.with_file_name("metadata.sig");
+        let json = serde_json::to_string_pretty(&snapshot.metadata)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to serialize metadata: {}", e)))?;
+        atomic_write(&store_path, json.as_bytes())?;
+
+        // `open` verifies `metadata.json` against `metadata.sig` before
+        // trusting it; re-sign the file we just restored first, or `open`
+        // checks the freshly-written catalog against the stale signature
+        // left over from before the restore, fails, and quarantines the
+        // very data this is supposed to recover.
+        if let Some(trust) = &self.trust {
+            trust.reseal(&store_path, &sig_path)?;
+        }
+        self.metadata_store = MetadataStore::open(&self.data_mount, self.trust.clone())?;
+
+        if let (Some(cleanup_config), Some(config_path)) = (&snapshot.cleanup_config, cleanup_config_path) {
+            let content = toml::to_string_pretty(cleanup_config)
+                .map_err(|e| UsbBootHutError::Config(format!("Failed to serialize cleanup config: {}", e)))?;
+            atomic_write(config_path, content.as_bytes())?;
+        }
+
+        if let Some(grub_config) = &snapshot.grub_config {
+            let grub_cfg_path = self.boot_mount.join("grub/grub.cfg");
+            atomic_write(&grub_cfg_path, grub_config.as_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Scans `/isos` against the metadata catalog so the drive can self-heal
+    /// after partial metadata loss, without needing a prior snapshot: files
+    /// present on disk but missing from metadata get re-registered (re-hashing
+    /// via `calculate_checksum_with_progress`, regenerating boot params
+    /// through `generate_boot_params`, and re-adding their GRUB entry), and
+    /// metadata entries whose files are gone are pruned.
+    pub fn reconcile(&mut self) -> Result<ReconcileStats> {
+        let mut stats = ReconcileStats::default();
+        let iso_dir = self.data_mount.join("isos");
+
+        let missing_ids: Vec<String> = self.metadata_store.list_all().iter()
+            .filter(|m| !iso_dir.join(&m.filename).exists())
+            .map(|m| m.id.clone())
+            .collect();
+        for id in missing_ids {
+            let _ = self.metadata_store.remove_iso(&id);
+            stats.pruned += 1;
+        }
+
+        let known_filenames: std::collections::HashSet<String> = self.metadata_store.list_all()
+            .iter()
+            .map(|m| m.filename.clone())
+            .collect();
+
+        let entries = fs::read_dir(&iso_dir).io_context("read ISO directory", &iso_dir)?;
+        for entry in entries {
+            let entry = entry.io_context("read ISO directory entry", &iso_dir)?;
+            let path = entry.path();
+
+            if !path.is_file() {
+                continue;
+            }
+
+            let Some(filename) = path.file_name().and_then(|f| f.to_str()).map(str::to_string) else {
+                continue;
+            };
+            if filename.ends_with(".part") || known_filenames.contains(&filename) {
+                continue;
+            }
+
+            println!("🔍 Re-registering orphaned ISO: {}", filename);
+            match self.reregister_orphaned_iso(&path, &filename) {
+                Ok(()) => stats.reregistered += 1,
+                Err(e) => println!("⚠️  Failed to re-register {}: {}", filename, e),
+            }
+        }
+
+        Ok(stats)
+    }
+
+    fn reregister_orphaned_iso(&mut self, path: &Path, filename: &str) -> Result<()> {
+        let iso_info = IsoValidator::validate_iso(path)?;
+        let checksum = with_progress(iso_info.size, "Calculating SHA256", |pb| {
+            Self::calculate_checksum_with_progress(path, pb)
+        })?;
+
+        let boot_params = self.generate_boot_params(&iso_info);
+        let metadata = IsoMetadata::new(filename.to_string(), iso_info.iso_type.clone(), iso_info.size, checksum);
+        let display_name = metadata.display_name.clone();
+
+        let grub_mgr = GrubConfigManager::new(&self.boot_mount);
+        let iso_rel_path = format!("/isos/{}", filename);
+        grub_mgr.add_iso_entry(&display_name, &iso_rel_path, &boot_params)?;
+
+        self.metadata_store.add_iso(metadata)
+    }
+
     fn calculate_checksum_with_progress(iso_path: &Path, pb: &ProgressBar) -> Result<String> {
         use sha2::{Sha256, Digest};
         
-        let mut file = File::open(iso_path)
-            .map_err(|e| UsbBootHutError::Iso(format!("Failed to open ISO: {}", e)))?;
-            
+        let mut file = File::open(iso_path).io_context("open ISO", iso_path)?;
+
         let mut hasher = Sha256::new();
         let mut buffer = vec![0u8; 4 * 1024 * 1024]; // 4MB chunks
         let mut total_read = 0u64;
-        
+
         loop {
-            let bytes_read = file.read(&mut buffer)
-                .map_err(|e| UsbBootHutError::Iso(format!("Failed to read: {}", e)))?;
-                
+            let bytes_read = file.read(&mut buffer).io_context("read ISO", iso_path)?;
+
             if bytes_read == 0 {
                 break;
             }
-            
+
             hasher.update(&buffer[..bytes_read]);
             total_read += bytes_read as u64;
             pb.set_position(total_read);
         }
-        
+
         Ok(hex::encode(hasher.finalize()))
     }
-    
+
     fn copy_with_progress(src: &Path, dest: &Path, pb: &ProgressBar) -> Result<()> {
-        let mut src_file = File::open(src)
-            .map_err(|e| UsbBootHutError::Iso(format!("Failed to open source: {}", e)))?;
-        let mut dest_file = File::create(dest)
-            .map_err(|e| UsbBootHutError::Iso(format!("Failed to create dest: {}", e)))?;
-            
+        let mut src_file = File::open(src).io_context("open source ISO", src)?;
+        let mut dest_file = File::create(dest).io_context("create destination ISO", dest)?;
+
         let mut buffer = vec![0u8; 4 * 1024 * 1024]; // 4MB chunks
         let mut total_written = 0u64;
-        
+
         loop {
-            let bytes_read = src_file.read(&mut buffer)
-                .map_err(|e| UsbBootHutError::Iso(format!("Failed to read: {}", e)))?;
-                
+            let bytes_read = src_file.read(&mut buffer).io_context("read source ISO", src)?;
+
             if bytes_read == 0 {
                 break;
             }
-            
-            dest_file.write_all(&buffer[..bytes_read])
-                .map_err(|e| UsbBootHutError::Iso(format!("Failed to write: {}", e)))?;
-                
+
+            dest_file.write_all(&buffer[..bytes_read]).io_context("write ISO", dest)?;
+
             total_written += bytes_read as u64;
             pb.set_position(total_written);
         }
-        
-        dest_file.sync_all()
-            .map_err(|e| UsbBootHutError::Iso(format!("Failed to sync: {}", e)))?;
-            
+
+        dest_file.sync_all().io_context("sync ISO", dest)?;
+
         Ok(())
     }
-    
+
+    /// Strips a known compression extension off `iso_path`'s filename so the
+    /// stored copy is named like the ISO it contains rather than the archive
+    /// it arrived in.
+    fn dest_filename(iso_path: &Path) -> Result<String> {
+        let filename = iso_path.file_name()
+            .ok_or_else(|| UsbBootHutError::Iso("Invalid ISO filename".to_string()))?
+            .to_string_lossy()
+            .to_string();
+
+        for ext in Self::COMPRESSED_EXTENSIONS {
+            if let Some(stripped) = filename.strip_suffix(ext) {
+                return Ok(stripped.to_string());
+            }
+        }
+
+        Ok(filename)
+    }
+
+    /// Derives a destination filename from the last path segment of a
+    /// download URL, stripping any query string (e.g. a signed S3 link).
+    fn url_filename(url: &str) -> Result<String> {
+        let without_query = url.split(['?', '#']).next().unwrap_or(url);
+        let filename = without_query.rsplit('/').next().unwrap_or_default();
+
+        if filename.is_empty() {
+            return Err(UsbBootHutError::Iso(format!("Could not derive a filename from URL: {}", url)));
+        }
+
+        Ok(filename.to_string())
+    }
+
+    /// Streams `url`'s body into `dest`, resuming via an HTTP `Range`
+    /// request if `dest` already holds a partial download from a previous
+    /// attempt (re-hashing what's already there first so the final digest
+    /// still covers the whole file). Hashes incrementally as bytes arrive so
+    /// `add_iso_from_url` never needs a second read pass to checksum the
+    /// result. `verify_checksum` is matched against the running digest once
+    /// the transfer completes; a `sha512:`-prefixed value is checked against
+    /// SHA-512 instead of the SHA-256 that's always computed for the catalog.
+    /// On mismatch the partial file is left in place for inspection rather
+    /// than deleted, since the bytes themselves may still be useful evidence
+    /// of what went wrong.
+    fn download_with_resume(url: &str, dest: &Path, verify_checksum: Option<&str>) -> Result<String> {
+        use sha2::{Sha256, Sha512, Digest};
+        use std::io::{Seek, SeekFrom};
+        use std::fs::OpenOptions;
+
+        let (expected_sha256, expected_sha512) = match verify_checksum {
+            Some(v) => match v.split_once(':') {
+                Some(("sha512", digest)) => (None, Some(digest.to_string())),
+                Some(("sha256", digest)) => (Some(digest.to_string()), None),
+                _ => (Some(v.to_string()), None),
+            },
+            None => (None, None),
+        };
+
+        let mut sha256 = Sha256::new();
+        let mut sha512 = expected_sha512.is_some().then(Sha512::new);
+
+        let mut resume_from = 0u64;
+        if dest.exists() {
+            resume_from = fs::metadata(dest).io_context("stat partial download", dest)?.len();
+
+            let mut existing = File::open(dest).io_context("open partial download", dest)?;
+            let mut buffer = vec![0u8; 4 * 1024 * 1024];
+            loop {
+                let n = existing.read(&mut buffer).io_context("read partial download", dest)?;
+                if n == 0 {
+                    break;
+                }
+                sha256.update(&buffer[..n]);
+                if let Some(sha512) = sha512.as_mut() {
+                    sha512.update(&buffer[..n]);
+                }
+            }
+        }
+
+        let client = reqwest::blocking::Client::new();
+        let mut request = client.get(url);
+        if resume_from > 0 {
+            println!("⏯️  Resuming from byte {}", resume_from);
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", resume_from));
+        }
+
+        let mut response = request.send()
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to request {}: {}", url, e)))?;
+
+        if !response.status().is_success() {
+            return Err(UsbBootHutError::Iso(format!("HTTP {} fetching {}", response.status(), url)));
+        }
+
+        // The server may not support Range at all and just resend the whole
+        // body with a 200; in that case start the file, and its hashes, over.
+        let resumed = resume_from > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        if resume_from > 0 && !resumed {
+            resume_from = 0;
+            sha256 = Sha256::new();
+            sha512 = sha512.map(|_| Sha512::new());
+        }
+
+        let total = resume_from + response.content_length().unwrap_or(0);
+        let pb = ProgressManager::new().create_bytes_progress(total, "Downloading ISO");
+        pb.set_position(resume_from);
+
+        let mut dest_file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(dest)
+            .io_context("open partial download", dest)?;
+        if resumed {
+            dest_file.seek(SeekFrom::End(0)).io_context("seek partial download", dest)?;
+        } else {
+            dest_file.set_len(0).io_context("truncate partial download", dest)?;
+        }
+
+        let mut buffer = vec![0u8; 4 * 1024 * 1024];
+        let mut total_written = resume_from;
+        loop {
+            let bytes_read = response.read(&mut buffer).io_context("download ISO", dest)?;
+            if bytes_read == 0 {
+                break;
+            }
+
+            sha256.update(&buffer[..bytes_read]);
+            if let Some(sha512) = sha512.as_mut() {
+                sha512.update(&buffer[..bytes_read]);
+            }
+            dest_file.write_all(&buffer[..bytes_read]).io_context("write downloaded ISO", dest)?;
+
+            total_written += bytes_read as u64;
+            pb.set_position(total_written);
+        }
+        dest_file.sync_all().io_context("sync downloaded ISO", dest)?;
+        pb.finish_with_message("Done");
+
+        let sha256_hex = hex::encode(sha256.finalize());
+
+        if let Some(expected) = &expected_sha256 {
+            if !sha256_hex.eq_ignore_ascii_case(expected) {
+                return Err(UsbBootHutError::Iso(format!(
+                    "SHA256 mismatch: expected {}, got {} ({} left in place for inspection)",
+                    expected, sha256_hex, dest.display()
+                )));
+            }
+        }
+        if let Some(expected) = &expected_sha512 {
+            let sha512_hex = hex::encode(sha512.expect("sha512 hasher created when expected_sha512 is Some").finalize());
+            if !sha512_hex.eq_ignore_ascii_case(expected) {
+                return Err(UsbBootHutError::Iso(format!(
+                    "SHA512 mismatch: expected {}, got {} ({} left in place for inspection)",
+                    expected, sha512_hex, dest.display()
+                )));
+            }
+        }
+
+        Ok(sha256_hex)
+    }
+
+    /// Streams a compressed source (per `iso::image::open_image`) straight
+    /// into `dest`, decompressing on the fly so the archive is never fully
+    /// materialized in memory, and hashes the decompressed bytes as they're
+    /// written. The total size isn't known ahead of time for a gzip/xz/
+    /// bzip2/zstd stream, so progress is reported as bytes processed rather
+    /// than a percentage.
+    fn decompress_copy_with_progress(src: &Path, dest: &Path) -> Result<String> {
+        use crate::iso::image;
+        use sha2::{Sha256, Digest};
+        use indicatif::ProgressStyle;
+
+        let mut reader = image::open_image(src)?;
+        let mut dest_file = File::create(dest).io_context("create destination ISO", dest)?;
+
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} Decompressing... {bytes} written")
+                .unwrap()
+        );
+        pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; 4 * 1024 * 1024]; // 4MB chunks
+        let mut total_written = 0u64;
+
+        loop {
+            let bytes_read = reader.read(&mut buffer).io_context("decompress", src)?;
+
+            if bytes_read == 0 {
+                break;
+            }
+
+            hasher.update(&buffer[..bytes_read]);
+            dest_file.write_all(&buffer[..bytes_read]).io_context("write ISO", dest)?;
+
+            total_written += bytes_read as u64;
+            pb.set_position(total_written);
+        }
+
+        dest_file.sync_all().io_context("sync ISO", dest)?;
+        pb.finish_with_message("done");
+
+        Ok(hex::encode(hasher.finalize()))
+    }
+
     fn get_available_space(&self) -> Result<u64> {
         #[cfg(target_os = "linux")]
         {
@@ -252,25 +965,45 @@ impl IsoManager {
             Ok(0)
         }
     }
-    
+
+    #[cfg(target_os = "linux")]
+    fn data_partition_is_fat32(&self) -> bool {
+        let Ok(mounts) = fs::read_to_string("/proc/mounts") else {
+            return false;
+        };
+        let mount_str = self.data_mount.to_string_lossy();
+
+        mounts.lines()
+            .filter_map(|line| {
+                // Each line is "device mountpoint fstype options dump pass".
+                let mut fields = line.split_whitespace();
+                let _device = fields.next()?;
+                let mount_point = fields.next()?;
+                let fs_type = fields.next()?;
+                Some((mount_point, fs_type))
+            })
+            .any(|(mount_point, fs_type)| mount_point == mount_str && fs_type == "vfat")
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn data_partition_is_fat32(&self) -> bool {
+        false
+    }
+
     fn generate_boot_params(&self, iso_info: &IsoInfo) -> BootParams {
-        use crate::iso::IsoType;
-        
-        match iso_info.iso_type {
-            IsoType::Ubuntu => BootParams::Ubuntu {
-                version: iso_info.volume_id.clone()
-            },
-            IsoType::Debian => BootParams::Debian {
-                version: iso_info.volume_id.clone()
-            },
-            IsoType::Arch => BootParams::Arch,
-            IsoType::Windows => BootParams::Windows {
-                version: iso_info.volume_id.clone()
-            },
-            _ => BootParams::Custom {
-                kernel: "/vmlinuz".to_string(),
-                initrd: "/initrd.img".to_string(),
-                params: "quiet splash".to_string(),
+        use crate::iso::IsoProber;
+
+        // Probe the actual directory tree rather than trusting the volume ID,
+        // since that's the only thing telling us casper/live/arch apart.
+        match IsoProber::detect(&iso_info.path, &iso_info.volume_id) {
+            Ok(detected) => detected.boot_params,
+            Err(e) => {
+                println!("⚠️  Could not inspect ISO layout ({}), falling back to a generic entry", e);
+                BootParams::Custom {
+                    kernel: "/vmlinuz".to_string(),
+                    initrd: "/initrd.img".to_string(),
+                    params: "quiet splash".to_string(),
+                }
             }
         }
     }
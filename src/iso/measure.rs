@@ -0,0 +1,187 @@
+use crate::{Result, UsbBootHutError};
+use crate::iso::fs::Iso9660Reader;
+use std::path::{Path, PathBuf};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512, Digest};
+
+/// Which digest to measure the boot chain with; PCR4 is extended with
+/// SHA-1 on legacy TPM 1.2 and with the matching SHA-256/SHA-512 bank on
+/// TPM 2.0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgo {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// A single-pass hasher covering every algorithm `measure_boot_chain`
+/// supports, mirroring `disk::burn::StreamHasher`'s per-algorithm dispatch.
+enum GenericHasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl GenericHasher {
+    fn new(algo: HashAlgo) -> Self {
+        match algo {
+            HashAlgo::Sha1 => GenericHasher::Sha1(Sha1::new()),
+            HashAlgo::Sha256 => GenericHasher::Sha256(Sha256::new()),
+            HashAlgo::Sha512 => GenericHasher::Sha512(Sha512::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            GenericHasher::Sha1(h) => h.update(data),
+            GenericHasher::Sha256(h) => h.update(data),
+            GenericHasher::Sha512(h) => h.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            GenericHasher::Sha1(h) => h.finalize().to_vec(),
+            GenericHasher::Sha256(h) => h.finalize().to_vec(),
+            GenericHasher::Sha512(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+fn digest_size(algo: HashAlgo) -> usize {
+    match algo {
+        HashAlgo::Sha1 => 20,
+        HashAlgo::Sha256 => 32,
+        HashAlgo::Sha512 => 64,
+    }
+}
+
+fn hash_bytes(algo: HashAlgo, data: &[u8]) -> Vec<u8> {
+    let mut hasher = GenericHasher::new(algo);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// One measured EFI boot image: which file inside the ISO, its Authenticode
+/// digest, and PCR4's value immediately after extending with it.
+#[derive(Debug, Clone)]
+pub struct ImageMeasurement {
+    pub path: PathBuf,
+    pub authenticode_digest: String,
+    pub pcr4_after: String,
+}
+
+/// The predicted PCR4 measurement chain for every EFI boot stub an ISO will
+/// load, in boot order.
+#[derive(Debug, Clone)]
+pub struct PcrReport {
+    pub algo: HashAlgo,
+    pub images: Vec<ImageMeasurement>,
+    pub final_pcr4: String,
+}
+
+/// Well-known UEFI boot stub paths, measured into PCR4 as
+/// `EV_EFI_BOOT_SERVICES_APPLICATION` events in the order firmware tries
+/// them.
+const EFI_BOOT_FILES: &[&str] = &[
+    "EFI/BOOT/BOOTX64.EFI",
+    "EFI/BOOT/BOOTIA32.EFI",
+    "EFI/BOOT/BOOTAA64.EFI",
+];
+
+/// Computes the Authenticode digest of every EFI boot stub `iso_path`
+/// contains and predicts the resulting PCR4 value, so a user can verify a
+/// USB will measure as expected before deployment without booting it.
+///
+/// PCR4 starts all-zero and is extended once per image found, in
+/// `EFI_BOOT_FILES` order: `PCR = H(PCR_old || H(event))`, where the event
+/// digest is the image's Authenticode hash (an `EV_EFI_BOOT_SERVICES_APPLICATION`
+/// event, per the TCG PC Client Platform Firmware Profile).
+pub fn measure_boot_chain(iso_path: &Path, algo: HashAlgo) -> Result<PcrReport> {
+    let mut reader = Iso9660Reader::open(iso_path)?;
+
+    let mut pcr = vec![0u8; digest_size(algo)];
+    let mut images = Vec::new();
+
+    for candidate in EFI_BOOT_FILES {
+        let Ok(pe_bytes) = reader.read_file(candidate) else {
+            continue;
+        };
+
+        let authenticode_digest = authenticode_hash(algo, &pe_bytes)?;
+
+        let event_digest = hash_bytes(algo, &authenticode_digest);
+        let mut extend_input = pcr.clone();
+        extend_input.extend_from_slice(&event_digest);
+        pcr = hash_bytes(algo, &extend_input);
+
+        images.push(ImageMeasurement {
+            path: PathBuf::from(format!("/{}", candidate)),
+            authenticode_digest: hex::encode(&authenticode_digest),
+            pcr4_after: hex::encode(&pcr),
+        });
+    }
+
+    Ok(PcrReport { algo, images, final_pcr4: hex::encode(&pcr) })
+}
+
+/// Computes a PE/COFF image's Authenticode digest: the whole file, except
+/// the 4-byte `CheckSum` field in the optional header and the 8-byte
+/// certificate-table data directory entry plus the attribute certificate
+/// table it points to (the Authenticode signature itself, appended at the
+/// file's tail) -- per Microsoft's "Calculating the PE Image Hash".
+fn authenticode_hash(algo: HashAlgo, pe: &[u8]) -> Result<Vec<u8>> {
+    if pe.len() < 0x40 || &pe[0..2] != b"MZ" {
+        return Err(UsbBootHutError::Iso("Not a valid PE file (missing MZ signature)".to_string()));
+    }
+
+    let pe_offset = u32::from_le_bytes(pe[0x3C..0x40].try_into().unwrap()) as usize;
+    if pe.len() < pe_offset + 24 || &pe[pe_offset..pe_offset + 4] != b"PE\0\0" {
+        return Err(UsbBootHutError::Iso("Not a valid PE file (missing PE signature)".to_string()));
+    }
+
+    // COFF File Header is 20 bytes, right after the 4-byte "PE\0\0" signature.
+    let optional_header_offset = pe_offset + 4 + 20;
+    if pe.len() < optional_header_offset + 2 {
+        return Err(UsbBootHutError::Iso("PE file truncated before optional header".to_string()));
+    }
+
+    let magic = u16::from_le_bytes(pe[optional_header_offset..optional_header_offset + 2].try_into().unwrap());
+    // PE32 has a 4-byte BaseOfData field PE32+ lacks, but PE32+'s ImageBase
+    // is 8 bytes instead of 4 -- the two cancel out, so CheckSum lands at
+    // offset 64 in both layouts. The data directory array does not: PE32+'s
+    // four stack/heap size fields are 8 bytes each, moving it from 96 to 112.
+    let is_pe32_plus = match magic {
+        0x10b => false,
+        0x20b => true,
+        _ => return Err(UsbBootHutError::Iso("Unsupported PE optional header magic".to_string())),
+    };
+
+    let checksum_offset = optional_header_offset + 64;
+    let data_directory_offset = optional_header_offset + if is_pe32_plus { 112 } else { 96 };
+    // IMAGE_DIRECTORY_ENTRY_SECURITY is data directory index 4, 8 bytes each.
+    let cert_dir_offset = data_directory_offset + 4 * 8;
+
+    if pe.len() < cert_dir_offset + 8 {
+        return Err(UsbBootHutError::Iso("PE file truncated before certificate table directory".to_string()));
+    }
+
+    let cert_table_offset = u32::from_le_bytes(pe[cert_dir_offset..cert_dir_offset + 4].try_into().unwrap()) as usize;
+    let cert_table_size = u32::from_le_bytes(pe[cert_dir_offset + 4..cert_dir_offset + 8].try_into().unwrap()) as usize;
+
+    let hash_end = if cert_table_size > 0
+        && cert_table_offset >= cert_dir_offset + 8
+        && cert_table_offset <= pe.len()
+    {
+        cert_table_offset
+    } else {
+        pe.len()
+    };
+
+    let mut hasher = GenericHasher::new(algo);
+    hasher.update(&pe[0..checksum_offset]);
+    hasher.update(&pe[checksum_offset + 4..cert_dir_offset]);
+    hasher.update(&pe[cert_dir_offset + 8..hash_end]);
+
+    Ok(hasher.finalize())
+}
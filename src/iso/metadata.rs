@@ -3,6 +3,8 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use crate::{Result, UsbBootHutError};
 use crate::iso::IsoType;
+use crate::iso::container::StoredCompression;
+use crate::iso::signing::MetadataTrust;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IsoMetadata {
@@ -17,6 +19,50 @@ pub struct IsoMetadata {
     pub boot_params: Option<BootConfiguration>,
     pub category: IsoCategory,
     pub tags: Vec<String>,
+    /// Per-algorithm digests from `iso::verify::compute_digests`; `None`
+    /// until the ISO has gone through a `verify` pass (`#[serde(default)]`
+    /// so metadata written before this field existed still deserializes).
+    #[serde(default)]
+    pub digests: Option<MultiDigest>,
+    /// Outcome of the most recent `verify` pass; `Unverified` until then.
+    #[serde(default)]
+    pub verification_status: VerificationStatus,
+    /// `Some` once `IsoManager::archive_iso` has replaced the raw file with
+    /// a compressed container to save space; `None` for a normal, directly
+    /// bootable ISO. `#[serde(default)]` for metadata written before this
+    /// field existed.
+    #[serde(default)]
+    pub compression: Option<StoredCompression>,
+    /// Which key vouches for this entry (e.g. "stable" vs. "community"), so
+    /// a drive can trust different channels against different keys. `None`
+    /// for entries added before this field existed, or on an unsigned
+    /// catalog. `#[serde(default)]` for backward compatibility.
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+/// CRC32, MD5, SHA-1, and SHA-256 of an ISO's decompressed contents,
+/// computed together in one read pass by `iso::verify::compute_digests`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiDigest {
+    pub crc32: String,
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+}
+
+/// Where an ISO stands with respect to its last `verify` pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum VerificationStatus {
+    /// Never run through `verify` (or added before this field existed).
+    #[default]
+    Unverified,
+    /// Re-hashed and matched the checksum recorded at add-time.
+    Verified,
+    /// Matched an entry in a known-good release database by SHA-1/size.
+    KnownGood,
+    /// Re-hashed but didn't match the checksum recorded at add-time.
+    Mismatch,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,21 +84,43 @@ pub enum IsoCategory {
 
 pub struct MetadataStore {
     store_path: PathBuf,
+    sig_path: PathBuf,
     metadata: Vec<IsoMetadata>,
+    trust: Option<MetadataTrust>,
 }
 
 impl MetadataStore {
     pub fn new(data_mount: &Path) -> Result<Self> {
+        Self::open(data_mount, None)
+    }
+
+    /// Same as `new`, but when `trust` is `Some`, `metadata.json` is
+    /// verified against its detached `metadata.sig` envelope before
+    /// `metadata` is populated: a missing, wrong-channel, or non-verifying
+    /// signature moves `metadata.json` aside to `metadata.json.quarantined`
+    /// and the load fails, rather than trusting a catalog that could have
+    /// been tampered with offline. Every `save` afterwards re-signs through
+    /// the same `trust` (a no-op if it's verify-only).
+    pub fn open(data_mount: &Path, trust: Option<MetadataTrust>) -> Result<Self> {
         let store_path = data_mount.join(".usb-boot-hut/metadata.json");
-        
+        let sig_path = store_path.with_file_name("metadata.sig");
+
         // Ensure directory exists
         if let Some(parent) = store_path.parent() {
             fs::create_dir_all(parent)
                 .map_err(|e| UsbBootHutError::Iso(format!("Failed to create metadata dir: {}", e)))?;
         }
-        
+
         // Load existing metadata or create new
         let metadata = if store_path.exists() {
+            if let Some(trust) = &trust {
+                if let Err(e) = trust.verify(&store_path, &sig_path) {
+                    let quarantine_path = store_path.with_file_name("metadata.json.quarantined");
+                    let _ = fs::rename(&store_path, &quarantine_path);
+                    return Err(e);
+                }
+            }
+
             let content = fs::read_to_string(&store_path)
                 .map_err(|e| UsbBootHutError::Iso(format!("Failed to read metadata: {}", e)))?;
             serde_json::from_str(&content)
@@ -60,10 +128,12 @@ impl MetadataStore {
         } else {
             Vec::new()
         };
-        
+
         Ok(Self {
             store_path,
+            sig_path,
             metadata,
+            trust,
         })
     }
     
@@ -128,10 +198,14 @@ impl MetadataStore {
     fn save(&self) -> Result<()> {
         let json = serde_json::to_string_pretty(&self.metadata)
             .map_err(|e| UsbBootHutError::Iso(format!("Failed to serialize metadata: {}", e)))?;
-            
-        fs::write(&self.store_path, json)
+
+        crate::utils::atomic_write(&self.store_path, json.as_bytes())
             .map_err(|e| UsbBootHutError::Iso(format!("Failed to write metadata: {}", e)))?;
-            
+
+        if let Some(trust) = &self.trust {
+            trust.reseal(&self.store_path, &self.sig_path)?;
+        }
+
         Ok(())
     }
 }
@@ -154,6 +228,10 @@ impl IsoMetadata {
             boot_params: None,
             category,
             tags: Vec::new(),
+            digests: None,
+            verification_status: VerificationStatus::Unverified,
+            compression: None,
+            channel: None,
         }
     }
     
@@ -1,7 +1,27 @@
 pub mod manager;
 pub mod validator;
 pub mod metadata;
+pub mod image;
+pub mod probe;
+pub mod dedupe;
+pub mod snapshot;
+pub mod fs;
+pub mod eltorito;
+pub mod measure;
+pub mod verify;
+pub mod container;
+pub mod signing;
 
 pub use manager::*;
 pub use validator::*;
-pub use metadata::*;
\ No newline at end of file
+pub use metadata::*;
+pub use image::*;
+pub use probe::*;
+pub use dedupe::*;
+pub use snapshot::*;
+pub use fs::*;
+pub use eltorito::*;
+pub use measure::*;
+pub use verify::*;
+pub use container::*;
+pub use signing::*;
\ No newline at end of file
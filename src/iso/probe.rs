@@ -0,0 +1,108 @@
+use crate::Result;
+use crate::bootloader::BootParams;
+use crate::iso::fs::Iso9660Reader;
+use std::path::Path;
+
+/// The flavour GRUB/Syslinux config generation needs: which `BootParams`
+/// variant to emit, and the exact kernel/initrd paths found inside the ISO.
+pub struct DetectedBoot {
+    pub boot_params: BootParams,
+    pub kernel: String,
+    pub initrd: String,
+}
+
+/// Inspects an ISO's directory tree the way grml2usb enumerates flavours,
+/// so `IsoManager::add_iso` can pick a `BootParams` without the caller
+/// having to guess one.
+pub struct IsoProber;
+
+impl IsoProber {
+    /// Lists the ISO 9660 tree via `Iso9660Reader` (Joliet- and Rock
+    /// Ridge-aware, so names come back in their real case) and matches
+    /// well-known live-CD layouts against it.
+    pub fn detect(iso_path: &Path, volume_id: &str) -> Result<DetectedBoot> {
+        let entries = Self::list_entries(iso_path)?;
+        let has = |needle: &str| entries.iter().any(|e| e.eq_ignore_ascii_case(needle));
+
+        if has("/casper/vmlinuz") {
+            return Ok(DetectedBoot {
+                boot_params: BootParams::Ubuntu { version: volume_id.to_string() },
+                kernel: "/casper/vmlinuz".to_string(),
+                initrd: "/casper/initrd".to_string(),
+            });
+        }
+
+        if has("/live/vmlinuz") && has("/.disk/info") {
+            return Ok(DetectedBoot {
+                boot_params: BootParams::Debian { version: volume_id.to_string() },
+                kernel: "/live/vmlinuz".to_string(),
+                initrd: "/live/initrd.img".to_string(),
+            });
+        }
+
+        if let Some(kernel) = Self::newest_match(&entries, "/arch/boot/x86_64/vmlinuz-linux") {
+            return Ok(DetectedBoot {
+                boot_params: BootParams::Arch,
+                kernel,
+                initrd: "/arch/boot/x86_64/initramfs-linux.img".to_string(),
+            });
+        }
+
+        if has("/sources/boot.wim") {
+            return Ok(DetectedBoot {
+                boot_params: BootParams::Windows { version: volume_id.to_string() },
+                kernel: String::new(),
+                initrd: "/sources/boot.wim".to_string(),
+            });
+        }
+
+        // Generic BIOS-era layout: a bare kernel plus isolinux or an embedded
+        // GRUB config, with no distro-specific loopback convention to rely on.
+        if let Some(kernel) = Self::newest_match(&entries, "/boot/vmlinuz") {
+            let initrd = Self::newest_match(&entries, "/boot/initrd")
+                .unwrap_or_else(|| "/boot/initrd.img".to_string());
+            let params = if has("/isolinux/isolinux.cfg") || has("/boot/grub/grub.cfg") {
+                "boot=live quiet splash".to_string()
+            } else {
+                "quiet splash".to_string()
+            };
+            return Ok(DetectedBoot {
+                boot_params: BootParams::Custom { kernel: kernel.clone(), initrd: initrd.clone(), params },
+                kernel,
+                initrd,
+            });
+        }
+
+        // Nothing recognised: hand back a Custom entry that boots into a clear
+        // error instead of guessing at a kernel path that doesn't exist.
+        Ok(DetectedBoot {
+            boot_params: BootParams::Custom {
+                kernel: String::new(),
+                initrd: String::new(),
+                params: "echo 'usb-boot-hut: could not detect a bootable kernel on this ISO'".to_string(),
+            },
+            kernel: String::new(),
+            initrd: String::new(),
+        })
+    }
+
+    /// Returns the lexicographically-last entry under `prefix`, a reasonable
+    /// proxy for "newest" when an ISO ships more than one kernel build
+    /// (e.g. `vmlinuz-linux` and `vmlinuz-linux-lts`).
+    fn newest_match(entries: &[String], prefix: &str) -> Option<String> {
+        let prefix_lower = prefix.to_lowercase();
+        let mut matches: Vec<&String> = entries.iter()
+            .filter(|e| e.to_lowercase().starts_with(&prefix_lower))
+            .collect();
+        matches.sort();
+        matches.last().map(|s| (*s).clone())
+    }
+
+    fn list_entries(iso_path: &Path) -> Result<Vec<String>> {
+        Ok(Iso9660Reader::open(iso_path)?
+            .list_files()?
+            .into_iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect())
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{Result, UsbBootHutError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Identifies this crate's detached-signature envelope format so a stray or
+/// foreign `.sig` file next to `metadata.json` doesn't get misread as one.
+const SIGNATURE_MAGIC: [u8; 4] = *b"UBHS";
+/// Bumped if the envelope layout below ever changes incompatibly.
+const SIGNATURE_FORMAT_VERSION: u16 = 1;
+
+/// Detached signature over `metadata.json`, stored as
+/// `.usb-boot-hut/metadata.sig`: a small header naming the signing channel,
+/// followed by the raw (binary, non-armored) OpenPGP signature `gpg
+/// --detach-sign` produces. Verification and signing both shell out to
+/// `gpg`, the same approach `disk::burn`'s source-image signature check
+/// uses.
+struct SignatureEnvelope {
+    channel: String,
+    signature: Vec<u8>,
+}
+
+impl SignatureEnvelope {
+    fn read(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let bytes = std::fs::read(path)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read metadata signature: {}", e)))?;
+
+        if bytes.len() < 8 || bytes[0..4] != SIGNATURE_MAGIC {
+            return Err(UsbBootHutError::SignatureVerification(
+                "metadata.sig is not a recognized USB Boot Hut signature envelope".to_string()
+            ));
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != SIGNATURE_FORMAT_VERSION {
+            return Err(UsbBootHutError::SignatureVerification(format!(
+                "metadata.sig format version {} is not supported (expected {})",
+                version, SIGNATURE_FORMAT_VERSION
+            )));
+        }
+
+        let channel_len = u16::from_le_bytes([bytes[6], bytes[7]]) as usize;
+        let channel_start = 8;
+        let channel_end = channel_start + channel_len;
+        if bytes.len() < channel_end {
+            return Err(UsbBootHutError::SignatureVerification("metadata.sig is truncated".to_string()));
+        }
+
+        let channel = String::from_utf8(bytes[channel_start..channel_end].to_vec())
+            .map_err(|e| UsbBootHutError::SignatureVerification(format!("metadata.sig channel name is not valid UTF-8: {}", e)))?;
+        let signature = bytes[channel_end..].to_vec();
+
+        Ok(Some(Self { channel, signature }))
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        let mut bytes = Vec::with_capacity(8 + self.channel.len() + self.signature.len());
+        bytes.extend_from_slice(&SIGNATURE_MAGIC);
+        bytes.extend_from_slice(&SIGNATURE_FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.channel.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(self.channel.as_bytes());
+        bytes.extend_from_slice(&self.signature);
+
+        crate::utils::atomic_write(path, &bytes)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to write metadata signature: {}", e)))
+    }
+}
+
+/// Which channel's key a device's ISO catalog is signed/verified against,
+/// and where to find that key. Different shared drives (e.g. a team's
+/// "stable" drive vs. a "community" drive anyone can add to) can pin
+/// different trusted keys, matching how `IsoMetadata::channel` tags entries
+/// by who vouches for them.
+#[derive(Debug, Clone)]
+pub struct MetadataTrust {
+    pub channel: String,
+    pub trusted_keyring: Option<PathBuf>,
+    pub signing_key: Option<String>,
+}
+
+impl MetadataTrust {
+    /// Verification only: checks `metadata.json` against `metadata.sig` with
+    /// `gpg --verify`, pinning `trusted_keyring` if given.
+    pub fn verify_only(channel: impl Into<String>, trusted_keyring: Option<PathBuf>) -> Self {
+        Self { channel: channel.into(), trusted_keyring, signing_key: None }
+    }
+
+    /// Verification plus the ability to (re-)sign after a save, using
+    /// `signing_key` (a `gpg --local-user`-style key ID or fingerprint).
+    pub fn signing(channel: impl Into<String>, trusted_keyring: Option<PathBuf>, signing_key: String) -> Self {
+        Self { channel: channel.into(), trusted_keyring, signing_key: Some(signing_key) }
+    }
+
+    /// Verifies `metadata_path` against the envelope at `sig_path`. Fails if
+    /// there's no envelope, it names a different channel, or `gpg` rejects
+    /// the signature.
+    pub(crate) fn verify(&self, metadata_path: &Path, sig_path: &Path) -> Result<()> {
+        let envelope = SignatureEnvelope::read(sig_path)?.ok_or_else(|| {
+            UsbBootHutError::SignatureVerification("No metadata.sig found; catalog is unsigned".to_string())
+        })?;
+
+        if envelope.channel != self.channel {
+            return Err(UsbBootHutError::SignatureVerification(format!(
+                "metadata.sig was signed for channel '{}', expected '{}'",
+                envelope.channel, self.channel
+            )));
+        }
+
+        let mut sig_file = tempfile::NamedTempFile::new()
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to create temp file: {}", e)))?;
+        sig_file.write_all(&envelope.signature)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to write temp signature: {}", e)))?;
+
+        let mut cmd = Command::new("gpg");
+        cmd.arg("--batch");
+        if let Some(keyring) = &self.trusted_keyring {
+            cmd.args(["--no-default-keyring", "--keyring"]).arg(keyring);
+        }
+        cmd.arg("--verify").arg(sig_file.path()).arg(metadata_path);
+
+        let output = cmd.output()
+            .map_err(|e| UsbBootHutError::SignatureVerification(format!("Failed to run gpg: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::SignatureVerification(
+                format!("Metadata catalog signature did not verify: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Re-signs `metadata_path`, overwriting `sig_path`, with `gpg
+    /// --detach-sign --local-user <signing_key>`. A no-op if no signing key
+    /// is configured (verify-only trust).
+    pub(crate) fn reseal(&self, metadata_path: &Path, sig_path: &Path) -> Result<()> {
+        let Some(signing_key) = &self.signing_key else { return Ok(()); };
+
+        let sig_out = tempfile::NamedTempFile::new()
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to create temp file: {}", e)))?;
+
+        let output = Command::new("gpg")
+            .args(["--batch", "--yes", "--local-user", signing_key, "--detach-sign", "--output"])
+            .arg(sig_out.path())
+            .arg(metadata_path)
+            .output()
+            .map_err(|e| UsbBootHutError::SignatureVerification(format!("Failed to run gpg: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(UsbBootHutError::SignatureVerification(
+                format!("Failed to sign metadata catalog: {}", String::from_utf8_lossy(&output.stderr))
+            ));
+        }
+
+        let signature = std::fs::read(sig_out.path())
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read generated signature: {}", e)))?;
+
+        SignatureEnvelope { channel: self.channel.clone(), signature }.write(sig_path)
+    }
+}
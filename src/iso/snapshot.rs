@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use crate::iso::IsoMetadata;
+use crate::cleanup::CleanupConfig;
+
+/// The current on-disk snapshot format. Bump when `Snapshot`'s shape
+/// changes so `IsoManager::load_snapshot` can reject an incompatible file
+/// instead of silently deserializing garbage.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// A point-in-time export of everything needed to rebuild a drive's
+/// bookkeeping without touching the (large) ISO files themselves: the
+/// metadata catalog, the cleanup rules, and the generated GRUB menu. A
+/// corrupted filesystem can wipe this bookkeeping while the ISOs survive,
+/// so keeping it in one portable file lets a drive be rebuilt elsewhere.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub metadata: Vec<IsoMetadata>,
+    pub cleanup_config: Option<CleanupConfig>,
+    pub grub_config: Option<String>,
+}
+
+/// Outcome of `IsoManager::reconcile`: what changed while scanning `/isos`
+/// against the metadata catalog.
+#[derive(Debug, Default)]
+pub struct ReconcileStats {
+    pub reregistered: u64,
+    pub pruned: u64,
+}
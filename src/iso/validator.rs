@@ -1,4 +1,5 @@
 use crate::{Result, UsbBootHutError};
+use crate::iso::eltorito::BootEntry;
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
@@ -8,9 +9,17 @@ pub struct IsoValidator;
 
 impl IsoValidator {
     pub fn validate_iso(iso_path: &Path) -> Result<IsoInfo> {
-        let mut file = File::open(iso_path)
+        // Transparently decompress gzip/zstd/bzip2/xz sources (and archived
+        // containers) into a seekable temp file first, so the ISO 9660 checks
+        // below run against the underlying disc image rather than failing on
+        // a compressed container's own magic bytes. CISO/WBFS images are not
+        // decompressed here (`materialize` skips them) and fail the check.
+        let materialized = crate::iso::image::materialize(iso_path)?;
+        let real_path = materialized.path();
+
+        let mut file = File::open(real_path)
             .map_err(|e| UsbBootHutError::Iso(format!("Failed to open ISO: {}", e)))?;
-            
+
         // Check ISO 9660 signature
         let mut buffer = vec![0u8; 6];
         file.seek(SeekFrom::Start(0x8001))
@@ -34,7 +43,8 @@ impl IsoValidator {
             .to_string();
             
         // Check for bootability
-        let bootable = Self::check_bootable(&mut file)?;
+        let boot_entries = crate::iso::eltorito::parse_boot_catalog(real_path)?;
+        let bootable = Self::check_bootable(&boot_entries, real_path)?;
         
         // Get file size
         file.seek(SeekFrom::End(0))
@@ -47,28 +57,35 @@ impl IsoValidator {
             volume_id: volume_id.clone(),
             size,
             bootable,
+            boot_entries,
             iso_type: Self::detect_iso_type(&volume_id),
         })
     }
-    
-    fn check_bootable(file: &mut File) -> Result<bool> {
-        // Check El Torito boot record
-        let mut buffer = vec![0u8; 32];
-        file.seek(SeekFrom::Start(0x8801))
-            .map_err(|e| UsbBootHutError::Iso(format!("Failed to seek: {}", e)))?;
-        file.read_exact(&mut buffer)
-            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read boot record: {}", e)))?;
-            
-        // Check for El Torito signature
-        if &buffer[0..5] == b"\x00CD001" && &buffer[30..32] == b"\x55\xAA" {
+
+    /// True if El Torito names any bootable entry (BIOS or UEFI), or, for
+    /// ISOs without El Torito at all, if the ISO 9660 directory tree (which
+    /// is Joliet/Rock Ridge aware) contains a known EFI boot stub. A parse
+    /// failure in the directory walk isn't propagated, since this is a
+    /// best-effort bootability signal rather than a hard requirement.
+    fn check_bootable(boot_entries: &[BootEntry], iso_path: &Path) -> Result<bool> {
+        if boot_entries.iter().any(|entry| entry.bootable) {
             return Ok(true);
         }
-        
-        // Check for UEFI boot
-        // This would involve looking for EFI/BOOT/BOOTX64.EFI in the ISO
-        // For now, we'll assume ISOs with certain patterns are bootable
-        
-        Ok(false)
+
+        const EFI_BOOT_FILES: &[&str] = &[
+            "EFI/BOOT/BOOTX64.EFI",
+            "EFI/BOOT/BOOTIA32.EFI",
+            "EFI/BOOT/BOOTAA64.EFI",
+        ];
+
+        let files = crate::iso::fs::Iso9660Reader::open(iso_path)
+            .and_then(|mut reader| reader.list_files())
+            .unwrap_or_default();
+        let names: std::collections::HashSet<String> = files.iter()
+            .map(|p| p.to_string_lossy().trim_start_matches('/').to_lowercase())
+            .collect();
+
+        Ok(EFI_BOOT_FILES.iter().any(|candidate| names.contains(&candidate.to_lowercase())))
     }
     
     fn detect_iso_type(volume_id: &str) -> IsoType {
@@ -91,24 +108,26 @@ impl IsoValidator {
         }
     }
     
+    /// Hashes `iso_path`'s decompressed logical contents, so a compressed
+    /// image (zstd/bzip2/xz/CISO/WBFS) and its raw `.iso` produce the same
+    /// checksum.
     pub fn calculate_checksum(iso_path: &Path) -> Result<String> {
-        let mut file = File::open(iso_path)
-            .map_err(|e| UsbBootHutError::Iso(format!("Failed to open ISO: {}", e)))?;
-            
+        let mut reader = crate::iso::image::open_image(iso_path)?;
+
         let mut hasher = Sha256::new();
         let mut buffer = vec![0u8; 1024 * 1024]; // 1MB chunks
-        
+
         loop {
-            let bytes_read = file.read(&mut buffer)
+            let bytes_read = reader.read(&mut buffer)
                 .map_err(|e| UsbBootHutError::Iso(format!("Failed to read: {}", e)))?;
-                
+
             if bytes_read == 0 {
                 break;
             }
-            
+
             hasher.update(&buffer[..bytes_read]);
         }
-        
+
         Ok(hex::encode(hasher.finalize()))
     }
     
@@ -116,6 +135,13 @@ impl IsoValidator {
         let calculated = Self::calculate_checksum(iso_path)?;
         Ok(calculated.eq_ignore_ascii_case(expected_checksum))
     }
+
+    /// True if `iso_path` is a compressed archive (gzip/bzip2/xz/zstd) that
+    /// must be decompressed before `validate_iso` can seek its ISO 9660
+    /// structure.
+    pub fn is_compressed(iso_path: &Path) -> Result<bool> {
+        crate::iso::image::is_compressed(iso_path)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -124,6 +150,7 @@ pub struct IsoInfo {
     pub volume_id: String,
     pub size: u64,
     pub bootable: bool,
+    pub boot_entries: Vec<BootEntry>,
     pub iso_type: IsoType,
 }
 
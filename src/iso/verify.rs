@@ -0,0 +1,162 @@
+use crate::{Result, UsbBootHutError, IoContext};
+use crate::iso::{MetadataStore, MultiDigest, VerificationStatus};
+use crc32fast::Hasher as Crc32Hasher;
+use indicatif::ProgressBar;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Sha256, Digest};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Streams `iso_path`'s decompressed logical contents through CRC32, MD5,
+/// SHA-1, and SHA-256 simultaneously in a single read pass, the way disc
+/// tools (redump and friends) compute every digest a known-good database
+/// might be keyed on without re-reading the image once per algorithm.
+pub fn compute_digests(iso_path: &Path) -> Result<MultiDigest> {
+    let mut reader = crate::iso::image::open_image(iso_path)?;
+
+    let mut crc32 = Crc32Hasher::new();
+    let mut md5 = Md5::new();
+    let mut sha1 = Sha1::new();
+    let mut sha256 = Sha256::new();
+
+    let mut buffer = vec![0u8; 4 * 1024 * 1024];
+    loop {
+        let bytes_read = reader.read(&mut buffer)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read: {}", e)))?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        let chunk = &buffer[..bytes_read];
+        crc32.update(chunk);
+        md5.update(chunk);
+        sha1.update(chunk);
+        sha256.update(chunk);
+    }
+
+    Ok(MultiDigest {
+        crc32: format!("{:08x}", crc32.finalize()),
+        md5: hex::encode(md5.finalize()),
+        sha1: hex::encode(sha1.finalize()),
+        sha256: hex::encode(sha256.finalize()),
+    })
+}
+
+/// Same as `compute_digests` (including its transparent decompression, so
+/// an ISO that `IsoManager::archive_iso` replaced with a container still
+/// hashes to its original contents), but reports progress through `pb` in
+/// decompressed bytes, for callers that want a progress bar on a
+/// potentially multi-gigabyte file.
+pub fn compute_digests_with_progress(iso_path: &Path, pb: &ProgressBar) -> Result<MultiDigest> {
+    let mut file = crate::iso::image::open_image(iso_path)?;
+
+    let mut crc32 = Crc32Hasher::new();
+    let mut md5 = Md5::new();
+    let mut sha1 = Sha1::new();
+    let mut sha256 = Sha256::new();
+
+    let mut buffer = vec![0u8; 4 * 1024 * 1024];
+    let mut total_read = 0u64;
+
+    loop {
+        let bytes_read = file.read(&mut buffer).io_context("read ISO", iso_path)?;
+        if bytes_read == 0 {
+            break;
+        }
+
+        let chunk = &buffer[..bytes_read];
+        crc32.update(chunk);
+        md5.update(chunk);
+        sha1.update(chunk);
+        sha256.update(chunk);
+
+        total_read += bytes_read as u64;
+        pb.set_position(total_read);
+    }
+
+    Ok(MultiDigest {
+        crc32: format!("{:08x}", crc32.finalize()),
+        md5: hex::encode(md5.finalize()),
+        sha1: hex::encode(sha1.finalize()),
+        sha256: hex::encode(sha256.finalize()),
+    })
+}
+
+/// One release record from a known-good (redump-style) database.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct KnownGoodEntry {
+    pub name: String,
+    pub sha1: String,
+    pub size: u64,
+}
+
+/// A known-good release database, keyed by (SHA-1, size) so a renamed but
+/// otherwise byte-identical ISO still matches.
+pub struct KnownGoodDb {
+    entries: HashMap<(String, u64), KnownGoodEntry>,
+}
+
+impl KnownGoodDb {
+    /// Loads a JSON array of `KnownGoodEntry` records from `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to read known-good database: {}", e)))?;
+        let records: Vec<KnownGoodEntry> = serde_json::from_str(&content)
+            .map_err(|e| UsbBootHutError::Iso(format!("Failed to parse known-good database: {}", e)))?;
+
+        let entries = records.into_iter()
+            .map(|entry| ((entry.sha1.to_lowercase(), entry.size), entry))
+            .collect();
+
+        Ok(Self { entries })
+    }
+
+    pub fn lookup(&self, sha1: &str, size: u64) -> Option<&KnownGoodEntry> {
+        self.entries.get(&(sha1.to_lowercase(), size))
+    }
+}
+
+/// Re-hashes the ISO backing `iso_id` through every supported digest,
+/// compares the SHA-256 against the checksum recorded at add-time, and
+/// (if `known_good` is given) checks whether it matches a recognized
+/// official release by SHA-1/size. Always stamps the new digests and the
+/// resulting status back into `metadata_store`; `last_verified` is only
+/// updated when the checksum matched, since a mismatch means the file on
+/// disk isn't what was last verified.
+pub fn verify_and_stamp(
+    metadata_store: &mut MetadataStore,
+    data_mount: &Path,
+    iso_id: &str,
+    known_good: Option<&KnownGoodDb>,
+) -> Result<VerificationStatus> {
+    let metadata = metadata_store.get_iso(iso_id)
+        .ok_or_else(|| UsbBootHutError::Iso("ISO not found".to_string()))?
+        .clone();
+
+    let iso_path = data_mount.join("isos").join(&metadata.filename);
+    let digests = compute_digests(&iso_path)?;
+
+    let mut updated = metadata.clone();
+    updated.digests = Some(digests.clone());
+
+    let status = if !digests.sha256.eq_ignore_ascii_case(&metadata.checksum) {
+        VerificationStatus::Mismatch
+    } else if let Some(entry) = known_good.and_then(|db| db.lookup(&digests.sha1, metadata.size)) {
+        println!("✅ Matched known-good release: {}", entry.name);
+        VerificationStatus::KnownGood
+    } else {
+        VerificationStatus::Verified
+    };
+
+    updated.verification_status = status;
+    if status != VerificationStatus::Mismatch {
+        updated.last_verified = Some(chrono::Utc::now());
+    }
+
+    metadata_store.update_iso(iso_id, updated)?;
+
+    Ok(status)
+}
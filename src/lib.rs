@@ -8,43 +8,82 @@ pub mod cleanup;
 pub mod config;
 pub mod utils;
 
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum UsbBootHutError {
     #[error("Device error: {0}")]
     Device(String),
-    
+
     #[error("Partition error: {0}")]
     Partition(String),
-    
+
     #[error("Encryption error: {0}")]
     Encryption(String),
-    
+
     #[error("Bootloader error: {0}")]
     Bootloader(String),
-    
+
     #[error("ISO error: {0}")]
     Iso(String),
-    
+
     #[error("Configuration error: {0}")]
     Config(String),
-    
+
     #[error("Permission error: {0}")]
     Permission(String),
-    
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
-    
+
+    /// An I/O failure tied to a specific file, with the originating
+    /// `io::Error` preserved as the `source` so callers can match on its
+    /// `ErrorKind` (permission denied, out of space, not found, ...) instead
+    /// of pattern-matching a formatted string.
+    #[error("failed to {action} {path}")]
+    PathIo {
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
     #[error("Dialog error: {0}")]
     Dialog(String),
-    
+
     #[error("Platform not supported: {0}")]
     UnsupportedPlatform(String),
+
+    #[error("Source image digest mismatch: expected {expected}, got {actual} (bad download or corrupt source)")]
+    SourceDigestMismatch { expected: String, actual: String },
+
+    #[error("Device readback digest mismatch: expected {expected}, got {actual} (write may have failed or disk is faulty)")]
+    DeviceReadbackMismatch { expected: String, actual: String },
+
+    #[error("Signature verification failed: {0}")]
+    SignatureVerification(String),
 }
 
 pub type Result<T> = std::result::Result<T, UsbBootHutError>;
 
+/// Attaches file-path context to a raw `io::Result`, turning it into a
+/// `UsbBootHutError::PathIo` that keeps the original `io::Error` as its
+/// `source` instead of flattening it into a string.
+pub trait IoContext<T> {
+    fn io_context(self, action: &'static str, path: &Path) -> Result<T>;
+}
+
+impl<T> IoContext<T> for std::result::Result<T, std::io::Error> {
+    fn io_context(self, action: &'static str, path: &Path) -> Result<T> {
+        self.map_err(|source| UsbBootHutError::PathIo {
+            action,
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
 pub const APP_NAME: &str = "USB Boot Hut";
 pub const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const MIN_DRIVE_SIZE: u64 = 4 * 1024 * 1024 * 1024; // 4GB minimum
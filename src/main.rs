@@ -22,11 +22,25 @@ fn main() {
     
     // Run the command
     if let Err(e) = commands::run(cli) {
-        eprintln!("{} {}", "Error:".red().bold(), e);
+        print_error_chain(&e);
         process::exit(1);
     }
 }
 
+/// Prints an error followed by its full `source()` chain, so a `PathIo`
+/// failure shows both the file it was operating on and the underlying
+/// `io::Error` (permission denied, disk full, not found, ...) instead of
+/// just the outermost message.
+fn print_error_chain(err: &dyn std::error::Error) {
+    eprintln!("{} {}", "Error:".red().bold(), err);
+
+    let mut source = err.source();
+    while let Some(cause) = source {
+        eprintln!("  {} {}", "caused by:".dimmed(), cause);
+        source = cause.source();
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn is_root() -> bool {
     unsafe { libc::geteuid() == 0 }
@@ -49,6 +63,8 @@ fn needs_root(cli: &Cli) -> bool {
         Commands::Add { .. } |
         Commands::Remove { .. } |
         Commands::Clean { .. } |
+        Commands::Dedupe { .. } |
+        Commands::Backup { .. } |
         Commands::UpdateGrub { .. } => true,
         
         Commands::List { .. } |
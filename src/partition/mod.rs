@@ -1,156 +1,490 @@
 use crate::{Result, UsbBootHutError, ESP_SIZE, BOOT_SIZE};
+use gptman::{GPT, GPTPartitionEntry};
+use std::fs::{File, OpenOptions};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+
+/// Partition-type GUIDs for the entries we write; see the UEFI spec's
+/// "Partition Type GUIDs" appendix.
+const ESP_TYPE_GUID: &str = "C12A7328-F81F-11D2-BA4B-00A0C93EC93B";
+const LINUX_DATA_TYPE_GUID: &str = "0FC63DAF-8483-4772-8E79-3D69D8477DE4";
+
+/// Partition starts are rounded up to this boundary, matching every other
+/// partitioning tool's default alignment (plays well with 4Kn sectors and
+/// SSD/flash erase-block wear-levelling).
+const PARTITION_ALIGNMENT_BYTES: u64 = 1024 * 1024;
+
+/// `BLKRRPART`: `_IO(0x12, 95)` from `linux/fs.h`. Asks the kernel to
+/// re-read the partition table of the device the fd refers to.
+#[cfg(target_os = "linux")]
+const BLKRRPART: libc::c_ulong = 0x125F;
+
+/// Data-partition filesystem choice for `Format`'s `--data-fs` flag, and the
+/// type behind `AppConfig::default_data_filesystem` when no flag is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DataFilesystem {
+    Ext4,
+    Exfat,
+    Btrfs,
+}
+
+impl DataFilesystem {
+    /// Parses a config/CLI string (case-insensitive) into a `DataFilesystem`.
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "ext4" => Ok(Self::Ext4),
+            "exfat" => Ok(Self::Exfat),
+            "btrfs" => Ok(Self::Btrfs),
+            other => Err(UsbBootHutError::Config(
+                format!("Unknown data filesystem '{}': expected ext4, exfat, or btrfs", other)
+            )),
+        }
+    }
+}
+
+/// A labeled, writable-overlay partition (Ubuntu's `casper-rw`, Debian's
+/// `persistence`) carved out after the data partition for live distros that
+/// support persistence.
+#[derive(Debug, Clone)]
+pub struct PersistenceLayout {
+    pub label: String,
+    pub size_mb: u64,
+}
+
+/// Data-partition and optional persistence-partition shape for `format`,
+/// assembled by `handle_format` from `AppConfig` defaults overridden by
+/// `--data-fs`/`--data-size-mb`/`--persistence`/`--persistence-size-mb`.
+#[derive(Debug, Clone)]
+pub struct PartitionLayout {
+    pub data_filesystem: DataFilesystem,
+    /// Size of the data partition in MB; `None` uses all remaining space.
+    pub data_size_mb: Option<u64>,
+    pub persistence: Option<PersistenceLayout>,
+}
+
+impl Default for PartitionLayout {
+    fn default() -> Self {
+        Self {
+            data_filesystem: DataFilesystem::Ext4,
+            data_size_mb: None,
+            persistence: None,
+        }
+    }
+}
+
+/// How large a `PartitionSpec` should be.
+#[derive(Debug, Clone, Copy)]
+pub enum PartitionSize {
+    /// An exact size in bytes.
+    Fixed(u64),
+    /// Everything left on the device after every earlier entry in the
+    /// layout; only meaningful on the last `PartitionSpec`.
+    Remaining,
+}
+
+/// One GPT entry for `PartitionManager` to create, in the order it should
+/// be laid out on disk.
+#[derive(Debug, Clone)]
+pub struct PartitionSpec {
+    pub name: String,
+    pub type_guid: String,
+    pub size: PartitionSize,
+    /// Filesystem to format this partition with, if the caller wants
+    /// `PartitionManager` to know; not all layouts format every partition
+    /// the same way (the ESP is always FAT32 regardless of this field).
+    pub filesystem: Option<DataFilesystem>,
+}
+
+/// The ordered partition table `PartitionManager` creates, replacing the
+/// old hardcoded ESP/boot/data trio with data the caller assembles: a
+/// preset (`standard`, `with_persistence`) or a fully custom (`manual`)
+/// partition list, analogous to an installer's automatic-EFI vs.
+/// manual-partitioning choice.
+#[derive(Debug, Clone)]
+pub struct PartitionTableLayout {
+    pub partitions: Vec<PartitionSpec>,
+}
+
+impl PartitionTableLayout {
+    /// The original fixed trio: ESP, boot, data, expressed as a layout.
+    /// Partition names double as GPT `PARTLABEL`s, set to match the
+    /// `USB_ESP`/`USB_BOOT`/`USB_DATA` filesystem labels `format_esp`/
+    /// `format_boot`/`format_data` already give each partition, so
+    /// `LABEL=`/`PARTLABEL=` resolve to the same partition either way.
+    pub fn standard(data_filesystem: DataFilesystem, data_size_mb: Option<u64>) -> Self {
+        Self {
+            partitions: vec![
+                PartitionSpec {
+                    name: "USB_ESP".to_string(),
+                    type_guid: ESP_TYPE_GUID.to_string(),
+                    size: PartitionSize::Fixed(ESP_SIZE),
+                    filesystem: None,
+                },
+                PartitionSpec {
+                    name: "USB_BOOT".to_string(),
+                    type_guid: LINUX_DATA_TYPE_GUID.to_string(),
+                    size: PartitionSize::Fixed(BOOT_SIZE),
+                    filesystem: None,
+                },
+                PartitionSpec {
+                    name: "USB_DATA".to_string(),
+                    type_guid: LINUX_DATA_TYPE_GUID.to_string(),
+                    size: data_size_mb.map(|mb| PartitionSize::Fixed(mb * 1024 * 1024)).unwrap_or(PartitionSize::Remaining),
+                    filesystem: Some(data_filesystem),
+                },
+            ],
+        }
+    }
+
+    /// `standard` plus a labeled persistence partition using all remaining
+    /// space after the data partition.
+    pub fn with_persistence(data_filesystem: DataFilesystem, data_size_mb: Option<u64>, persistence: &PersistenceLayout) -> Self {
+        let mut layout = Self::standard(data_filesystem, data_size_mb);
+        layout.partitions.push(PartitionSpec {
+            name: persistence.label.clone(),
+            type_guid: LINUX_DATA_TYPE_GUID.to_string(),
+            size: PartitionSize::Remaining,
+            filesystem: Some(DataFilesystem::Ext4),
+        });
+        layout
+    }
+
+    /// A caller-supplied partition list, for full manual control over
+    /// naming, ordering, and sizing.
+    pub fn manual(partitions: Vec<PartitionSpec>) -> Self {
+        Self { partitions }
+    }
+
+    /// `standard`, but with the boot partition duplicated into `USB_BOOT_A`/
+    /// `USB_BOOT_B` slots, always at partition numbers 2 and 3
+    /// (`BOOT_SLOT_A_PARTITION`/`BOOT_SLOT_B_PARTITION`) so
+    /// `PartitionManager::{get,set}_slot_attributes` and `Commands::Slot`
+    /// can address them without re-deriving which number is which. Laying
+    /// out this shape is as far as this goes for now: staging a new GRUB
+    /// configuration into the inactive slot at install/update time isn't
+    /// wired up yet, so callers format/populate each `USB_BOOT_*` partition
+    /// by hand before `slot activate` flips the GPT attributes that decide
+    /// which one boots. Not combinable with `--persistence`.
+    pub fn with_ab_boot(data_filesystem: DataFilesystem, data_size_mb: Option<u64>) -> Self {
+        Self {
+            partitions: vec![
+                PartitionSpec {
+                    name: "USB_ESP".to_string(),
+                    type_guid: ESP_TYPE_GUID.to_string(),
+                    size: PartitionSize::Fixed(ESP_SIZE),
+                    filesystem: None,
+                },
+                PartitionSpec {
+                    name: "USB_BOOT_A".to_string(),
+                    type_guid: LINUX_DATA_TYPE_GUID.to_string(),
+                    size: PartitionSize::Fixed(BOOT_SIZE),
+                    filesystem: None,
+                },
+                PartitionSpec {
+                    name: "USB_BOOT_B".to_string(),
+                    type_guid: LINUX_DATA_TYPE_GUID.to_string(),
+                    size: PartitionSize::Fixed(BOOT_SIZE),
+                    filesystem: None,
+                },
+                PartitionSpec {
+                    name: "USB_DATA".to_string(),
+                    type_guid: LINUX_DATA_TYPE_GUID.to_string(),
+                    size: data_size_mb.map(|mb| PartitionSize::Fixed(mb * 1024 * 1024)).unwrap_or(PartitionSize::Remaining),
+                    filesystem: Some(data_filesystem),
+                },
+            ],
+        }
+    }
+}
+
+/// The `USB_BOOT_A`/`USB_BOOT_B` partition numbers `with_ab_boot` lays out,
+/// and the ones `Commands::Slot` addresses by name ("a"/"b").
+pub const BOOT_SLOT_A_PARTITION: u32 = 2;
+pub const BOOT_SLOT_B_PARTITION: u32 = 3;
+
+/// A GPT entry's ChromeOS-style boot priority attributes, packed into the
+/// same `attribute_bits` field every other partition leaves at 0: bits
+/// 48-51 hold `priority` (0-15, highest wins), bits 52-55 hold
+/// `tries_remaining` (0-15, decremented by the bootloader each attempt),
+/// and bit 56 holds `successful` (latched once the booted slot confirms
+/// itself good). This is the same bit layout `cgpt`/`vboot_reference` use,
+/// so a GRUB script that already speaks ChromeOS-style slot selection
+/// understands it unmodified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BootSlotAttributes {
+    pub priority: u8,
+    pub tries_remaining: u8,
+    pub successful: bool,
+}
+
+const PRIORITY_OFFSET: u32 = 48;
+const PRIORITY_MASK: u64 = 0xf;
+const TRIES_OFFSET: u32 = 52;
+const TRIES_MASK: u64 = 0xf;
+const SUCCESSFUL_BIT: u32 = 56;
+
+impl BootSlotAttributes {
+    pub fn from_bits(bits: u64) -> Self {
+        Self {
+            priority: ((bits >> PRIORITY_OFFSET) & PRIORITY_MASK) as u8,
+            tries_remaining: ((bits >> TRIES_OFFSET) & TRIES_MASK) as u8,
+            successful: (bits >> SUCCESSFUL_BIT) & 1 == 1,
+        }
+    }
+
+    /// Packs back into a full `attribute_bits` value, preserving whatever
+    /// non-ChromeOS bits (e.g. the UEFI "required partition" bit 0) were
+    /// already set outside the 48-56 range this struct owns.
+    pub fn to_bits(self, existing_bits: u64) -> u64 {
+        let cleared = existing_bits & !((PRIORITY_MASK << PRIORITY_OFFSET) | (TRIES_MASK << TRIES_OFFSET) | (1 << SUCCESSFUL_BIT));
+        cleared
+            | ((self.priority as u64 & PRIORITY_MASK) << PRIORITY_OFFSET)
+            | ((self.tries_remaining as u64 & TRIES_MASK) << TRIES_OFFSET)
+            | ((self.successful as u64) << SUCCESSFUL_BIT)
+    }
+}
+
+/// One partition's byte range inside a disk image file, as returned by
+/// `PartitionManager::create_image`. Callers loop-mount `[start, end)`
+/// (`losetup --offset start --sizelimit end-start`) to get back an ordinary
+/// device path for the rest of the formatting/bootloader pipeline.
+#[derive(Debug, Clone, Copy)]
+pub struct PartitionByteRange {
+    pub start: u64,
+    pub end: u64,
+}
 
 pub struct PartitionManager {
     device_path: PathBuf,
+    layout: PartitionTableLayout,
 }
 
 impl PartitionManager {
-    pub fn new(device_path: &Path) -> Self {
+    pub fn new(device_path: &Path, layout: PartitionTableLayout) -> Self {
         Self {
             device_path: device_path.to_path_buf(),
+            layout,
         }
     }
-    
+
+    /// Overwrites the device with a fresh, empty GPT (protective MBR plus
+    /// primary/backup headers and entry arrays), destroying whatever
+    /// partition table was there before.
     pub fn wipe_partition_table(&self) -> Result<()> {
-        // Use sgdisk to zap all partition data
-        let output = Command::new("sgdisk")
-            .args(["--zap-all", self.device_path.to_str().unwrap()])
-            .output()
-            .map_err(|e| UsbBootHutError::Partition(format!("Failed to run sgdisk: {}", e)))?;
-            
-        if !output.status.success() {
-            return Err(UsbBootHutError::Partition(
-                format!("Failed to wipe partitions: {}", String::from_utf8_lossy(&output.stderr))
-            ));
-        }
-        
-        // Also clear MBR
-        let output = Command::new("dd")
-            .args([
-                "if=/dev/zero",
-                &format!("of={}", self.device_path.display()),
-                "bs=512",
-                "count=1",
-            ])
-            .output()
-            .map_err(|e| UsbBootHutError::Partition(format!("Failed to clear MBR: {}", e)))?;
-            
-        if !output.status.success() {
-            return Err(UsbBootHutError::Partition(
-                format!("Failed to clear MBR: {}", String::from_utf8_lossy(&output.stderr))
-            ));
-        }
-        
-        Ok(())
+        self.write_blank_gpt()
     }
-    
+
     pub fn create_gpt(&self) -> Result<()> {
-        let output = Command::new("sgdisk")
-            .args([
-                "--clear",
-                "--new=0:0:0", // Create new GPT
-                self.device_path.to_str().unwrap()
-            ])
-            .output()
-            .map_err(|e| UsbBootHutError::Partition(format!("Failed to create GPT: {}", e)))?;
-            
-        if !output.status.success() {
-            return Err(UsbBootHutError::Partition(
-                format!("Failed to create GPT: {}", String::from_utf8_lossy(&output.stderr))
-            ));
-        }
-        
-        // Clear any existing partitions
-        let output = Command::new("sgdisk")
-            .args(["--zap-all", self.device_path.to_str().unwrap()])
-            .output()
-            .map_err(|e| UsbBootHutError::Partition(format!("Failed to clear partitions: {}", e)))?;
-            
-        if !output.status.success() {
-            return Err(UsbBootHutError::Partition(
-                format!("Failed to clear partitions: {}", String::from_utf8_lossy(&output.stderr))
-            ));
-        }
-        
+        self.write_blank_gpt()
+    }
+
+    /// Creates every partition in `self.layout`, in order, and returns their
+    /// device paths in the same order. Each entry claims space right after
+    /// the one before it, so `Remaining` only makes sense as the last entry.
+    pub fn create_partitions(&self) -> Result<Vec<PathBuf>> {
+        self.layout.partitions.iter().enumerate()
+            .map(|(i, spec)| {
+                let number = (i + 1) as u32;
+                let size_bytes = match spec.size {
+                    PartitionSize::Fixed(bytes) => Some(bytes),
+                    PartitionSize::Remaining => None,
+                };
+                self.add_partition(number, &spec.name, &spec.type_guid, size_bytes)
+            })
+            .collect()
+    }
+
+    fn open_device(&self) -> Result<File> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.device_path)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to open {}: {}", self.device_path.display(), e)))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn sector_size(&self, file: &mut File) -> Result<u64> {
+        gptman::linux::get_sector_size(file)
+            .map(|s| s as u64)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to get sector size: {}", e)))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn sector_size(&self, _file: &mut File) -> Result<u64> {
+        Ok(512)
+    }
+
+    fn write_blank_gpt(&self) -> Result<()> {
+        let mut file = self.open_device()?;
+        let sector_size = self.sector_size(&mut file)?;
+        let disk_guid = *uuid::Uuid::new_v4().as_bytes();
+
+        let mut gpt = GPT::new_from(&mut file, sector_size, disk_guid)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to build GPT: {}", e)))?;
+
+        gpt.write_into(&mut file)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to write GPT: {}", e)))?;
+
+        file.sync_all()
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to sync: {}", e)))?;
+
         Ok(())
     }
-    
-    pub fn create_esp_partition(&self) -> Result<PathBuf> {
-        // Create ESP partition (partition 1)
-        let esp_size_mb = ESP_SIZE / (1024 * 1024);
-        
-        let output = Command::new("sgdisk")
-            .args([
-                &format!("--new=1:0:+{}M", esp_size_mb),
-                "--typecode=1:EF00", // EFI System Partition
-                "--change-name=1:EFI System Partition",
-                self.device_path.to_str().unwrap()
-            ])
-            .output()
-            .map_err(|e| UsbBootHutError::Partition(format!("Failed to create ESP: {}", e)))?;
-            
-        if !output.status.success() {
+
+    /// Reads the device's existing GPT, appends a `size_bytes`-long
+    /// partition (or, if `None`, one spanning all remaining space) right
+    /// after the highest partition already in use, aligned up to 1 MiB,
+    /// and writes the table back.
+    fn add_partition(&self, number: u32, name: &str, type_guid: &str, size_bytes: Option<u64>) -> Result<PathBuf> {
+        let mut file = self.open_device()?;
+        let sector_size = self.sector_size(&mut file)?;
+
+        let mut gpt = GPT::find_from(&mut file)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to read GPT: {}", e)))?;
+
+        let (starting_lba, ending_lba) = Self::next_partition_lbas(&gpt, sector_size, name, size_bytes)?;
+        let type_guid = uuid::Uuid::parse_str(type_guid)
+            .map_err(|e| UsbBootHutError::Partition(format!("Invalid partition type GUID: {}", e)))?;
+
+        gpt[number] = GPTPartitionEntry {
+            partition_type_guid: *type_guid.as_bytes(),
+            unique_partition_guid: *uuid::Uuid::new_v4().as_bytes(),
+            starting_lba,
+            ending_lba,
+            attribute_bits: 0,
+            partition_name: name.into(),
+        };
+
+        gpt.write_into(&mut file)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to write partition table: {}", e)))?;
+
+        self.refresh_partitions(&mut file)?;
+
+        Ok(self.get_partition_path(number))
+    }
+
+    /// Shared LBA math behind `add_partition` (real device, re-read GPT per
+    /// call) and `create_image` (in-memory GPT, one call per partition):
+    /// claims `size_bytes` (or everything left, if `None`) right after the
+    /// highest partition already in use, aligned up to 1 MiB.
+    fn next_partition_lbas(gpt: &GPT, sector_size: u64, name: &str, size_bytes: Option<u64>) -> Result<(u64, u64)> {
+        let alignment_lba = (PARTITION_ALIGNMENT_BYTES / sector_size).max(1);
+        let min_start_lba = gpt.iter()
+            .filter(|(_, entry)| entry.is_used())
+            .map(|(_, entry)| entry.ending_lba + 1)
+            .max()
+            .unwrap_or(gpt.header.first_usable_lba);
+        let starting_lba = ((min_start_lba + alignment_lba - 1) / alignment_lba) * alignment_lba;
+
+        let ending_lba = match size_bytes {
+            Some(bytes) => (starting_lba + bytes / sector_size - 1).min(gpt.header.last_usable_lba),
+            None => gpt.header.last_usable_lba,
+        };
+
+        if starting_lba > ending_lba {
             return Err(UsbBootHutError::Partition(
-                format!("Failed to create ESP: {}", String::from_utf8_lossy(&output.stderr))
+                format!("Not enough space left on device for '{}'", name)
             ));
         }
-        
-        // Return partition path
-        Ok(self.get_partition_path(1))
-    }
-    
-    pub fn create_boot_partition(&self) -> Result<PathBuf> {
-        // Create boot partition (partition 2)
-        let boot_size_mb = BOOT_SIZE / (1024 * 1024);
-        
-        let output = Command::new("sgdisk")
-            .args([
-                &format!("--new=2:0:+{}M", boot_size_mb),
-                "--typecode=2:8300", // Linux filesystem
-                "--change-name=2:Boot Partition",
-                self.device_path.to_str().unwrap()
-            ])
-            .output()
-            .map_err(|e| UsbBootHutError::Partition(format!("Failed to create boot partition: {}", e)))?;
-            
-        if !output.status.success() {
-            return Err(UsbBootHutError::Partition(
-                format!("Failed to create boot partition: {}", String::from_utf8_lossy(&output.stderr))
-            ));
+
+        Ok((starting_lba, ending_lba))
+    }
+
+    /// Lays down a fresh GPT plus every partition in `self.layout` directly
+    /// into `output`, an ordinary file rather than a block device: `output`
+    /// is first sized to `size_bytes` with `set_len` (the `truncate`
+    /// equivalent `gen_disk`-style image builders use), then the same
+    /// `gptman` types `add_partition` uses build the table in memory before
+    /// a single `write_into`. There's no `/dev/...N` device node for a
+    /// partition inside a plain file and nothing for the kernel to rescan,
+    /// so this returns each partition's byte range instead of a path --
+    /// callers loop-mount (`losetup --offset/--sizelimit`) each range to
+    /// reuse the rest of the formatting/bootloader pipeline unchanged.
+    pub fn create_image(&self, size_bytes: u64) -> Result<Vec<PartitionByteRange>> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.device_path)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to create image {}: {}", self.device_path.display(), e)))?;
+
+        file.set_len(size_bytes)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to size image {}: {}", self.device_path.display(), e)))?;
+
+        let sector_size = 512; // no real block device to query a sector size from
+        let disk_guid = *uuid::Uuid::new_v4().as_bytes();
+        let mut gpt = GPT::new_from(&mut file, sector_size, disk_guid)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to build GPT: {}", e)))?;
+
+        let mut ranges = Vec::with_capacity(self.layout.partitions.len());
+        for (i, spec) in self.layout.partitions.iter().enumerate() {
+            let number = (i + 1) as u32;
+            let size_bytes = match spec.size {
+                PartitionSize::Fixed(bytes) => Some(bytes),
+                PartitionSize::Remaining => None,
+            };
+            let (starting_lba, ending_lba) = Self::next_partition_lbas(&gpt, sector_size, &spec.name, size_bytes)?;
+            let type_guid = uuid::Uuid::parse_str(&spec.type_guid)
+                .map_err(|e| UsbBootHutError::Partition(format!("Invalid partition type GUID: {}", e)))?;
+
+            gpt[number] = GPTPartitionEntry {
+                partition_type_guid: *type_guid.as_bytes(),
+                unique_partition_guid: *uuid::Uuid::new_v4().as_bytes(),
+                starting_lba,
+                ending_lba,
+                attribute_bits: 0,
+                partition_name: spec.name.as_str().into(),
+            };
+
+            ranges.push(PartitionByteRange {
+                start: starting_lba * sector_size,
+                end: (ending_lba + 1) * sector_size,
+            });
         }
-        
-        Ok(self.get_partition_path(2))
-    }
-    
-    pub fn create_data_partition(&self) -> Result<PathBuf> {
-        // Create data partition (partition 3) using remaining space
-        let output = Command::new("sgdisk")
-            .args([
-                "--new=3:0:0", // Use all remaining space
-                "--typecode=3:8300", // Linux filesystem
-                "--change-name=3:Data Partition",
-                self.device_path.to_str().unwrap()
-            ])
-            .output()
-            .map_err(|e| UsbBootHutError::Partition(format!("Failed to create data partition: {}", e)))?;
-            
-        if !output.status.success() {
-            return Err(UsbBootHutError::Partition(
-                format!("Failed to create data partition: {}", String::from_utf8_lossy(&output.stderr))
-            ));
+
+        gpt.write_into(&mut file)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to write partition table: {}", e)))?;
+
+        Ok(ranges)
+    }
+
+    /// Reads partition `number`'s current boot-slot attributes straight out
+    /// of the on-disk GPT, so `Commands::Slot` can decide the other slot's
+    /// state before flipping either one.
+    pub fn get_slot_attributes(&self, number: u32) -> Result<BootSlotAttributes> {
+        let mut file = self.open_device()?;
+        let gpt = GPT::find_from(&mut file)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to read GPT: {}", e)))?;
+
+        let entry = gpt.iter().find(|(n, _)| *n == number)
+            .map(|(_, entry)| entry)
+            .ok_or_else(|| UsbBootHutError::Partition(format!("No partition #{} on {}", number, self.device_path.display())))?;
+
+        Ok(BootSlotAttributes::from_bits(entry.attribute_bits))
+    }
+
+    /// Writes `attrs` into partition `number`'s GPT attribute bits, leaving
+    /// every other field (and every other partition entry) untouched.
+    pub fn set_slot_attributes(&self, number: u32, attrs: BootSlotAttributes) -> Result<()> {
+        let mut file = self.open_device()?;
+        let mut gpt = GPT::find_from(&mut file)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to read GPT: {}", e)))?;
+
+        if !gpt[number].is_used() {
+            return Err(UsbBootHutError::Partition(format!("No partition #{} on {}", number, self.device_path.display())));
         }
-        
-        // Inform kernel of partition changes
-        self.refresh_partitions()?;
-        
-        Ok(self.get_partition_path(3))
-    }
-    
-    fn get_partition_path(&self, number: u32) -> PathBuf {
+
+        gpt[number].attribute_bits = attrs.to_bits(gpt[number].attribute_bits);
+
+        gpt.write_into(&mut file)
+            .map_err(|e| UsbBootHutError::Partition(format!("Failed to write partition table: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub fn get_partition_path(&self, number: u32) -> PathBuf {
         let device_str = self.device_path.to_str().unwrap();
-        
+
         // Handle different partition naming schemes
         if device_str.contains("nvme") || device_str.contains("mmcblk") {
             // NVMe and MMC devices use 'p' before partition number
@@ -160,37 +494,36 @@ impl PartitionManager {
             PathBuf::from(format!("{}{}", device_str, number))
         }
     }
-    
-    fn refresh_partitions(&self) -> Result<()> {
-        // Tell kernel to re-read partition table
-        let output = Command::new("partprobe")
-            .arg(self.device_path.to_str().unwrap())
-            .output()
-            .map_err(|e| UsbBootHutError::Partition(format!("Failed to run partprobe: {}", e)))?;
-            
-        if !output.status.success() {
-            // Try alternative method
-            let output = Command::new("blockdev")
-                .args(["--rereadpt", self.device_path.to_str().unwrap()])
-                .output()
-                .map_err(|e| UsbBootHutError::Partition(format!("Failed to refresh partitions: {}", e)))?;
-                
-            if !output.status.success() {
-                return Err(UsbBootHutError::Partition(
-                    "Failed to refresh partition table".to_string()
-                ));
-            }
+
+    /// Tells the kernel to re-read the partition table via `BLKRRPART`
+    /// instead of shelling out to `partprobe`/`blockdev --rereadpt`.
+    #[cfg(target_os = "linux")]
+    fn refresh_partitions(&self, file: &mut File) -> Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let rc = unsafe { libc::ioctl(file.as_raw_fd(), BLKRRPART, 0) };
+        if rc != 0 {
+            return Err(UsbBootHutError::Partition(format!(
+                "Failed to refresh partition table: {}", std::io::Error::last_os_error()
+            )));
         }
-        
-        // Give kernel a moment to update device nodes
+
+        // Give the kernel a moment to update device nodes
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn refresh_partitions(&self, _file: &mut File) -> Result<()> {
         std::thread::sleep(std::time::Duration::from_millis(500));
-        
         Ok(())
     }
-    
+
+    /// Verifies every partition in `self.layout` exists on disk, whatever
+    /// its length happens to be.
     pub fn verify_partitions(&self) -> Result<()> {
-        // Verify all expected partitions exist
-        for i in 1..=3 {
+        for i in 1..=self.layout.partitions.len() as u32 {
             let part_path = self.get_partition_path(i);
             if !part_path.exists() {
                 return Err(UsbBootHutError::Partition(
@@ -198,7 +531,7 @@ impl PartitionManager {
                 ));
             }
         }
-        
+
         Ok(())
     }
 }
\ No newline at end of file
@@ -23,33 +23,6 @@ pub const USB_SPINNER: &[&str] = &[
     " [โโโโโ   ] ",
 ];
 
-pub const ENCRYPTION_FRAMES: &[&str] = &[
-    " ๐ ENCRYPTING [โ         ] ",
-    " ๐ ENCRYPTING [โโ        ] ",
-    " ๐ ENCRYPTING [โโโ       ] ",
-    " ๐ ENCRYPTING [โโโโ      ] ",
-    " ๐ ENCRYPTING [โโโโโ     ] ",
-    " ๐ ENCRYPTING [โโโโโโ    ] ",
-    " ๐ ENCRYPTING [โโโโโโโ   ] ",
-    " ๐ ENCRYPTING [โโโโโโโโ  ] ",
-    " ๐ ENCRYPTING [โโโโโโโโโ ] ",
-    " ๐ ENCRYPTING [โโโโโโโโโโ] ",
-];
-
-pub const WIPE_ANIMATION: &[&str] = &[
-    " ๐งน WIPING [โโโโโโโโโโ] 0%  ",
-    " ๐งน WIPING [โโโโโโโโโโ] 10% ",
-    " ๐งน WIPING [โโโโโโโโโโ] 20% ",
-    " ๐งน WIPING [โโโโโโโโโโ] 30% ",
-    " ๐งน WIPING [โโโโโโโโโโ] 40% ",
-    " ๐งน WIPING [โโโโโโโโโโ] 50% ",
-    " ๐งน WIPING [โโโโโโโโโโ] 60% ",
-    " ๐งน WIPING [โโโโโโโโโโ] 70% ",
-    " ๐งน WIPING [โโโโโโโโโโ] 80% ",
-    " ๐งน WIPING [โโโโโโโโโโ] 90% ",
-    " ๐งน WIPING [โโโโโโโโโโ] 100%",
-];
-
 pub const SUCCESS_FRAMES: &[&str] = &[
     " โ ",
     " โ ",
@@ -97,24 +70,6 @@ impl AnimationPlayer {
         }
     }
     
-    pub fn play_encryption(&mut self, current_progress: u8) {
-        let frame_idx = (current_progress as usize * ENCRYPTION_FRAMES.len()) / 100;
-        let frame_idx = frame_idx.min(ENCRYPTION_FRAMES.len() - 1);
-        
-        self.term.clear_line().ok();
-        print!("\r{}", style(ENCRYPTION_FRAMES[frame_idx]).green());
-        self.term.flush().ok();
-    }
-    
-    pub fn play_wipe(&mut self, current_progress: u8) {
-        let frame_idx = (current_progress as usize * WIPE_ANIMATION.len()) / 100;
-        let frame_idx = frame_idx.min(WIPE_ANIMATION.len() - 1);
-        
-        self.term.clear_line().ok();
-        print!("\r{}", style(WIPE_ANIMATION[frame_idx]).red());
-        self.term.flush().ok();
-    }
-    
     pub fn show_success(&mut self, message: &str) {
         for frame in SUCCESS_FRAMES {
             self.term.clear_line().ok();
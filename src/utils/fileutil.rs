@@ -0,0 +1,65 @@
+use crate::Result;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+/// Permissions for atomically-written config/metadata files: owner
+/// read/write only, since these can describe the drive's partition and
+/// credential layout.
+#[cfg(unix)]
+const FILE_MODE: u32 = 0o600;
+
+/// Writes `bytes` to `path` without ever leaving a truncated file behind if
+/// the process is killed or the drive is yanked mid-write. The data is
+/// written to a temp file in the same directory (so the following rename
+/// stays on one filesystem), `fsync`'d, renamed over `path`, and the parent
+/// directory is then `fsync`'d so the rename itself survives a crash.
+pub fn atomic_write(path: &Path, bytes: &[u8]) -> Result<()> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent)?;
+
+    let tmp_path = temp_path_in(parent, path);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(FILE_MODE);
+
+    let mut tmp_file = options.open(&tmp_path)?;
+    tmp_file.write_all(bytes)?;
+    tmp_file.sync_all()?;
+    drop(tmp_file);
+
+    fs::rename(&tmp_path, path)?;
+    sync_dir(parent)?;
+
+    Ok(())
+}
+
+/// A temp file name that can't collide with another process writing the
+/// same target, without pulling in a random-name generator.
+fn temp_path_in(dir: &Path, target: &Path) -> PathBuf {
+    let filename = target.file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+
+    dir.join(format!(".{}.tmp-{}", filename, std::process::id()))
+}
+
+/// `fsync`s a directory so a preceding rename into it is durable.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<()> {
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// No directory-handle syncing on platforms where this isn't meaningful
+/// (e.g. Windows).
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<()> {
+    Ok(())
+}
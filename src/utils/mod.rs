@@ -0,0 +1,7 @@
+pub mod animations;
+pub mod progress;
+pub mod fileutil;
+
+pub use animations::*;
+pub use progress::*;
+pub use fileutil::*;